@@ -0,0 +1,1324 @@
+//! definition of VoiceVox openapi path section
+//!
+//! 各エンドポイントはバックエンドに依存せず,`VoiceVoxClient`を通して呼び出します.
+
+use crate::api_schema::{
+    self, AccentPhrase, EngineManifestRaw, MorphableTargetInfo, PresetId, SpeakerUuid, StyleId,
+    TryFromRawError, WordType,
+};
+use crate::audio::{self, ArchiveEntryError, ConnectOptions, Wav, WavError};
+use crate::cancel::CancellationToken;
+use crate::capabilities::Feature;
+use crate::endpoint::{Endpoint, EndpointRequest};
+use crate::kana::KanaError;
+use crate::transport::{HttpResponse, Transport};
+use crate::version::EngineVersion;
+
+pub use crate::client::VoiceVoxClient;
+pub use crate::error::{APIError, AccentPhrasesErrors};
+
+use base64::{engine, Engine};
+use serde::de::DeserializeOwned;
+use std::{collections::HashMap, convert::TryInto};
+
+pub type CoreVersion = Option<String>;
+
+/// `Endpoint`を実装したリクエスト構造体に`call`を実装する.
+macro_rules! impl_call {
+    ($($endpoint:ty),* $(,)?) => {
+        $(
+            impl $endpoint {
+                pub async fn call<T: Transport>(
+                    self,
+                    client: &VoiceVoxClient<T>,
+                ) -> Result<<Self as Endpoint>::Response, <Self as Endpoint>::Error> {
+                    client.send(self).await
+                }
+
+                /// `token`で中断できる`call`.
+                ///
+                /// 中断すると接続を閉じて`APIError::Cancelled`を返します.
+                pub async fn call_cancellable<T: Transport>(
+                    self,
+                    client: &VoiceVoxClient<T>,
+                    token: &CancellationToken,
+                ) -> Result<<Self as Endpoint>::Response, <Self as Endpoint>::Error> {
+                    client.send_cancellable(self, token).await
+                }
+
+                /// `call`と同じリクエストを同期的に送信する.
+                #[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+                pub fn call_blocking<T: crate::blocking::BlockingTransport>(
+                    self,
+                    client: &VoiceVoxClient<T>,
+                ) -> Result<<Self as Endpoint>::Response, <Self as Endpoint>::Error> {
+                    client.send_blocking(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_call!(
+    AudioQuery,
+    AudioQueryFromPreset,
+    AccentPhrases,
+    ValidateKana,
+    MoraData,
+    MoraLength,
+    MoraPitch,
+    Synthesis,
+    CancellableSynthesis,
+    MultiSynthesis,
+    SynthesisMorphing,
+    MorpableTargets,
+    ConnectWaves,
+    Singers,
+    SingerInfo,
+    SingFrameAudioQuery,
+    FrameSynthesis,
+    Presets,
+    AddPreset,
+    UpdatePreset,
+    DeletePreset,
+    Version,
+    CoreVersions,
+    Speakers,
+    SpeakerInfo,
+    SupportedDevices,
+    DownloadableLibraries,
+    InstalledLibraries,
+    InstallLibrary,
+    UninstallLibrary,
+    InitializeSpeaker,
+    IsInitializedSpeaker,
+    EngineManifest,
+    UserDict,
+    UserDictWord,
+    RewriteUserDictWord,
+    DeleteUserDictWord,
+    ImportUserDict,
+    Setting,
+    UpdateSetting,
+);
+
+/// base64を含むレスポンスボディを読み取りデコードする.
+fn ok_json_raw<R, T>(response: HttpResponse) -> Result<T, APIError>
+where
+    R: DeserializeOwned + TryInto<T, Error = TryFromRawError>,
+{
+    if response.status != 200 {
+        return Err(response.into_error());
+    }
+    response
+        .json::<R>()?
+        .try_into()
+        .map_err(|e: TryFromRawError| response.decode_error(e))
+}
+
+/// # 音声合成用のクエリを作成する
+///
+/// クエリの初期値を得ます。ここで得られたクエリはそのまま音声合成に利用できます。各値の意味はSchemasを参照してください。
+///
+#[derive(Debug, Clone)]
+pub struct AudioQuery {
+    pub text: String,
+    pub speaker: StyleId,
+    pub core_version: CoreVersion,
+}
+
+impl Endpoint for AudioQuery {
+    type Response = api_schema::AudioQuery;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::post("/audio_query")
+            .query("speaker", self.speaker)
+            .core_version(&self.core_version)
+            .query("text", &self.text))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+///
+/// # 音声合成用のクエリをプリセットを用いて作成する
+/// クエリの初期値を得ます。ここで得られたクエリはそのまま音声合成に利用できます。各値の意味は`Schemas`を参照してください。
+///
+///
+#[derive(Debug, Clone)]
+pub struct AudioQueryFromPreset {
+    pub text: String,
+    pub preset_id: PresetId,
+    pub core_version: CoreVersion,
+}
+
+impl Endpoint for AudioQueryFromPreset {
+    type Response = api_schema::AudioQuery;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::post("/audio_query_from_preset")
+            .query("preset_id", self.preset_id)
+            .core_version(&self.core_version)
+            .query("text", &self.text))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+/// # テキストからアクセント句を得る
+/// テキストからアクセント句を得ます。
+///
+/// is_kanaが`true`のとき、テキストは次のようなAquesTalkライクな記法に従う読み仮名として処理されます。デフォルトは`false`です。
+///
+/// * 全てのカナはカタカナで記述される
+/// * アクセント句は`/`または`、`で区切る。`、`で区切った場合に限り無音区間が挿入される。
+/// * カナの手前に`_`を入れるとそのカナは無声化される
+/// * アクセント位置を`'`で指定する。全てのアクセント句にはアクセント位置を1つ指定する必要がある。
+/// * アクセント句末に`？`(全角)を入れることにより疑問文の発音ができる。
+///
+#[derive(Debug, Clone)]
+pub struct AccentPhrases {
+    pub text: String,
+    pub speaker: StyleId,
+    pub is_kana: Option<bool>,
+    pub core_version: CoreVersion,
+}
+
+impl Endpoint for AccentPhrases {
+    type Response = Vec<AccentPhrase>;
+    type Error = AccentPhrasesErrors;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, AccentPhrasesErrors> {
+        Ok(EndpointRequest::post("/accent_phrases")
+            .query("speaker", self.speaker)
+            .core_version(&self.core_version)
+            .query_opt("is_kana", self.is_kana)
+            .query("text", &self.text))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, AccentPhrasesErrors> {
+        match response.status {
+            400 => Err(kana_error(&response, &self.text)),
+            _ => Ok(response.ok_json()?),
+        }
+    }
+}
+
+/// 読み仮名の解析に失敗したときに400で返すレスポンス.
+#[derive(serde::Deserialize)]
+struct ParseKanaDetail {
+    detail: api_schema::ParseKanaBadRequest,
+}
+
+fn kana_error(response: &HttpResponse, kana: &str) -> AccentPhrasesErrors {
+    let bad_request: ParseKanaDetail = match response.json() {
+        Ok(bad_request) => bad_request,
+        Err(e) => return e.into(),
+    };
+    match KanaError::from_bad_request(&bad_request.detail, kana) {
+        Some(e) => AccentPhrasesErrors::KanaParseError(e),
+        None => response
+            .decode_error(format!(
+                "unknown error_name {}",
+                bad_request.detail.error_name
+            ))
+            .into(),
+    }
+}
+
+/// テキストがAquesTalkライクな記法に従っているかを判定する.
+///
+/// 従っていない場合は`AccentPhrases`と同じ400のエラーを返します.
+/// `kana::parse`で同じ判定を手元で行えます.
+#[derive(Debug, Clone)]
+pub struct ValidateKana {
+    pub text: String,
+}
+
+impl Endpoint for ValidateKana {
+    type Response = bool;
+    type Error = AccentPhrasesErrors;
+    const SINCE: Option<EngineVersion> = Some(EngineVersion::new(0, 14, 0));
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, AccentPhrasesErrors> {
+        Ok(EndpointRequest::post("/validate_kana").query("text", &self.text))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, AccentPhrasesErrors> {
+        match response.status {
+            400 => Err(kana_error(&response, &self.text)),
+            _ => Ok(response.ok_json()?),
+        }
+    }
+}
+
+///アクセント句から音高を得る
+#[derive(Debug, Clone)]
+pub struct MoraData {
+    //in query
+    pub speaker: StyleId,
+    pub core_version: CoreVersion,
+    //in body
+    pub accent_phrases: Vec<AccentPhrase>,
+}
+
+impl Endpoint for MoraData {
+    type Response = Vec<AccentPhrase>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        EndpointRequest::post("/mora_data")
+            .query("speaker", self.speaker)
+            .core_version(&self.core_version)
+            .json(&self.accent_phrases)
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+/// # アクセント句から音素長を得る
+#[derive(Debug, Clone)]
+pub struct MoraLength {
+    // in query.
+    pub speaker: StyleId,
+    pub core_version: CoreVersion,
+    // in body.
+    pub accent_phrases: Vec<AccentPhrase>,
+}
+
+impl Endpoint for MoraLength {
+    type Response = Vec<AccentPhrase>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        EndpointRequest::post("/mora_length")
+            .query("speaker", self.speaker)
+            .core_version(&self.core_version)
+            .json(&self.accent_phrases)
+    }
+
+    fn features(&self) -> Vec<Feature> {
+        vec![Feature::AdjustPhonemeLength]
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+/// # アクセント句から音高を得る
+#[derive(Debug, Clone)]
+pub struct MoraPitch {
+    // in query.
+    pub speaker: StyleId,
+    pub core_version: CoreVersion,
+    // in body.
+    pub accent_phrases: Vec<AccentPhrase>,
+}
+
+impl Endpoint for MoraPitch {
+    type Response = Vec<AccentPhrase>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        EndpointRequest::post("/mora_pitch")
+            .query("speaker", self.speaker)
+            .core_version(&self.core_version)
+            .json(&self.accent_phrases)
+    }
+
+    fn features(&self) -> Vec<Feature> {
+        vec![Feature::AdjustMoraPitch]
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+/// # 音声合成する
+#[derive(Debug, Clone)]
+pub struct Synthesis {
+    // in query
+    pub speaker: StyleId,
+    pub enable_interrogative_upspeak: Option<bool>,
+    pub core_version: CoreVersion,
+    // in body json.
+    pub audio_query: api_schema::AudioQuery,
+}
+
+impl Endpoint for Synthesis {
+    type Response = Vec<u8>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        EndpointRequest::post("/synthesis")
+            .query("speaker", self.speaker)
+            .query_opt(
+                "enable_interrogative_upspeak",
+                self.enable_interrogative_upspeak,
+            )
+            .core_version(&self.core_version)
+            .json(&self.audio_query)
+    }
+
+    fn features(&self) -> Vec<Feature> {
+        let mut features = Feature::of_query(&self.audio_query);
+        if self.enable_interrogative_upspeak == Some(true) {
+            features.push(Feature::InterrogativeUpspeak);
+        }
+        features
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_bytes()
+    }
+}
+
+/// # 音声合成する（キャンセル可能）
+///
+/// `call_cancellable`で中断すると接続が閉じられ,エンジン側の合成も停止します.
+/// エンジンを`--enable_cancellable_synthesis`を付けて起動する必要があります.
+#[derive(Debug, Clone)]
+pub struct CancellableSynthesis {
+    // in query
+    pub speaker: StyleId,
+    pub core_version: CoreVersion,
+    // in body json.
+    pub audio_query: api_schema::AudioQuery,
+}
+
+impl Endpoint for CancellableSynthesis {
+    type Response = Vec<u8>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        EndpointRequest::post("/cancellable_synthesis")
+            .query("speaker", self.speaker)
+            .core_version(&self.core_version)
+            .json(&self.audio_query)
+    }
+
+    fn features(&self) -> Vec<Feature> {
+        Feature::of_query(&self.audio_query)
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_bytes()
+    }
+}
+
+/// # まとめて音声合成する
+///
+/// 複数のwavがzipでまとめられて返されます.
+#[derive(Debug, Clone)]
+pub struct MultiSynthesis {
+    // in query
+    pub speaker: StyleId,
+    pub core_version: CoreVersion,
+    // in body json.
+    pub audio_query: Vec<api_schema::AudioQuery>,
+}
+
+impl MultiSynthesis {
+    /// 合成した音声を`audio_query`と同じ順番で返す.
+    ///
+    /// 個々の音声が欠けていたり壊れていた場合はその要素だけがエラーになります.
+    pub async fn call_wavs<T: Transport>(
+        self,
+        client: &VoiceVoxClient<T>,
+    ) -> Result<Vec<Result<Wav, ArchiveEntryError>>, APIError> {
+        let count = self.audio_query.len();
+        unpack_wavs(&self.call(client).await?, count)
+    }
+
+    /// `call_wavs`と同じリクエストを同期的に送信する.
+    #[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+    pub fn call_wavs_blocking<T: crate::blocking::BlockingTransport>(
+        self,
+        client: &VoiceVoxClient<T>,
+    ) -> Result<Vec<Result<Wav, ArchiveEntryError>>, APIError> {
+        let count = self.audio_query.len();
+        unpack_wavs(&self.call_blocking(client)?, count)
+    }
+}
+
+fn unpack_wavs(zip: &[u8], count: usize) -> Result<Vec<Result<Wav, ArchiveEntryError>>, APIError> {
+    audio::unpack_multi_synthesis(zip, count).map_err(|e| APIError::InvalidArchive(e.to_string()))
+}
+
+impl Endpoint for MultiSynthesis {
+    type Response = Vec<u8>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        EndpointRequest::post("/multi_synthesis")
+            .query("speaker", self.speaker)
+            .core_version(&self.core_version)
+            .json(&self.audio_query)
+    }
+
+    fn features(&self) -> Vec<Feature> {
+        self.audio_query
+            .iter()
+            .flat_map(Feature::of_query)
+            .collect()
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_bytes()
+    }
+}
+
+/// # 2人の話者でモーフィングした音声を合成する
+///
+/// 指定された2人の話者で音声を合成、指定した割合でモーフィングした音声を得ます。 モーフィングの割合はmorph_rateで指定でき、0.0でベースの話者、1.0でターゲットの話者に近づきます。
+#[derive(Debug, Clone)]
+pub struct SynthesisMorphing {
+    // in query
+    pub base_speaker: StyleId,
+    pub target_speaker: StyleId,
+    pub morph_rate: f64,
+    pub core_version: CoreVersion,
+    // in body json.
+    pub audio_query: api_schema::AudioQuery,
+}
+
+impl Endpoint for SynthesisMorphing {
+    type Response = Vec<u8>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        EndpointRequest::post("/synthesis_morphing")
+            .query("base_speaker", self.base_speaker)
+            .query("target_speaker", self.target_speaker)
+            .query("morph_rate", self.morph_rate)
+            .core_version(&self.core_version)
+            .json(&self.audio_query)
+    }
+
+    fn features(&self) -> Vec<Feature> {
+        let mut features = vec![Feature::SynthesisMorphing];
+        features.extend(Feature::of_query(&self.audio_query));
+        features
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_bytes()
+    }
+}
+
+/// 指定されたベース話者に対してエンジン内の各話者がモーフィング機能を利用可能か返します。
+/// モーフィングの許可/禁止は/speakersのspeaker.supported_features.synthesis_morphingに記載されています。
+/// プロパティが存在しない場合は、モーフィングが許可されているとみなします。
+#[derive(Debug, Clone)]
+pub struct MorpableTargets {
+    // in request json.
+    pub style_id: Vec<StyleId>,
+    // in query
+    pub core_version: CoreVersion,
+}
+
+impl Endpoint for MorpableTargets {
+    type Response = Vec<HashMap<StyleId, MorphableTargetInfo>>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        EndpointRequest::post("/morphable_targets")
+            .core_version(&self.core_version)
+            .json(&self.style_id)
+    }
+
+    fn features(&self) -> Vec<Feature> {
+        vec![Feature::SynthesisMorphing]
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+/// # base64エンコードされた複数のwavデータを一つに結合する
+///
+/// base64エンコードされたwavデータを一纏めにし、wavファイルで返します。
+/// 送信するwavはbase64エンコードを行います.
+///
+#[derive(Debug, Clone)]
+pub struct ConnectWaves {
+    pub waves: Vec<Vec<u8>>,
+}
+
+impl ConnectWaves {
+    /// エンジンを使わずに結合する.
+    ///
+    /// 詳しくは`audio::connect`を参照してください.
+    pub fn connect_offline(&self, options: &ConnectOptions) -> Result<Vec<u8>, WavError> {
+        audio::connect_bytes(&self.waves, options)
+    }
+}
+
+impl Endpoint for ConnectWaves {
+    type Response = Vec<u8>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        let waves: Vec<String> = self
+            .waves
+            .iter()
+            .map(|wave| engine::general_purpose::STANDARD.encode(wave))
+            .collect();
+        EndpointRequest::post("/connect_waves").json(&waves)
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_bytes()
+    }
+}
+
+/// 歌手の一覧を得る.
+#[derive(Debug, Clone)]
+pub struct Singers {
+    pub core_version: CoreVersion,
+}
+
+impl Endpoint for Singers {
+    type Response = Vec<api_schema::Speaker>;
+    type Error = APIError;
+    const SINCE: Option<EngineVersion> = Some(EngineVersion::new(0, 16, 0));
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::get("/singers").core_version(&self.core_version))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+/// 歌手の詳細を得る.
+#[derive(Debug, Clone)]
+pub struct SingerInfo {
+    pub speaker_uuid: SpeakerUuid,
+    pub core_version: CoreVersion,
+}
+
+impl Endpoint for SingerInfo {
+    type Response = api_schema::SpeakerInfo;
+    type Error = APIError;
+    const SINCE: Option<EngineVersion> = Some(EngineVersion::new(0, 16, 0));
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::get("/singer_info")
+            .query("speaker_uuid", &self.speaker_uuid)
+            .core_version(&self.core_version))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        ok_json_raw::<api_schema::SpeakerInfoRaw, _>(response)
+    }
+}
+
+/// 楽譜から歌唱合成用のクエリを作成する.
+///
+/// `speaker`には歌唱指導用のスタイルを指定します.
+#[derive(Debug, Clone)]
+pub struct SingFrameAudioQuery {
+    pub speaker: StyleId,
+    pub core_version: CoreVersion,
+    pub score: api_schema::Score,
+}
+
+impl Endpoint for SingFrameAudioQuery {
+    type Response = api_schema::FrameAudioQuery;
+    type Error = APIError;
+    const SINCE: Option<EngineVersion> = Some(EngineVersion::new(0, 16, 0));
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        EndpointRequest::post("/sing_frame_audio_query")
+            .query("speaker", self.speaker)
+            .core_version(&self.core_version)
+            .json(&self.score)
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+/// 歌唱合成用のクエリから音声を合成する.
+#[derive(Debug, Clone)]
+pub struct FrameSynthesis {
+    pub speaker: StyleId,
+    pub core_version: CoreVersion,
+    pub frame_audio_query: api_schema::FrameAudioQuery,
+}
+
+impl Endpoint for FrameSynthesis {
+    type Response = Vec<u8>;
+    type Error = APIError;
+    const SINCE: Option<EngineVersion> = Some(EngineVersion::new(0, 16, 0));
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        EndpointRequest::post("/frame_synthesis")
+            .query("speaker", self.speaker)
+            .core_version(&self.core_version)
+            .json(&self.frame_audio_query)
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_bytes()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Presets;
+
+impl Endpoint for Presets {
+    type Response = Vec<api_schema::Preset>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::get("/presets"))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+///新しいプリセットを追加します
+///
+/// # Parameters
+///
+/// preset: Preset 新しいプリセット。 プリセットIDが既存のものと重複している場合は、新規のプリセットIDが採番されます。
+///
+/// # Returns
+///
+/// id: int 追加したプリセットのプリセットID
+#[derive(Debug, Clone)]
+pub struct AddPreset {
+    pub preset: api_schema::Preset,
+}
+
+impl Endpoint for AddPreset {
+    type Response = PresetId;
+    type Error = APIError;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        EndpointRequest::post("/add_preset").json(&self.preset)
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+///既存のプリセットを更新します
+/// # Parameters
+///
+/// preset: Preset 更新するプリセット。 プリセットIDが更新対象と一致している必要があります。
+///
+/// # Returns
+///
+/// id: int 更新したプリセットのプリセットID
+#[derive(Debug, Clone)]
+pub struct UpdatePreset {
+    pub preset: api_schema::Preset,
+}
+
+impl Endpoint for UpdatePreset {
+    type Response = PresetId;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        EndpointRequest::post("/update_preset").json(&self.preset)
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+///既存のプリセットを削除します
+///
+///Parameters
+///
+///id: int 削除するプリセットのプリセットID
+///
+#[derive(Debug, Clone)]
+pub struct DeletePreset {
+    pub preset_id: PresetId,
+}
+
+impl Endpoint for DeletePreset {
+    type Response = ();
+    type Error = APIError;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::post("/delete_preset").query("id", self.preset_id))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.no_content()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Version;
+
+impl Endpoint for Version {
+    type Response = Option<String>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::get("/version"))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+impl Version {
+    /// バージョンを取得して`EngineVersion`として読み取る.
+    pub async fn detect<T: Transport>(
+        client: &VoiceVoxClient<T>,
+    ) -> Result<EngineVersion, APIError> {
+        parse_version(Version.call(client).await?)
+    }
+
+    /// `detect`と同じリクエストを同期的に送信する.
+    #[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+    pub fn detect_blocking<T: crate::blocking::BlockingTransport>(
+        client: &VoiceVoxClient<T>,
+    ) -> Result<EngineVersion, APIError> {
+        parse_version(Version.call_blocking(client)?)
+    }
+}
+
+fn parse_version(version: Option<String>) -> Result<EngineVersion, APIError> {
+    let version = version.unwrap_or_default();
+    EngineVersion::parse(&version).ok_or_else(|| APIError::Decode {
+        status: 200,
+        body: version.clone(),
+        message: format!("invalid engine version {version:?}"),
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct CoreVersions;
+
+impl Endpoint for CoreVersions {
+    type Response = Vec<String>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::get("/core_versions"))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Speakers {
+    pub core_version: CoreVersion,
+}
+
+impl Endpoint for Speakers {
+    type Response = Vec<api_schema::Speaker>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::get("/speakers").core_version(&self.core_version))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpeakerInfo {
+    pub speaker_uuid: SpeakerUuid,
+    pub core_version: CoreVersion,
+}
+
+impl Endpoint for SpeakerInfo {
+    type Response = api_schema::SpeakerInfo;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::get("/speaker_info")
+            .query("speaker_uuid", &self.speaker_uuid)
+            .core_version(&self.core_version))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        ok_json_raw::<api_schema::SpeakerInfoRaw, _>(response)
+    }
+}
+
+/// エンジンが使えるデバイスを得る.
+///
+/// 使うデバイスはエンジンの起動引数で決まります. 切り替え方は`api_schema::Device::engine_args`を参照してください.
+#[derive(Debug, Clone)]
+pub struct SupportedDevices {
+    pub core_version: CoreVersion,
+}
+
+impl Endpoint for SupportedDevices {
+    type Response = api_schema::SupportedDevices;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::get("/supported_devices").core_version(&self.core_version))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+/// ダウンロードできる音声ライブラリの一覧を得る.
+///
+/// `download_url`から取得したアーカイブを`InstallLibrary`でインストールできます.
+#[derive(Debug, Clone)]
+pub struct DownloadableLibraries;
+
+impl Endpoint for DownloadableLibraries {
+    type Response = Vec<api_schema::DownloadableLibraries>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::get("/downloadable_libraries"))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        ok_json_raw::<api_schema::DownloadableLibrariesListRaw, _>(response)
+    }
+}
+
+/// インストール済みの音声ライブラリをUUIDをキーにして得る.
+#[derive(Debug, Clone)]
+pub struct InstalledLibraries;
+
+impl Endpoint for InstalledLibraries {
+    type Response = HashMap<String, api_schema::InstalledLibrary>;
+    type Error = APIError;
+    const SINCE: Option<EngineVersion> = Some(EngineVersion::new(0, 15, 0));
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::get("/installed_libraries"))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        ok_json_raw::<api_schema::InstalledLibrariesRaw, _>(response)
+    }
+}
+
+/// 音声ライブラリをインストールする.
+///
+/// `library`には`download_url`から取得したアーカイブをそのまま渡します.
+#[derive(Debug, Clone)]
+pub struct InstallLibrary {
+    pub library_uuid: String,
+    pub library: Vec<u8>,
+}
+
+impl Endpoint for InstallLibrary {
+    type Response = ();
+    type Error = APIError;
+    const SINCE: Option<EngineVersion> = Some(EngineVersion::new(0, 15, 0));
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(
+            EndpointRequest::post(format!("/install_library/{}", self.library_uuid))
+                .bytes(self.library.clone(), "application/octet-stream"),
+        )
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.no_content()
+    }
+}
+
+/// 音声ライブラリをアンインストールする.
+#[derive(Debug, Clone)]
+pub struct UninstallLibrary {
+    pub library_uuid: String,
+}
+
+impl Endpoint for UninstallLibrary {
+    type Response = ();
+    type Error = APIError;
+    const SINCE: Option<EngineVersion> = Some(EngineVersion::new(0, 15, 0));
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::post(format!(
+            "/uninstall_library/{}",
+            self.library_uuid
+        )))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.no_content()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InitializeSpeaker {
+    pub speaker: StyleId,
+    pub core_version: CoreVersion,
+}
+
+impl Endpoint for InitializeSpeaker {
+    type Response = ();
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::post("/initialize_speaker")
+            .query("speaker", self.speaker)
+            .core_version(&self.core_version))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.no_content()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IsInitializedSpeaker {
+    pub speaker: StyleId,
+    pub core_version: CoreVersion,
+}
+
+impl Endpoint for IsInitializedSpeaker {
+    type Response = bool;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::get("/is_initialized_speaker")
+            .query("speaker", self.speaker)
+            .core_version(&self.core_version))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EngineManifest;
+
+impl Endpoint for EngineManifest {
+    type Response = api_schema::EngineManifest;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::get("/engine_manifest"))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        ok_json_raw::<EngineManifestRaw, _>(response)
+    }
+}
+
+/// Get registered word list from user dictionary.
+///
+/// This result contains word UUID and definition.
+///
+#[derive(Debug, Clone)]
+pub struct UserDict;
+
+impl Endpoint for UserDict {
+    type Response = HashMap<String, api_schema::UserDictWord>;
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::get("/user_dict"))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+/// Add word to user dictionary.
+///
+/// This result returns word UUID.
+///
+#[derive(Debug, Clone)]
+pub struct UserDictWord {
+    ///言葉の表層形
+    pub surface: String,
+    ///言葉の発音(カタカナ)
+    pub pronunciation: String,
+    /// アクセント型(音が下がる場所)
+    pub accent_type: i32,
+    pub word_type: Option<WordType>,
+    ///単語の優先度
+    pub priority: Option<i32>,
+}
+
+impl Endpoint for UserDictWord {
+    type Response = String;
+    type Error = APIError;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::post("/user_dict_word")
+            .query("surface", &self.surface)
+            .query("pronunciation", &self.pronunciation)
+            .query("accent_type", self.accent_type)
+            .query_opt("priority", self.priority)
+            .query_opt("word_type", self.word_type.as_ref()))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.ok_json()
+    }
+}
+
+impl UserDictWord {
+    /// `uuid`の単語をこの内容に書き換えるリクエストにする.
+    pub fn rewrite(self, uuid: impl Into<String>) -> RewriteUserDictWord {
+        RewriteUserDictWord {
+            uuid: uuid.into(),
+            surface: self.surface,
+            pronunciation: self.pronunciation,
+            accent_type: self.accent_type,
+            word_type: self.word_type,
+            priority: self.priority,
+        }
+    }
+}
+
+/// 登録済みの単語と同じ内容で追加するリクエストを作る.
+///
+/// 品詞が`WordType`のどれにも当てはまらない単語は`word_type`が`None`になります.
+impl From<&api_schema::UserDictWord> for UserDictWord {
+    fn from(word: &api_schema::UserDictWord) -> Self {
+        Self {
+            surface: word.surface.clone(),
+            pronunciation: word.pronunciation.clone(),
+            accent_type: word.accent_type,
+            word_type: word.word_type(),
+            priority: Some(word.priority),
+        }
+    }
+}
+
+impl From<api_schema::UserDictWord> for UserDictWord {
+    fn from(word: api_schema::UserDictWord) -> Self {
+        Self::from(&word)
+    }
+}
+
+/// エンジンが登録するのと同じ内容の単語を作る.
+///
+/// 省略された項目はエンジンの既定値になります.
+impl From<UserDictWord> for api_schema::UserDictWord {
+    fn from(word: UserDictWord) -> Self {
+        api_schema::UserDictWord::builder(word.surface, word.pronunciation, word.accent_type)
+            .word_type(word.word_type.unwrap_or_default())
+            .priority(
+                word.priority
+                    .unwrap_or(api_schema::UserDictWord::DEFAULT_PRIORITY),
+            )
+            .build()
+    }
+}
+
+/// rewrite word on user dictionary.
+///
+#[derive(Debug, Clone)]
+pub struct RewriteUserDictWord {
+    /// word uuid
+    pub uuid: String,
+    ///言葉の表層形
+    pub surface: String,
+    ///言葉の発音(カタカナ)
+    pub pronunciation: String,
+    /// アクセント型(音が下がる場所)
+    pub accent_type: i32,
+    pub word_type: Option<WordType>,
+    ///単語の優先度
+    pub priority: Option<i32>,
+}
+
+impl Endpoint for RewriteUserDictWord {
+    type Response = ();
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(
+            EndpointRequest::put(format!("/user_dict_word/{}", self.uuid))
+                .query("surface", &self.surface)
+                .query("pronunciation", &self.pronunciation)
+                .query("accent_type", self.accent_type)
+                .query_opt("priority", self.priority)
+                .query_opt("word_type", self.word_type.as_ref()),
+        )
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.no_content()
+    }
+}
+
+impl From<RewriteUserDictWord> for UserDictWord {
+    fn from(word: RewriteUserDictWord) -> Self {
+        Self {
+            surface: word.surface,
+            pronunciation: word.pronunciation,
+            accent_type: word.accent_type,
+            word_type: word.word_type,
+            priority: word.priority,
+        }
+    }
+}
+
+impl From<RewriteUserDictWord> for api_schema::UserDictWord {
+    fn from(word: RewriteUserDictWord) -> Self {
+        UserDictWord::from(word).into()
+    }
+}
+
+/// delete word from user dictionary.
+///
+///
+#[derive(Debug, Clone)]
+pub struct DeleteUserDictWord {
+    /// word uuid
+    pub uuid: String,
+}
+
+impl Endpoint for DeleteUserDictWord {
+    type Response = ();
+    type Error = APIError;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::delete(format!(
+            "/user_dict_word/{}",
+            self.uuid
+        )))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.no_content()
+    }
+}
+
+/// import user dictionary.
+///
+#[derive(Debug, Clone)]
+pub struct ImportUserDict {
+    /// 同じUUIDの単語が既にあるとき,読み込む単語で上書きする.
+    pub over_ride: bool,
+    pub dictionary: HashMap<String, api_schema::UserDictWord>,
+}
+
+impl Endpoint for ImportUserDict {
+    type Response = ();
+    type Error = APIError;
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        EndpointRequest::post("/import_user_dict")
+            .query("override", self.over_ride)
+            .json(&self.dictionary)
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.no_content()
+    }
+}
+
+/// エンジンの設定ページを得る.
+///
+/// エンジンは設定をHTMLのページとしてだけ返すので,レスポンスはHTMLの文字列です.
+#[derive(Debug, Clone)]
+pub struct Setting;
+
+impl Endpoint for Setting {
+    type Response = String;
+    type Error = APIError;
+    const SINCE: Option<EngineVersion> = Some(EngineVersion::new(0, 14, 0));
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        Ok(EndpointRequest::get("/setting"))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        match response.status {
+            200 => Ok(response.text()),
+            _ => Err(response.into_error()),
+        }
+    }
+}
+
+/// エンジンのCORSの設定を変更する.
+#[derive(Debug, Clone)]
+pub struct UpdateSetting {
+    pub setting: api_schema::Setting,
+}
+
+impl Endpoint for UpdateSetting {
+    type Response = ();
+    type Error = APIError;
+    const SINCE: Option<EngineVersion> = Some(EngineVersion::new(0, 14, 0));
+    const IDEMPOTENT: bool = true;
+
+    fn request(&self) -> Result<EndpointRequest, APIError> {
+        let mut form = vec![("cors_policy_mode", self.setting.cors_policy_mode.as_str())];
+        if let Some(allow_origin) = &self.setting.allow_origin {
+            form.push(("allow_origin", allow_origin));
+        }
+        Ok(EndpointRequest::post("/setting").form(form))
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, APIError> {
+        response.no_content()
+    }
+}
@@ -5,6 +5,353 @@ use base64::{engine, Engine};
 use serde::{Deserialize, Serialize};
 use std::convert::{TryFrom, TryInto};
 
+/// 新しいエンジンが追加した,このクレートが知らないフィールド.
+///
+/// 読み込んだ値はそのまま保持し,シリアライズすると元のフィールドとして書き出します.
+pub type ExtraFields = serde_json::Map<String, serde_json::Value>;
+
+/// スタイルID.APIの`speaker`や`style_id`,`StyleInfo.id`に使われる値.
+#[derive(
+    Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
+#[serde(transparent)]
+pub struct StyleId(pub i32);
+
+impl From<i32> for StyleId {
+    fn from(id: i32) -> Self {
+        Self(id)
+    }
+}
+
+impl From<StyleId> for i32 {
+    fn from(id: StyleId) -> Self {
+        id.0
+    }
+}
+
+impl std::fmt::Display for StyleId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// 話者のUUID.`SpeakerInfo`の取得や`Preset.speaker_uuid`に使われる値.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[serde(transparent)]
+pub struct SpeakerUuid(pub String);
+
+impl SpeakerUuid {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SpeakerUuid {
+    fn from(uuid: String) -> Self {
+        Self(uuid)
+    }
+}
+
+impl From<&str> for SpeakerUuid {
+    fn from(uuid: &str) -> Self {
+        Self(uuid.to_owned())
+    }
+}
+
+impl From<SpeakerUuid> for String {
+    fn from(uuid: SpeakerUuid) -> Self {
+        uuid.0
+    }
+}
+
+impl AsRef<str> for SpeakerUuid {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for SpeakerUuid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// プリセットのID.`Preset.id`と`AudioQueryFromPreset.preset_id`に使われる値.
+#[derive(
+    Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
+#[serde(transparent)]
+pub struct PresetId(pub i32);
+
+impl From<i32> for PresetId {
+    fn from(id: i32) -> Self {
+        Self(id)
+    }
+}
+
+impl From<PresetId> for i32 {
+    fn from(id: PresetId) -> Self {
+        id.0
+    }
+}
+
+impl std::fmt::Display for PresetId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+macro_rules! phonemes {
+    ($($(#[$doc:meta])* $variant:ident => $name:literal,)*) => {
+        /// エンジンの音素.
+        ///
+        /// エンジンと同じ文字列(`"pau"`,`"N"`,`"cl"`,無声化した母音`"A"`など)でシリアライズされます.
+        /// 知らない音素は`Unknown`になります.
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub enum Phoneme {
+            $($(#[$doc])* $variant,)*
+            /// 新しいエンジンが追加した音素.
+            Unknown(String),
+        }
+
+        impl Phoneme {
+            /// エンジンが使う全ての音素.
+            pub const ALL: &'static [Phoneme] = &[$(Phoneme::$variant,)*];
+
+            /// エンジンでの表記.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Phoneme::$variant => $name,)*
+                    Phoneme::Unknown(phoneme) => phoneme,
+                }
+            }
+        }
+
+        impl From<String> for Phoneme {
+            fn from(phoneme: String) -> Self {
+                match phoneme.as_str() {
+                    $($name => Phoneme::$variant,)*
+                    _ => Phoneme::Unknown(phoneme),
+                }
+            }
+        }
+
+        /// `Phoneme::ALL`のどれでもない文字列はエラーになります.
+        impl std::str::FromStr for Phoneme {
+            type Err = UnknownPhoneme;
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($name => Ok(Phoneme::$variant),)*
+                    _ => Err(UnknownPhoneme(s.to_owned())),
+                }
+            }
+        }
+    };
+}
+
+/// エンジンが文字列で表す列挙型を定義する.
+///
+/// 各値とエンジンでの表記を並べると,知らない文字列を持つ`Unknown(String)`を加えて,
+/// `as_str`と`From<String>`,同じ文字列でのシリアライズを実装する.
+macro_rules! string_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident => $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            /// 新しいエンジンが追加した値.
+            Unknown(String),
+        }
+
+        impl $name {
+            /// エンジンでの表記.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(value) => value,
+                }
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                match value.as_str() {
+                    $($value => $name::$variant,)*
+                    _ => $name::Unknown(value),
+                }
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                String::deserialize(deserializer).map($name::from)
+            }
+        }
+    };
+}
+
+phonemes! {
+    /// 無音.
+    Pau => "pau",
+    /// 無声化した`a`.
+    DevoicedA => "A",
+    /// 無声化した`e`.
+    DevoicedE => "E",
+    /// 無声化した`i`.
+    DevoicedI => "I",
+    /// 撥音(ン).子音の`n`は`N`ではなく[`Phoneme::N`]です.
+    MoraicN => "N",
+    /// 無声化した`o`.
+    DevoicedO => "O",
+    /// 無声化した`u`.
+    DevoicedU => "U",
+    A => "a",
+    B => "b",
+    By => "by",
+    Ch => "ch",
+    /// 促音(ッ).
+    Cl => "cl",
+    D => "d",
+    Dy => "dy",
+    E => "e",
+    F => "f",
+    G => "g",
+    Gw => "gw",
+    Gy => "gy",
+    H => "h",
+    Hy => "hy",
+    I => "i",
+    J => "j",
+    K => "k",
+    Kw => "kw",
+    Ky => "ky",
+    M => "m",
+    My => "my",
+    /// 子音の`n`.
+    N => "n",
+    Ny => "ny",
+    O => "o",
+    P => "p",
+    Py => "py",
+    R => "r",
+    Ry => "ry",
+    S => "s",
+    Sh => "sh",
+    T => "t",
+    Ts => "ts",
+    Ty => "ty",
+    U => "u",
+    V => "v",
+    W => "w",
+    Y => "y",
+    Z => "z",
+}
+
+impl Phoneme {
+    /// モーラの母音になる音素.有声と無声の母音と`N`,`cl`,`pau`です.
+    pub fn is_vowel(&self) -> bool {
+        self.is_voiced_vowel()
+            || self.is_devoiced()
+            || matches!(self, Phoneme::MoraicN | Phoneme::Cl | Phoneme::Pau)
+    }
+
+    /// 有声の母音(`a`,`i`,`u`,`e`,`o`).
+    pub fn is_voiced_vowel(&self) -> bool {
+        matches!(
+            self,
+            Phoneme::A | Phoneme::I | Phoneme::U | Phoneme::E | Phoneme::O
+        )
+    }
+
+    /// 無声化した母音(`A`,`I`,`U`,`E`,`O`).
+    pub fn is_devoiced(&self) -> bool {
+        matches!(
+            self,
+            Phoneme::DevoicedA
+                | Phoneme::DevoicedI
+                | Phoneme::DevoicedU
+                | Phoneme::DevoicedE
+                | Phoneme::DevoicedO
+        )
+    }
+
+    /// 無声化した母音.有声の母音以外は`None`.
+    pub fn devoiced(&self) -> Option<Phoneme> {
+        Some(match self {
+            Phoneme::A => Phoneme::DevoicedA,
+            Phoneme::I => Phoneme::DevoicedI,
+            Phoneme::U => Phoneme::DevoicedU,
+            Phoneme::E => Phoneme::DevoicedE,
+            Phoneme::O => Phoneme::DevoicedO,
+            _ => return None,
+        })
+    }
+
+    /// 無声化した母音を有声に戻す.それ以外はそのまま.
+    pub fn voiced(&self) -> Phoneme {
+        match self {
+            Phoneme::DevoicedA => Phoneme::A,
+            Phoneme::DevoicedI => Phoneme::I,
+            Phoneme::DevoicedU => Phoneme::U,
+            Phoneme::DevoicedE => Phoneme::E,
+            Phoneme::DevoicedO => Phoneme::O,
+            other => other.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for Phoneme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Phoneme {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Phoneme {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(Phoneme::from)
+    }
+}
+
+/// エンジンにない音素.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownPhoneme(pub String);
+
+impl std::fmt::Display for UnknownPhoneme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown phoneme `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownPhoneme {}
+
 /// this is Used in all around.
 ///
 ///
@@ -21,6 +368,9 @@ pub struct AudioQuery {
     pub outputSamplingRate: i32,
     pub outputStereo: bool,
     pub kana: Option<String>,
+    /// 新しいエンジンのクエリの項目(`pauseLength`など).合成するときにそのまま送り返します.
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 impl Default for AudioQuery {
@@ -36,6 +386,7 @@ impl Default for AudioQuery {
             outputSamplingRate: 24000,
             outputStereo: Default::default(),
             kana: None,
+            extra: ExtraFields::new(),
         }
     }
 }
@@ -96,6 +447,7 @@ impl From<AudioQueryInProject> for AudioQuery {
             outputSamplingRate: aq.outputSamplingRate,
             outputStereo: aq.outputStereo,
             kana: Some(aq.kana),
+            extra: ExtraFields::new(),
         }
     }
 }
@@ -144,9 +496,9 @@ impl From<AccentPhraseInProject> for AccentPhrase {
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct Mora {
     pub text: String,
-    pub consonant: Option<String>,
+    pub consonant: Option<Phoneme>,
     pub consonant_length: Option<f64>,
-    pub vowel: String,
+    pub vowel: Phoneme,
     pub vowel_length: f64,
     pub pitch: f64,
 }
@@ -157,11 +509,11 @@ pub struct MoraInProject {
     pub text: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
-    pub consonant: Option<String>,
+    pub consonant: Option<Phoneme>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
     pub consonantLength: Option<f64>,
-    pub vowel: String,
+    pub vowel: Phoneme,
     pub vowelLength: f64,
     pub pitch: f64,
 }
@@ -183,21 +535,192 @@ impl From<MoraInProject> for Mora {
     fn from(mora: MoraInProject) -> Self {
         Self {
             text: mora.text.clone(),
-            consonant: mora.consonant.clone(),
+            consonant: mora.consonant,
             consonant_length: mora.consonantLength,
-            vowel: mora.vowel.clone(),
+            vowel: mora.vowel,
             vowel_length: mora.vowelLength,
             pitch: mora.pitch,
         }
     }
 }
 
+/// 歌唱合成に使う楽譜.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+pub struct Score {
+    pub notes: Vec<Note>,
+}
+
+/// 楽譜の1つの音符.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Note {
+    /// 音符のID.`FramePhoneme.note_id`に引き継がれる.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// MIDIのノート番号.休符では`None`.
+    pub key: Option<i32>,
+    /// フレーム数.
+    pub frame_length: i32,
+    /// 1モーラ分の歌詞.休符では空文字列.
+    pub lyric: String,
+}
+
+impl Note {
+    /// 休符.
+    pub fn rest(frame_length: i32) -> Self {
+        Self {
+            id: None,
+            key: None,
+            frame_length,
+            lyric: String::new(),
+        }
+    }
+}
+
+/// フレームごとの音高と音量を持つ歌唱合成用のクエリ.
+///
+/// フレームの長さは`1 / FRAME_RATE`秒です.
+#[allow(non_snake_case)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct FrameAudioQuery {
+    /// フレームごとの基本周波数(Hz).無声では0.
+    pub f0: Vec<f64>,
+    /// フレームごとの音量.
+    pub volume: Vec<f64>,
+    pub phonemes: Vec<FramePhoneme>,
+    pub volumeScale: f64,
+    pub outputSamplingRate: i32,
+    pub outputStereo: bool,
+}
+
+/// 音素と長さ.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct FramePhoneme {
+    pub phoneme: Phoneme,
+    /// フレーム数.
+    pub frame_length: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note_id: Option<String>,
+}
+
+impl FramePhoneme {
+    /// 長さ(秒).
+    pub fn seconds(&self) -> f64 {
+        self.frame_length as f64 / FrameAudioQuery::FRAME_RATE
+    }
+}
+
+impl FrameAudioQuery {
+    /// 1秒あたりのフレーム数.
+    pub const FRAME_RATE: f64 = 93.75;
+
+    /// 音素をモーラにまとめる.
+    ///
+    /// 長さは秒に,音高は母音のフレームの基本周波数の平均の自然対数に変換します.
+    /// 無声のモーラと無音の音高は0で,`text`は子音と母音から引いたカタカナになります.
+    pub fn moras(&self) -> Vec<Mora> {
+        let mut moras = Vec::new();
+        let mut consonant: Option<&FramePhoneme> = None;
+        let mut frame = 0;
+        for phoneme in &self.phonemes {
+            let start = frame;
+            frame += phoneme.frame_length.max(0) as usize;
+            if !phoneme.phoneme.is_vowel() {
+                consonant = Some(phoneme);
+                continue;
+            }
+            let f0: Vec<f64> = self
+                .f0
+                .get(start..frame.min(self.f0.len()))
+                .unwrap_or_default()
+                .iter()
+                .copied()
+                .filter(|f0| *f0 > 0.0)
+                .collect();
+            let voiced = phoneme.phoneme.is_voiced_vowel() || phoneme.phoneme == Phoneme::MoraicN;
+            let pitch = match (voiced, f0.len()) {
+                (true, 1..) => (f0.iter().sum::<f64>() / f0.len() as f64).ln(),
+                _ => 0.0,
+            };
+            let consonant = consonant.take();
+            let text = if phoneme.phoneme == Phoneme::Pau {
+                "、"
+            } else {
+                crate::kana::text(consonant.map(|c| &c.phoneme), &phoneme.phoneme)
+                    .unwrap_or_default()
+            };
+            moras.push(Mora {
+                text: text.to_owned(),
+                consonant: consonant.map(|c| c.phoneme.clone()),
+                consonant_length: consonant.map(FramePhoneme::seconds),
+                vowel: phoneme.phoneme.clone(),
+                vowel_length: phoneme.seconds(),
+                pitch,
+            });
+        }
+        moras
+    }
+
+    /// モーラから音素とフレームごとの音高を作る.
+    ///
+    /// 長さはフレーム数に丸め,音高は`exp(pitch)`をモーラ全体の基本周波数とします.
+    /// 有声のモーラのフレームの音量は`volume`,それ以外は0になります.
+    pub fn from_moras(moras: &[Mora], volume: f64) -> Self {
+        let mut query = Self {
+            f0: Vec::new(),
+            volume: Vec::new(),
+            phonemes: Vec::new(),
+            volumeScale: 1.0,
+            outputSamplingRate: 24000,
+            outputStereo: false,
+        };
+        for mora in moras {
+            let f0 = if mora.pitch > 0.0 {
+                mora.pitch.exp()
+            } else {
+                0.0
+            };
+            let phonemes = mora
+                .consonant
+                .clone()
+                .map(|c| (c, mora.consonant_length.unwrap_or(0.0)))
+                .into_iter()
+                .chain(std::iter::once((mora.vowel.clone(), mora.vowel_length)));
+            for (phoneme, seconds) in phonemes {
+                let frame_length = (seconds * Self::FRAME_RATE).round().max(0.0) as usize;
+                query.phonemes.push(FramePhoneme {
+                    phoneme,
+                    frame_length: frame_length as i32,
+                    note_id: None,
+                });
+                query.f0.extend(std::iter::repeat_n(f0, frame_length));
+                let volume = if f0 > 0.0 { volume } else { 0.0 };
+                query
+                    .volume
+                    .extend(std::iter::repeat_n(volume, frame_length));
+            }
+        }
+        query
+    }
+}
+
 #[allow(non_snake_case)]
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct HttpValidationError {
     pub detail: Vec<ValidationError>,
 }
 
+impl std::fmt::Display for HttpValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, e) in self.detail.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}: {}", e.loc.join("."), e.msg)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ValidationError {
     ///Location
@@ -209,22 +732,27 @@ pub struct ValidationError {
     pub _type: String,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct AccentPhrasesResponse {
-    pub accent_phrases: Vec<AccentPhrase>,
-}
-
+/// 読み仮名の解析に失敗したときのエラー.
+///
+/// `kana::KanaError::from_bad_request`で型付きのエラーに変換できます.
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ParseKanaBadRequest {
+    /// エラーメッセージ.
     pub text: String,
     pub error_name: String,
-    pub error_args: String,
+    /// エラーを起こした箇所.`text`か`position`を持つ.
+    pub error_args: std::collections::HashMap<String, String>,
 }
-#[derive(Debug, Clone)]
+/// 読み仮名の解析エラーの種類.
+///
+/// エンジンの`error_name`と同じ名前でシリアライズされます.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ErrorName {
     UnknownText,
     AccentTop,
     AccentTwice,
+    #[serde(rename = "ACCENT_NOTFOUND")]
     AccentNotFound,
     EmptyPhrase,
     InterrogationMarkNotAtEnd,
@@ -234,10 +762,10 @@ pub enum ErrorName {
 #[allow(non_snake_case, unused_variables)]
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Preset {
-    pub id: i32,
+    pub id: PresetId,
     pub name: String,
-    pub speaker_uuid: String,
-    pub style_id: i32,
+    pub speaker_uuid: SpeakerUuid,
+    pub style_id: StyleId,
     pub speedScale: f64,
     pub pitchScale: f64,
     pub intonationScale: f64,
@@ -248,26 +776,42 @@ pub struct Preset {
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Speaker {
+    /// ない場合は全ての機能に対応しているとみなします.
+    #[serde(default)]
     pub supported_features: SpeakerSupportedFeatures,
     /// character name
     pub name: String,
     /// used to call SpeakerInfo.
-    pub speaker_uuid: String,
+    pub speaker_uuid: SpeakerUuid,
     /// collection of emotion style.
     pub styles: Vec<SpeakerStyle>,
+    #[serde(default)]
     pub version: Option<String>,
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct SpeakerSupportedFeatures {
+    /// ない場合はモーフィングが許可されているとみなします.
+    #[serde(default)]
     pub permitted_synthesis_morphing: SpeakerSupportPermittedSynthesisMorphing,
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub enum SpeakerSupportPermittedSynthesisMorphing {
-    ALL,
-    SELF_ONLY,
-    NOTHING,
+string_enum! {
+    /// 話者のモーフィングの許可.
+    ///
+    /// 知らない値は`Unknown`になります.
+    #[allow(non_camel_case_types)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+    pub enum SpeakerSupportPermittedSynthesisMorphing {
+        #[default]
+        ALL => "ALL",
+        SELF_ONLY => "SELF_ONLY",
+        NOTHING => "NOTHING",
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -275,12 +819,35 @@ pub struct SpeakerStyle {
     /// emotion style.
     pub name: String,
     /// style_id or speaker same as [StyleInfo.id]
-    pub id: i32,
+    pub id: StyleId,
+    /// スタイルの種類.ない場合は`Talk`です.
+    #[serde(rename = "type", default)]
+    pub style_type: StyleType,
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+string_enum! {
+    /// スタイルの種類.
+    ///
+    /// 知らない値は`Unknown`になります.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+    pub enum StyleType {
+        /// 音声合成.
+        #[default]
+        Talk => "talk",
+        /// 歌唱合成の`SingFrameAudioQuery`に使う.
+        SingingTeacher => "singing_teacher",
+        /// 歌唱合成の`FrameSynthesis`に使う.
+        FrameDecode => "frame_decode",
+        /// `SingingTeacher`と`FrameDecode`の両方に使える.
+        Sing => "sing",
+    }
 }
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
 pub struct StyleInfoRaw {
-    pub(crate) id: i32,
+    pub(crate) id: StyleId,
     /// base64
     pub(crate) icon: String,
     /// base64
@@ -336,10 +903,19 @@ pub struct SpeakerInfoRaw {
     pub(crate) style_infos: Vec<StyleInfoRaw>,
 }
 
+#[derive(Debug, Clone, Copy)]
 pub enum TryFromRawError {
     Base64Decode,
 }
 
+impl std::fmt::Display for TryFromRawError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TryFromRawError::Base64Decode => f.write_str("invalid base64 data"),
+        }
+    }
+}
+
 impl TryFrom<SpeakerInfoRaw> for SpeakerInfo {
     type Error = TryFromRawError;
     fn try_from(mut raw: SpeakerInfoRaw) -> Result<Self, Self::Error> {
@@ -384,7 +960,7 @@ pub struct StyleInfo {
     /// * MultiSynthesis.speaker
     /// * SynthesisMorphing.base_speaker
     /// * SynthesisMorphing.target_speaker
-    pub id: i32,
+    pub id: StyleId,
     ///png file
     pub icon: Vec<u8>,
     ///wav file
@@ -401,36 +977,209 @@ pub struct SupportedDevices {
     pub dml: Option<bool>,
 }
 
+/// 音声合成に使うデバイス.
+///
+/// 使えるデバイスは`api::SupportedDevices`で調べられます.
+/// 切り替えるにはエンジンを起動し直します. `Device::engine_args`を参照してください.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Device {
+    Cpu,
+    Cuda,
+    DirectMl,
+}
+
+impl Device {
+    /// このデバイスを使うためのエンジンの起動引数.
+    ///
+    /// エンジンにはデバイスを切り替えるAPIがなく,起動時にデバイスを選びます.
+    /// 切り替えるには`supervisor::EngineConfig::device`で設定したエンジンを
+    /// `supervisor::EngineSupervisor::start`で起動し直します.
+    /// CUDAとDirectMLのどちらを使うかはエンジンのビルドで決まります.
+    pub fn engine_args(&self) -> &'static [&'static str] {
+        match self {
+            Device::Cpu => &[],
+            Device::Cuda | Device::DirectMl => &["--use_gpu"],
+        }
+    }
+}
+
+impl SupportedDevices {
+    /// 使えるデバイスの一覧.
+    pub fn devices(&self) -> Vec<Device> {
+        [
+            (Device::Cpu, self.cpu),
+            (Device::Cuda, self.cuda),
+            (Device::DirectMl, self.dml.unwrap_or(false)),
+        ]
+        .into_iter()
+        .filter_map(|(device, supported)| supported.then_some(device))
+        .collect()
+    }
+
+    pub fn supports(&self, device: Device) -> bool {
+        self.devices().contains(&device)
+    }
+}
+
+string_enum! {
+    /// CORSの許可の範囲.
+    ///
+    /// 知らない値は`Unknown`になります.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum CorsPolicyMode {
+        /// 全てのオリジンを許可する.
+        All => "all",
+        /// `app://`と`localhost`,`allow_origin`のオリジンだけを許可する.
+        Localapps => "localapps",
+    }
+}
+
+/// エンジンの設定.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    pub cors_policy_mode: CorsPolicyMode,
+    /// 追加で許可するオリジン.スペースで区切る.
+    pub allow_origin: Option<String>,
+}
+
+/// 音声ライブラリに含まれる話者.
+#[derive(Debug, Clone)]
+pub struct LibrarySpeaker {
+    pub speaker: Speaker,
+    pub speaker_info: SpeakerInfo,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LibrarySpeakerRaw {
+    pub speaker: Speaker,
+    pub speaker_info: SpeakerInfoRaw,
+}
+
+impl TryFrom<LibrarySpeakerRaw> for LibrarySpeaker {
+    type Error = TryFromRawError;
+
+    fn try_from(value: LibrarySpeakerRaw) -> Result<Self, Self::Error> {
+        Ok(Self {
+            speaker: value.speaker,
+            speaker_info: value.speaker_info.try_into()?,
+        })
+    }
+}
+
+/// インストール済みの音声ライブラリ.
+#[derive(Debug, Clone)]
+pub struct InstalledLibrary {
+    pub name: String,
+    pub uuid: String,
+    pub version: String,
+    pub download_url: String,
+    pub bytes: usize,
+    pub speakers: Vec<LibrarySpeaker>,
+    /// アンインストールできるか.
+    pub uninstallable: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct InstalledLibraryRaw {
+    pub name: String,
+    pub uuid: String,
+    pub version: String,
+    pub download_url: String,
+    pub bytes: usize,
+    pub speakers: Vec<LibrarySpeakerRaw>,
+    pub uninstallable: bool,
+}
+
+impl TryFrom<InstalledLibraryRaw> for InstalledLibrary {
+    type Error = TryFromRawError;
+
+    fn try_from(value: InstalledLibraryRaw) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: value.name,
+            uuid: value.uuid,
+            version: value.version,
+            download_url: value.download_url,
+            bytes: value.bytes,
+            speakers: value
+                .speakers
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            uninstallable: value.uninstallable,
+        })
+    }
+}
+
+/// ライブラリのUUIDをキーにしたインストール済みの音声ライブラリ.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(transparent)]
+pub struct InstalledLibrariesRaw(pub std::collections::HashMap<String, InstalledLibraryRaw>);
+
+impl TryFrom<InstalledLibrariesRaw> for std::collections::HashMap<String, InstalledLibrary> {
+    type Error = TryFromRawError;
+
+    fn try_from(value: InstalledLibrariesRaw) -> Result<Self, Self::Error> {
+        value
+            .0
+            .into_iter()
+            .map(|(uuid, library)| Ok((uuid, library.try_into()?)))
+            .collect()
+    }
+}
+
+/// ダウンロードできる音声ライブラリ.
 #[derive(Debug, Clone)]
 pub struct DownloadableLibraries {
+    pub name: String,
+    pub uuid: String,
+    pub version: String,
     pub download_url: String,
     pub bytes: usize,
-    pub speaker: Speaker,
-    pub speaker_info: SpeakerInfo,
+    pub speakers: Vec<LibrarySpeaker>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct DownloadableLibrariesRaw {
-    download_url: String,
-    bytes: usize,
-    speaker: Speaker,
-    speaker_info: SpeakerInfoRaw,
+    pub name: String,
+    pub uuid: String,
+    pub version: String,
+    pub download_url: String,
+    pub bytes: usize,
+    pub speakers: Vec<LibrarySpeakerRaw>,
 }
 
 impl TryFrom<DownloadableLibrariesRaw> for DownloadableLibraries {
     type Error = TryFromRawError;
 
     fn try_from(value: DownloadableLibrariesRaw) -> Result<Self, Self::Error> {
-        let speaker_info = value.speaker_info.try_into()?;
         Ok(Self {
+            name: value.name,
+            uuid: value.uuid,
+            version: value.version,
             download_url: value.download_url,
             bytes: value.bytes,
-            speaker: value.speaker,
-            speaker_info,
+            speakers: value
+                .speakers
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
         })
     }
 }
 
+/// `/downloadable_libraries`のレスポンス.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(transparent)]
+pub struct DownloadableLibrariesListRaw(pub Vec<DownloadableLibrariesRaw>);
+
+impl TryFrom<DownloadableLibrariesListRaw> for Vec<DownloadableLibraries> {
+    type Error = TryFromRawError;
+
+    fn try_from(value: DownloadableLibrariesListRaw) -> Result<Self, Self::Error> {
+        value.0.into_iter().map(TryInto::try_into).collect()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub(crate) struct EngineManifestRaw {
     manifest_version: String,
@@ -440,23 +1189,79 @@ pub(crate) struct EngineManifestRaw {
     icon: String,
     default_sampling_rate: i32,
     term_of_service: String,
+    #[serde(default)]
     update_infos: Vec<UpdateInfo>,
+    #[serde(default)]
     dependency_licenses: Vec<LicenseInfo>,
-    downloadable_libraries_path: String,
-    downloadable_libraries_url: String,
+    #[serde(default)]
+    downloadable_libraries_path: Option<String>,
+    #[serde(default)]
+    downloadable_libraries_url: Option<String>,
+    #[serde(default)]
     supported_features: SupportedFeatures,
+    #[serde(flatten)]
+    extra: ExtraFields,
 }
 
+/// エンジンが対応している機能.
+///
+/// 0.13で定義されていた項目はない場合に対応しているとみなし,
+/// 後から追加された項目はない場合に`None`になります.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SupportedFeatures {
+    #[serde(default = "supported")]
     pub adjust_mora_pitch: bool,
+    #[serde(default = "supported")]
     pub adjust_phoneme_length: bool,
+    #[serde(default = "supported")]
     pub adjust_speed_scale: bool,
+    #[serde(default = "supported")]
     pub adjust_pitch_scale: bool,
+    #[serde(default = "supported")]
     pub adjust_intonation_scale: bool,
+    #[serde(default = "supported")]
     pub adjust_volume_scale: bool,
+    #[serde(default = "supported")]
     pub interrogative_upspeak: bool,
+    #[serde(default = "supported")]
     pub synthesis_morphing: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adjust_pause_length: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sing: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manage_library: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_resource_url: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apply_katakana_english: Option<bool>,
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+fn supported() -> bool {
+    true
+}
+
+impl Default for SupportedFeatures {
+    fn default() -> Self {
+        Self {
+            adjust_mora_pitch: true,
+            adjust_phoneme_length: true,
+            adjust_speed_scale: true,
+            adjust_pitch_scale: true,
+            adjust_intonation_scale: true,
+            adjust_volume_scale: true,
+            interrogative_upspeak: true,
+            synthesis_morphing: true,
+            adjust_pause_length: None,
+            sing: None,
+            manage_library: None,
+            return_resource_url: None,
+            apply_katakana_english: None,
+            extra: ExtraFields::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -470,9 +1275,11 @@ pub struct EngineManifest {
     pub term_of_service: String,
     pub update_infos: Vec<UpdateInfo>,
     pub dependency_licenses: Vec<LicenseInfo>,
-    pub downloadable_libraries_path: String,
-    pub downloadable_libraries_url: String,
+    /// 新しいエンジンでは省略されます.
+    pub downloadable_libraries_path: Option<String>,
+    pub downloadable_libraries_url: Option<String>,
     pub supported_features: SupportedFeatures,
+    pub extra: ExtraFields,
 }
 
 impl TryFrom<EngineManifestRaw> for EngineManifest {
@@ -493,6 +1300,7 @@ impl TryFrom<EngineManifestRaw> for EngineManifest {
             downloadable_libraries_path: raw.downloadable_libraries_path,
             downloadable_libraries_url: raw.downloadable_libraries_url,
             supported_features: raw.supported_features,
+            extra: raw.extra,
         })
     }
 }
@@ -512,6 +1320,7 @@ impl From<EngineManifest> for EngineManifestRaw {
             downloadable_libraries_path: rustic.downloadable_libraries_path,
             downloadable_libraries_url: rustic.downloadable_libraries_url,
             supported_features: rustic.supported_features,
+            extra: rustic.extra,
         }
     }
 }
@@ -520,62 +1329,242 @@ impl From<EngineManifest> for EngineManifestRaw {
 pub struct UpdateInfo {
     version: String,
     descriptions: Vec<String>,
-    contributers: Vec<String>,
+    #[serde(default)]
+    contributors: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LicenseInfo {
     name: String,
-    version: String,
-    license: String,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    license: Option<String>,
     text: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// ユーザー辞書の単語.
+///
+/// `UserDict`が返し,`ImportUserDict`で送る形式です.
+/// 新しく作るときは`UserDictWord::builder`を使うとエンジンと同じ品詞の情報が入ります.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct UserDictWord {
+    /// 表層形.
+    pub surface: String,
+    /// 優先度. 0から10で,大きいほど優先される.
+    pub priority: i32,
+    /// 文脈ID.
+    #[serde(default)]
+    pub context_id: Option<i32>,
+    /// 品詞.
+    pub part_of_speech: String,
+    /// 品詞細分類1.
+    pub part_of_speech_detail_1: String,
+    /// 品詞細分類2.
+    pub part_of_speech_detail_2: String,
+    /// 品詞細分類3.
+    pub part_of_speech_detail_3: String,
+    /// 活用型.
+    pub inflectional_type: String,
+    /// 活用形.
+    pub inflectional_form: String,
+    /// 原形.
+    pub stem: String,
+    /// 読み.
+    pub yomi: String,
+    /// 発音(カタカナ).
+    pub pronunciation: String,
+    /// アクセント型(音が下がる場所).
+    pub accent_type: i32,
+    /// モーラ数.
+    #[serde(default)]
+    pub mora_count: Option<i32>,
+    /// アクセント結合規則.
+    pub accent_associative_rule: String,
+}
+
+impl UserDictWord {
+    /// エンジンが優先度を省略したときに使う値.
+    pub const DEFAULT_PRIORITY: i32 = 5;
+
+    pub fn builder(
+        surface: impl Into<String>,
+        pronunciation: impl Into<String>,
+        accent_type: i32,
+    ) -> UserDictWordBuilder {
+        UserDictWordBuilder {
+            surface: surface.into(),
+            pronunciation: pronunciation.into(),
+            accent_type,
+            word_type: WordType::default(),
+            priority: Self::DEFAULT_PRIORITY,
+        }
+    }
+
+    /// 品詞から単語の種類を求める.
+    ///
+    /// `WordType`のどれにも当てはまらない品詞の単語は`None`です.
+    pub fn word_type(&self) -> Option<WordType> {
+        let part_of_speech = [
+            self.part_of_speech.as_str(),
+            &self.part_of_speech_detail_1,
+            &self.part_of_speech_detail_2,
+            &self.part_of_speech_detail_3,
+        ];
+        WordType::ALL
+            .iter()
+            .find(|word_type| {
+                word_type
+                    .part_of_speech()
+                    .is_some_and(|(_, word_type)| word_type == part_of_speech)
+            })
+            .cloned()
+    }
+}
+
+/// `UserDictWord`を作る.
+///
+/// ```
+/// use voice_vox_api::api_schema::{UserDictWord, WordType};
+///
+/// let word = UserDictWord::builder("ずんだもん", "ズンダモン", 1)
+///     .word_type(WordType::ProperNoun)
+///     .priority(7)
+///     .build();
+/// assert_eq!(word.mora_count, Some(5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct UserDictWordBuilder {
     surface: String,
-    priority: i32,
-    context_id: Option<i32>,
-    part_of_speech: String,
-    part_of_speech_detail_1: String,
-    part_of_speech_detail_2: String,
-    part_of_speech_detail_3: String,
-    inflectional_type: String,
-    inflectional_form: String,
-    stem: String,
-    yomi: String,
     pronunciation: String,
     accent_type: i32,
-    mora_count: Option<i32>,
-    accent_associative_rule: String,
+    word_type: WordType,
+    priority: i32,
 }
 
-#[derive(Clone, Copy, Debug)]
-pub enum WordType {
-    ProperNoun,
-    CommonNoun,
-    Verb,
-    Adjective,
-    Suffix,
-}
+impl UserDictWordBuilder {
+    /// 既定は`WordType::ProperNoun`.
+    pub fn word_type(mut self, word_type: WordType) -> Self {
+        self.word_type = word_type;
+        self
+    }
 
-impl ToString for WordType {
-    fn to_string(&self) -> String {
-        match self {
-            WordType::ProperNoun => "PROPER_NOUN",
-            WordType::CommonNoun => "COMMON_NOUN",
-            WordType::Verb => "VERB",
-            WordType::Adjective => "ADJECTIVE",
-            WordType::Suffix => "SUFFIX",
+    /// 既定は`UserDictWord::DEFAULT_PRIORITY`.
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// エンジンが単語を追加したときと同じ内容で作る.
+    ///
+    /// エンジンと同じく,表層形のASCII文字は全角にします.
+    /// 値の検査はしません. 誤った発音やアクセント型はエンジンに送ったときにエラーになります.
+    /// `WordType::Unknown`の品詞はわからないので,`context_id`は`None`,品詞は`*`になります.
+    pub fn build(self) -> UserDictWord {
+        let (context_id, part_of_speech) = match self.word_type.part_of_speech() {
+            Some((context_id, part_of_speech)) => (Some(context_id), part_of_speech),
+            None => (None, ["*"; 4]),
+        };
+        let mora_count = self
+            .pronunciation
+            .chars()
+            .filter(|c| !"ァィゥェォャュョヮ".contains(*c))
+            .count() as i32;
+        UserDictWord {
+            surface: full_width_surface(&self.surface),
+            priority: self.priority,
+            context_id,
+            part_of_speech: part_of_speech[0].to_owned(),
+            part_of_speech_detail_1: part_of_speech[1].to_owned(),
+            part_of_speech_detail_2: part_of_speech[2].to_owned(),
+            part_of_speech_detail_3: part_of_speech[3].to_owned(),
+            inflectional_type: "*".to_owned(),
+            inflectional_form: "*".to_owned(),
+            stem: "*".to_owned(),
+            yomi: self.pronunciation.clone(),
+            pronunciation: self.pronunciation,
+            accent_type: self.accent_type,
+            mora_count: Some(mora_count),
+            accent_associative_rule: "*".to_owned(),
         }
-        .to_owned()
     }
 }
-impl Serialize for WordType {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        serializer.serialize_str(&self.to_string())
+
+/// エンジンが登録する表層形. `!`から`~`までのASCII文字を全角にする.
+pub(crate) fn full_width_surface(surface: &str) -> String {
+    surface
+        .chars()
+        .map(|c| match c {
+            '!'..='~' => char::from_u32(c as u32 - 0x21 + 0xFF01).unwrap(),
+            _ => c,
+        })
+        .collect()
+}
+
+string_enum! {
+    /// ユーザー辞書の単語の種類.
+    ///
+    /// 知らない値は`Unknown`になります.
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+    pub enum WordType {
+        #[default]
+        ProperNoun => "PROPER_NOUN",
+        CommonNoun => "COMMON_NOUN",
+        Verb => "VERB",
+        Adjective => "ADJECTIVE",
+        Suffix => "SUFFIX",
+    }
+}
+
+impl WordType {
+    pub const ALL: &'static [WordType] = &[
+        WordType::ProperNoun,
+        WordType::CommonNoun,
+        WordType::Verb,
+        WordType::Adjective,
+        WordType::Suffix,
+    ];
+
+    /// エンジンが単語に付ける文脈IDと品詞,品詞細分類1から3.`Unknown`は`None`.
+    pub fn part_of_speech(&self) -> Option<(i32, [&'static str; 4])> {
+        Some(match self {
+            WordType::ProperNoun => (1348, ["名詞", "固有名詞", "一般", "*"]),
+            WordType::CommonNoun => (1345, ["名詞", "一般", "*", "*"]),
+            WordType::Verb => (642, ["動詞", "自立", "*", "*"]),
+            WordType::Adjective => (20, ["形容詞", "自立", "*", "*"]),
+            WordType::Suffix => (1358, ["名詞", "接尾", "一般", "*"]),
+            WordType::Unknown(_) => return None,
+        })
+    }
+}
+
+impl std::fmt::Display for WordType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
     }
 }
+
+/// `WordType::ALL`のどれでもない文字列はエラーになります.
+impl std::str::FromStr for WordType {
+    type Err = UnknownWordType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WordType::ALL
+            .iter()
+            .find(|word_type| word_type.as_str() == s)
+            .cloned()
+            .ok_or_else(|| UnknownWordType(s.to_owned()))
+    }
+}
+
+/// エンジンにない単語の種類.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownWordType(pub String);
+
+impl std::fmt::Display for UnknownWordType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown word type `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownWordType {}
@@ -0,0 +1,517 @@
+//! wavの読み書き.
+//!
+//! `Synthesis`などが返すwavや`StyleInfo::voice_samples`をリニアPCMのサンプルとして扱います.
+//!
+//! ```ignore
+//! let wav = Wav::parse(&Synthesis { .. }.call(&client).await?)?;
+//! println!("{:?} {}", wav.duration(), wav.peak());
+//! ```
+
+use std::{fmt, time::Duration};
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// リニアPCMのwav.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wav {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// 8,16,24,32のいずれか.
+    pub bits_per_sample: u16,
+    /// チャンネルごとに交互に並んだサンプル.
+    ///
+    /// 値は`bits_per_sample`で表せる符号付き整数です. 8bitも符号付きに変換しています.
+    pub samples: Vec<i32>,
+}
+
+/// wavを読み取れなかった理由.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WavError {
+    /// RIFF/WAVEのヘッダがない.
+    NotWave,
+    /// チャンクの途中でデータが終わっている.
+    Truncated,
+    /// `fmt `チャンクがない.
+    MissingFormat,
+    /// `data`チャンクがない.
+    MissingData,
+    /// リニアPCM以外のフォーマット.
+    UnsupportedFormat(u16),
+    UnsupportedBitDepth(u16),
+    /// チャンネル数やサンプリングレートが0.
+    InvalidFormat,
+    /// チャンネル数,サンプリングレート,データの大きさがwavのヘッダで表せない.
+    TooLarge,
+    /// 結合する`index`番目のwavの量子化ビット数が他と異なる.
+    BitDepthMismatch {
+        index: usize,
+        expected: u16,
+        found: u16,
+    },
+    /// チャンネル数を変換できない.
+    ///
+    /// モノラルとステレオの間以外の変換には対応していません.
+    UnsupportedChannels {
+        from: u16,
+        to: u16,
+    },
+}
+
+impl fmt::Display for WavError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WavError::NotWave => f.write_str("not a RIFF/WAVE file"),
+            WavError::Truncated => f.write_str("wav data is truncated"),
+            WavError::MissingFormat => f.write_str("fmt chunk not found"),
+            WavError::MissingData => f.write_str("data chunk not found"),
+            WavError::UnsupportedFormat(format) => {
+                write!(f, "unsupported wav format {format:#06x}")
+            }
+            WavError::UnsupportedBitDepth(bits) => write!(f, "unsupported bit depth {bits}"),
+            WavError::InvalidFormat => f.write_str("channels and sample rate must not be 0"),
+            WavError::TooLarge => f.write_str("wav is too large for its header"),
+            WavError::BitDepthMismatch {
+                index,
+                expected,
+                found,
+            } => write!(f, "wav {index} has bit depth {found}, expected {expected}"),
+            WavError::UnsupportedChannels { from, to } => {
+                write!(f, "can not convert {from} channels to {to} channels")
+            }
+        }
+    }
+}
+
+impl std::error::Error for WavError {}
+
+impl Wav {
+    /// 無音のwavを作る.
+    pub fn silence(
+        sample_rate: u32,
+        channels: u16,
+        bits_per_sample: u16,
+        duration: Duration,
+    ) -> Self {
+        let frames = (duration.as_secs_f64() * sample_rate as f64).round() as usize;
+        Self {
+            sample_rate,
+            channels,
+            bits_per_sample,
+            samples: vec![0; frames * channels as usize],
+        }
+    }
+
+    /// RIFF/WAVEを読み取る.
+    pub fn parse(bytes: &[u8]) -> Result<Self, WavError> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(WavError::NotWave);
+        }
+        let mut format = None;
+        let mut rest = &bytes[12..];
+        while rest.len() >= 8 {
+            let id = &rest[0..4];
+            let size = u32_at(rest, 4) as usize;
+            let chunk = &rest[8..];
+            // エンジンによってはdataチャンクのサイズが実際より大きいことがあるので残りを全て使う.
+            let chunk = if id == b"data" && size > chunk.len() {
+                chunk
+            } else {
+                chunk.get(..size).ok_or(WavError::Truncated)?
+            };
+            match id {
+                b"fmt " => format = Some(parse_format(chunk)?),
+                b"data" => {
+                    let (sample_rate, channels, bits_per_sample) =
+                        format.ok_or(WavError::MissingFormat)?;
+                    return Ok(Self {
+                        sample_rate,
+                        channels,
+                        bits_per_sample,
+                        samples: decode_samples(chunk, bits_per_sample),
+                    });
+                }
+                _ => {}
+            }
+            rest = rest.get(8 + size + size % 2..).unwrap_or_default();
+        }
+        Err(match format {
+            Some(_) => WavError::MissingData,
+            None => WavError::MissingFormat,
+        })
+    }
+
+    /// RIFF/WAVEとして書き出す.
+    ///
+    /// `parse`で読み取れないフォーマットやヘッダで表せない大きさの場合はエラーになります.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WavError> {
+        let (block_align, byte_rate) =
+            format_sizes(self.sample_rate, self.channels, self.bits_per_sample)?;
+        let bytes_per_sample = self.bits_per_sample as usize / 8;
+        let (data_len, riff_len) = self
+            .samples
+            .len()
+            .checked_mul(bytes_per_sample)
+            .and_then(|len| u32::try_from(len).ok())
+            .and_then(|len| Some((len, len.checked_add(36 + len % 2)?)))
+            .ok_or(WavError::TooLarge)?;
+        let mut wav = Vec::with_capacity(44 + data_len as usize + 1);
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&riff_len.to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&FORMAT_PCM.to_le_bytes());
+        wav.extend_from_slice(&self.channels.to_le_bytes());
+        wav.extend_from_slice(&self.sample_rate.to_le_bytes());
+        wav.extend_from_slice(&byte_rate.to_le_bytes());
+        wav.extend_from_slice(&block_align.to_le_bytes());
+        wav.extend_from_slice(&self.bits_per_sample.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        for sample in &self.samples {
+            match bytes_per_sample {
+                1 => wav.push((*sample as u8).wrapping_add(128)),
+                bytes => wav.extend_from_slice(&sample.to_le_bytes()[..bytes]),
+            }
+        }
+        if data_len % 2 == 1 {
+            wav.push(0);
+        }
+        Ok(wav)
+    }
+
+    /// チャンネルあたりのサンプル数.
+    pub fn frames(&self) -> usize {
+        match self.channels {
+            0 => 0,
+            channels => self.samples.len() / channels as usize,
+        }
+    }
+
+    /// 再生時間.
+    pub fn duration(&self) -> Duration {
+        match self.sample_rate {
+            0 => Duration::ZERO,
+            sample_rate => Duration::from_secs_f64(self.frames() as f64 / sample_rate as f64),
+        }
+    }
+
+    /// サンプルを-1.0から1.0の範囲で返す.
+    pub fn normalized(&self) -> impl Iterator<Item = f32> + '_ {
+        let scale = self.full_scale();
+        self.samples
+            .iter()
+            .map(move |sample| *sample as f32 / scale)
+    }
+
+    /// 全チャンネルの振幅の最大値(0.0から1.0).
+    pub fn peak(&self) -> f32 {
+        self.normalized()
+            .fold(0.0, |peak, sample| peak.max(sample.abs()))
+    }
+
+    /// 時間方向に`buckets`個に分けた区間ごとの振幅の最大値.
+    ///
+    /// 波形の表示に使えます.
+    pub fn peaks(&self, buckets: usize) -> Vec<f32> {
+        let frames = self.frames();
+        if buckets == 0 || frames == 0 {
+            return vec![0.0; buckets];
+        }
+        let channels = self.channels as usize;
+        let scale = self.full_scale();
+        (0..buckets)
+            .map(|bucket| {
+                let start = bucket * frames / buckets;
+                let end = ((bucket + 1) * frames / buckets).max(start + 1).min(frames);
+                self.samples[start * channels..end * channels]
+                    .iter()
+                    .fold(0.0f32, |peak, sample| {
+                        peak.max((*sample as f32 / scale).abs())
+                    })
+            })
+            .collect()
+    }
+
+    /// サンプリングレートを変換する.
+    ///
+    /// サンプル間を線形補間します.
+    pub fn resample(&self, sample_rate: u32) -> Self {
+        if sample_rate == self.sample_rate || self.sample_rate == 0 || sample_rate == 0 {
+            return Self {
+                sample_rate,
+                ..self.clone()
+            };
+        }
+        let channels = self.channels as usize;
+        let frames = self.frames();
+        let ratio = self.sample_rate as f64 / sample_rate as f64;
+        let resampled_frames = (frames as f64 / ratio).round() as usize;
+        let mut samples = Vec::with_capacity(resampled_frames * channels);
+        for frame in 0..resampled_frames {
+            let position = frame as f64 * ratio;
+            let left = (position.floor() as usize).min(frames - 1);
+            let right = (left + 1).min(frames - 1);
+            let t = position - left as f64;
+            for channel in 0..channels {
+                let a = self.samples[left * channels + channel] as f64;
+                let b = self.samples[right * channels + channel] as f64;
+                samples.push((a + (b - a) * t).round() as i32);
+            }
+        }
+        Self {
+            sample_rate,
+            samples,
+            ..*self
+        }
+    }
+
+    /// チャンネル数を変換する.
+    ///
+    /// モノラルからステレオは同じサンプルを両方に,ステレオからモノラルは平均を使います.
+    pub fn to_channels(&self, channels: u16) -> Result<Self, WavError> {
+        let samples = match (self.channels, channels) {
+            (from, to) if from == to => self.samples.clone(),
+            (1, 2) => self.samples.iter().flat_map(|s| [*s, *s]).collect(),
+            (2, 1) => self
+                .samples
+                .chunks_exact(2)
+                .map(|s| ((s[0] as i64 + s[1] as i64) / 2) as i32)
+                .collect(),
+            (from, to) => return Err(WavError::UnsupportedChannels { from, to }),
+        };
+        Ok(Self {
+            channels,
+            samples,
+            ..*self
+        })
+    }
+
+    /// 正の最大値+1. サンプルを正規化するときに使う.
+    fn full_scale(&self) -> f32 {
+        (1u64 << (self.bits_per_sample.clamp(1, 32) - 1)) as f32
+    }
+}
+
+/// `connect`の設定.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectOptions {
+    /// 結合後のサンプリングレート. 省略した場合は最初のwavに合わせます.
+    pub sample_rate: Option<u32>,
+    /// 結合後のチャンネル数. 省略した場合はいずれかがステレオならステレオにします.
+    pub channels: Option<u16>,
+    /// wavの間に挟む無音の長さ.
+    pub silence: Duration,
+}
+
+/// 複数のwavを1つに結合する.
+///
+/// `ConnectWaves`と違いエンジンを使いません.
+/// サンプリングレート(`outputSamplingRate`)とチャンネル数(`outputStereo`)が異なる場合は変換して揃えますが,
+/// 量子化ビット数は全て同じである必要があります.
+pub fn connect(waves: &[Wav], options: &ConnectOptions) -> Result<Wav, WavError> {
+    let Some(first) = waves.first() else {
+        return Ok(Wav::silence(
+            options.sample_rate.unwrap_or(24000),
+            options.channels.unwrap_or(1),
+            16,
+            Duration::ZERO,
+        ));
+    };
+    let sample_rate = options.sample_rate.unwrap_or(first.sample_rate);
+    let channels = options
+        .channels
+        .unwrap_or_else(|| waves.iter().map(|wav| wav.channels).max().unwrap_or(1));
+    let silence = Wav::silence(
+        sample_rate,
+        channels,
+        first.bits_per_sample,
+        options.silence,
+    );
+    let mut connected = Wav {
+        samples: Vec::new(),
+        ..silence.clone()
+    };
+    for (index, wav) in waves.iter().enumerate() {
+        if wav.bits_per_sample != first.bits_per_sample {
+            return Err(WavError::BitDepthMismatch {
+                index,
+                expected: first.bits_per_sample,
+                found: wav.bits_per_sample,
+            });
+        }
+        if index != 0 {
+            connected.samples.extend_from_slice(&silence.samples);
+        }
+        let wav = wav.to_channels(channels)?.resample(sample_rate);
+        connected.samples.extend(wav.samples);
+    }
+    Ok(connected)
+}
+
+/// wavファイルのまま結合する.
+///
+/// `ConnectWaves::call`のかわりに使えます.
+pub fn connect_bytes<B: AsRef<[u8]>>(
+    waves: &[B],
+    options: &ConnectOptions,
+) -> Result<Vec<u8>, WavError> {
+    let waves = waves
+        .iter()
+        .map(|wav| Wav::parse(wav.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    connect(&waves, options)?.to_bytes()
+}
+
+/// zipに含まれるwavを読み取れなかった理由.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveEntryError {
+    /// 名前に対応するファイルがない.
+    Missing(String),
+    /// ファイルを展開できなかった.
+    Read { name: String, message: String },
+    /// wavとして読み取れなかった.
+    Wav { name: String, error: WavError },
+}
+
+impl fmt::Display for ArchiveEntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveEntryError::Missing(name) => write!(f, "{name} not found in archive"),
+            ArchiveEntryError::Read { name, message } => {
+                write!(f, "failed to read {name}: {message}")
+            }
+            ArchiveEntryError::Wav { name, error } => write!(f, "{name}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveEntryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ArchiveEntryError::Wav { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// `MultiSynthesis`が返したzipから`count`個のwavを順番に取り出す.
+///
+/// エンジンは`001.wav`,`002.wav`のように1から始まる連番でwavを格納します.
+/// 個々のwavのエラーはそれぞれの要素として返し,zip自体を読み取れない場合だけ全体をエラーにします.
+pub fn unpack_multi_synthesis(
+    zip: &[u8],
+    count: usize,
+) -> Result<Vec<Result<Wav, ArchiveEntryError>>, zip::result::ZipError> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(zip))?;
+    Ok((1..=count)
+        .map(|i| {
+            let name = format!("{i:03}.wav");
+            let mut bytes = Vec::new();
+            match archive.by_name(&name) {
+                Ok(mut file) => std::io::Read::read_to_end(&mut file, &mut bytes).map_err(|e| {
+                    ArchiveEntryError::Read {
+                        name: name.clone(),
+                        message: e.to_string(),
+                    }
+                })?,
+                Err(zip::result::ZipError::FileNotFound) => {
+                    return Err(ArchiveEntryError::Missing(name))
+                }
+                Err(e) => {
+                    return Err(ArchiveEntryError::Read {
+                        name,
+                        message: e.to_string(),
+                    })
+                }
+            };
+            Wav::parse(&bytes).map_err(|error| ArchiveEntryError::Wav { name, error })
+        })
+        .collect())
+}
+
+impl TryFrom<&[u8]> for Wav {
+    type Error = WavError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::parse(bytes)
+    }
+}
+
+impl TryFrom<&Wav> for Vec<u8> {
+    type Error = WavError;
+
+    fn try_from(wav: &Wav) -> Result<Self, Self::Error> {
+        wav.to_bytes()
+    }
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+/// `fmt `チャンクからサンプリングレート,チャンネル数,量子化ビット数を読み取る.
+fn parse_format(chunk: &[u8]) -> Result<(u32, u16, u16), WavError> {
+    if chunk.len() < 16 {
+        return Err(WavError::Truncated);
+    }
+    let mut format = u16_at(chunk, 0);
+    if format == FORMAT_EXTENSIBLE {
+        // サブフォーマットGUIDの先頭2byteがフォーマットを表す.
+        if chunk.len() < 26 {
+            return Err(WavError::Truncated);
+        }
+        format = u16_at(chunk, 24);
+    }
+    if format != FORMAT_PCM {
+        return Err(WavError::UnsupportedFormat(format));
+    }
+    let channels = u16_at(chunk, 2);
+    let sample_rate = u32_at(chunk, 4);
+    let bits_per_sample = u16_at(chunk, 14);
+    format_sizes(sample_rate, channels, bits_per_sample)?;
+    Ok((sample_rate, channels, bits_per_sample))
+}
+
+/// フォーマットを検証して,ヘッダに書くブロックサイズとバイトレートを求める.
+fn format_sizes(
+    sample_rate: u32,
+    channels: u16,
+    bits_per_sample: u16,
+) -> Result<(u16, u32), WavError> {
+    if !matches!(bits_per_sample, 8 | 16 | 24 | 32) {
+        return Err(WavError::UnsupportedBitDepth(bits_per_sample));
+    }
+    if channels == 0 || sample_rate == 0 {
+        return Err(WavError::InvalidFormat);
+    }
+    let block_align = channels
+        .checked_mul(bits_per_sample / 8)
+        .ok_or(WavError::TooLarge)?;
+    let byte_rate = sample_rate
+        .checked_mul(block_align as u32)
+        .ok_or(WavError::TooLarge)?;
+    Ok((block_align, byte_rate))
+}
+
+fn decode_samples(data: &[u8], bits_per_sample: u16) -> Vec<i32> {
+    match bits_per_sample {
+        8 => data.iter().map(|b| *b as i32 - 128).collect(),
+        16 => data
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as i32)
+            .collect(),
+        24 => data
+            .chunks_exact(3)
+            .map(|b| i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8)
+            .collect(),
+        _ => data
+            .chunks_exact(4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+    }
+}
@@ -0,0 +1,288 @@
+//! 複数の行をまとめて音声合成する.
+//!
+//! 行ごとに`AudioQuery`と`Synthesis`を呼び出し,同時に送信するリクエストの数を制限して並行に処理します.
+//! 結果は入力と同じ順番で返し,失敗した行はその要素だけがエラーになります.
+//!
+//! ```ignore
+//! let jobs = lines.iter().map(|line| BatchJob::text(line, StyleId(0))).collect();
+//! let wavs = BatchSynthesis::new(jobs)
+//!     .concurrency(4)
+//!     .run(&client, |event| println!("{event:?}"))
+//!     .await;
+//! ```
+
+use crate::{
+    api::{AudioQuery, Synthesis},
+    api_schema::{self, StyleId},
+    cancel::CancellationToken,
+    client::VoiceVoxClient,
+    endpoint::Endpoint,
+    error::APIError,
+    synthesis_cache::SynthesisCache,
+    transport::Transport,
+};
+use std::{future::Future, pin::Pin, sync::Arc, task::Poll};
+
+/// 合成する内容.
+#[derive(Debug, Clone)]
+pub enum BatchInput {
+    /// `AudioQuery`でクエリを作成してから合成する.
+    Text(String),
+    /// 編集済みのクエリをそのまま合成する.
+    Query(api_schema::AudioQuery),
+}
+
+/// 1行分の合成.
+#[derive(Debug, Clone)]
+pub struct BatchJob {
+    pub input: BatchInput,
+    pub style_id: StyleId,
+}
+
+impl BatchJob {
+    pub fn text(text: impl Into<String>, style_id: StyleId) -> Self {
+        Self {
+            input: BatchInput::Text(text.into()),
+            style_id,
+        }
+    }
+
+    pub fn query(audio_query: api_schema::AudioQuery, style_id: StyleId) -> Self {
+        Self {
+            input: BatchInput::Query(audio_query),
+            style_id,
+        }
+    }
+}
+
+/// 進捗.
+#[derive(Debug, Clone)]
+pub enum BatchEvent {
+    /// `index`番目の行を送信し始めた.
+    Started { index: usize },
+    /// `index`番目の行が終わった.`completed`は終わった行の数.
+    Finished {
+        index: usize,
+        completed: usize,
+        total: usize,
+        error: Option<APIError>,
+    },
+}
+
+/// まとめて合成するリクエスト.
+#[derive(Debug, Clone)]
+pub struct BatchSynthesis {
+    pub jobs: Vec<BatchJob>,
+    /// 同時に処理する行の数.0の場合も1行ずつ処理します.
+    pub concurrency: usize,
+    pub enable_interrogative_upspeak: Option<bool>,
+    pub token: Option<CancellationToken>,
+    pub cache: Option<Arc<SynthesisCache>>,
+}
+
+impl BatchSynthesis {
+    pub fn new(jobs: Vec<BatchJob>) -> Self {
+        Self {
+            jobs,
+            concurrency: 1,
+            enable_interrogative_upspeak: None,
+            token: None,
+            cache: None,
+        }
+    }
+
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    pub fn enable_interrogative_upspeak(mut self, enable: bool) -> Self {
+        self.enable_interrogative_upspeak = Some(enable);
+        self
+    }
+
+    /// 中断すると送信中の行と残りの行が`APIError::Cancelled`になります.
+    pub fn cancel_with(mut self, token: CancellationToken) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// 合成の結果を`cache`から返し,合成したものは`cache`に保存する.
+    ///
+    /// テキストの行は`AudioQuery`でクエリを作成してからキャッシュを探します.
+    /// `negotiate`したクライアントを使うと,行ごとにエンジンのバージョンを問い合わせずに済みます.
+    pub fn cache(mut self, cache: Arc<SynthesisCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// 全ての行を合成して入力と同じ順番で返す.
+    pub async fn run<T: Transport>(
+        self,
+        client: &VoiceVoxClient<T>,
+        mut on_event: impl FnMut(BatchEvent),
+    ) -> Vec<Result<Vec<u8>, APIError>> {
+        let total = self.jobs.len();
+        let concurrency = self.concurrency.max(1);
+        let upspeak = self.enable_interrogative_upspeak;
+        let token = self.token.as_ref();
+        let cache = self.cache.as_deref();
+        let mut results: Vec<Option<Result<Vec<u8>, APIError>>> = vec![None; total];
+        let mut pending = self.jobs.into_iter().enumerate().peekable();
+        let mut running = Vec::new();
+        let mut completed = 0;
+        std::future::poll_fn(|cx| loop {
+            while running.len() < concurrency {
+                let Some((index, job)) = pending.next() else {
+                    break;
+                };
+                on_event(BatchEvent::Started { index });
+                let job: Pin<Box<_>> = Box::pin(run_job(client, job, upspeak, token, cache));
+                running.push((index, job));
+            }
+            let mut finished = false;
+            let mut i = 0;
+            while i < running.len() {
+                let Poll::Ready(result) = running[i].1.as_mut().poll(cx) else {
+                    i += 1;
+                    continue;
+                };
+                let (index, _) = running.swap_remove(i);
+                completed += 1;
+                on_event(BatchEvent::Finished {
+                    index,
+                    completed,
+                    total,
+                    error: result.as_ref().err().cloned(),
+                });
+                results[index] = Some(result);
+                finished = true;
+            }
+            if running.is_empty() && pending.peek().is_none() {
+                return Poll::Ready(());
+            }
+            if !finished {
+                return Poll::Pending;
+            }
+        })
+        .await;
+        results.into_iter().map(Option::unwrap).collect()
+    }
+
+    /// `run`と同じリクエストを`concurrency`個のスレッドから同期的に送信する.
+    #[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+    pub fn run_blocking<T: crate::blocking::BlockingTransport + Sync>(
+        self,
+        client: &VoiceVoxClient<T>,
+        on_event: impl FnMut(BatchEvent) + Send,
+    ) -> Vec<Result<Vec<u8>, APIError>> {
+        use std::sync::Mutex;
+
+        let total = self.jobs.len();
+        let upspeak = self.enable_interrogative_upspeak;
+        let token = self.token.as_ref();
+        let cache = self.cache.as_deref();
+        let pending = Mutex::new(self.jobs.into_iter().enumerate());
+        let state = Mutex::new((vec![None; total], 0, on_event));
+        std::thread::scope(|scope| {
+            for _ in 0..self.concurrency.max(1).min(total) {
+                scope.spawn(|| loop {
+                    let Some((index, job)) = pending.lock().unwrap().next() else {
+                        break;
+                    };
+                    state.lock().unwrap().2(BatchEvent::Started { index });
+                    let result = run_job_blocking(client, job, upspeak, token, cache);
+                    let mut state = state.lock().unwrap();
+                    let (results, completed, on_event) = &mut *state;
+                    *completed += 1;
+                    on_event(BatchEvent::Finished {
+                        index,
+                        completed: *completed,
+                        total,
+                        error: result.as_ref().err().cloned(),
+                    });
+                    results[index] = Some(result);
+                });
+            }
+        });
+        let (results, _, _) = state.into_inner().unwrap();
+        results.into_iter().map(Option::unwrap).collect()
+    }
+}
+
+async fn run_job<T: Transport>(
+    client: &VoiceVoxClient<T>,
+    job: BatchJob,
+    enable_interrogative_upspeak: Option<bool>,
+    token: Option<&CancellationToken>,
+    cache: Option<&SynthesisCache>,
+) -> Result<Vec<u8>, APIError> {
+    async fn send<T: Transport, E: Endpoint<Error = APIError>>(
+        client: &VoiceVoxClient<T>,
+        endpoint: E,
+        token: Option<&CancellationToken>,
+    ) -> Result<E::Response, APIError> {
+        match token {
+            Some(token) => client.send_cancellable(endpoint, token).await,
+            None => client.send(endpoint).await,
+        }
+    }
+
+    let audio_query = match job.input {
+        BatchInput::Text(text) => {
+            let endpoint = AudioQuery {
+                text,
+                speaker: job.style_id,
+                core_version: None,
+            };
+            send(client, endpoint, token).await?
+        }
+        BatchInput::Query(audio_query) => audio_query,
+    };
+    let endpoint = Synthesis {
+        speaker: job.style_id,
+        enable_interrogative_upspeak,
+        core_version: None,
+        audio_query,
+    };
+    match (cache, token) {
+        (Some(cache), Some(token)) => cache.call_cancellable(client, endpoint, token).await,
+        (Some(cache), None) => cache.call(client, endpoint).await,
+        (None, _) => send(client, endpoint, token).await,
+    }
+}
+
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+fn run_job_blocking<T: crate::blocking::BlockingTransport>(
+    client: &VoiceVoxClient<T>,
+    job: BatchJob,
+    enable_interrogative_upspeak: Option<bool>,
+    token: Option<&CancellationToken>,
+    cache: Option<&SynthesisCache>,
+) -> Result<Vec<u8>, APIError> {
+    // 同期的な送信は途中で中断できないので,送信する前に確かめる.
+    let check = || match token {
+        Some(token) if token.is_cancelled() => Err(APIError::Cancelled),
+        _ => Ok(()),
+    };
+    check()?;
+    let audio_query = match job.input {
+        BatchInput::Text(text) => client.send_blocking(AudioQuery {
+            text,
+            speaker: job.style_id,
+            core_version: None,
+        })?,
+        BatchInput::Query(audio_query) => audio_query,
+    };
+    check()?;
+    let endpoint = Synthesis {
+        speaker: job.style_id,
+        enable_interrogative_upspeak,
+        core_version: None,
+        audio_query,
+    };
+    match cache {
+        Some(cache) => cache.call_blocking(client, endpoint),
+        None => client.send_blocking(endpoint),
+    }
+}
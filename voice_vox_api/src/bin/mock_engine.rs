@@ -0,0 +1,35 @@
+//! VoiceVox engineの代わりにモックのエンジンを起動する.
+//!
+//! ```text
+//! mock_engine [--host 127.0.0.1] [--port 50021]
+//! ```
+
+use voice_vox_api::mock::MockEngine;
+
+fn main() {
+    let mut host = "127.0.0.1".to_owned();
+    let mut port = 50021u16;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--host", Some(value)) => host = value,
+            ("--port", Some(value)) => match value.parse() {
+                Ok(value) => port = value,
+                Err(e) => exit(&format!("invalid port {value}: {e}")),
+            },
+            _ => exit(&format!("unknown argument {arg}")),
+        }
+    }
+    let engine = match MockEngine::bind((host.as_str(), port)) {
+        Ok(engine) => engine,
+        Err(e) => exit(&format!("failed to bind {host}:{port}: {e}")),
+    };
+    println!("mock engine listening on {}", engine.base_url());
+    engine.wait();
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{message}");
+    eprintln!("usage: mock_engine [--host HOST] [--port PORT]");
+    std::process::exit(2)
+}
@@ -0,0 +1,103 @@
+//! 非同期ランタイムを使わずにAPIを呼び出す.
+//!
+//! 各リクエスト構造体の`call_blocking`は`call`と同じリクエストを送信し,同じ型を返します.
+//!
+//! ```ignore
+//! let client = VoiceVoxClient::builder("http://localhost:50021").build_blocking()?;
+//! let audio_query = AudioQuery {
+//!     text: "こんにちは".to_owned(),
+//!     speaker: StyleId(0),
+//!     core_version: None,
+//! }
+//! .call_blocking(&client)?;
+//! ```
+
+use crate::{
+    api::Version,
+    capabilities::EngineCapabilities,
+    client::{VoiceVoxClient, DEFAULT_BASE_URL, READY_POLL_INTERVAL},
+    endpoint::Endpoint,
+    error::APIError,
+    transport::{HttpRequest, HttpResponse, Method, TransportConfig},
+    version::EngineVersion,
+};
+use std::time::{Duration, Instant};
+
+/// reqwestの同期クライアントを使う`VoiceVoxClient`.
+pub type BlockingClient = VoiceVoxClient<crate::reqwest_api::ReqwestBlockingTransport>;
+
+/// リクエストを同期的に送信するバックエンド.
+pub trait BlockingTransport: Sized {
+    /// 設定からバックエンドを作成する.
+    fn new(config: &TransportConfig) -> Result<Self, APIError>;
+
+    /// リクエストを送信してレスポンスボディを全て読み取る.
+    ///
+    /// ステータスコードによらずレスポンスを返し,通信自体が失敗した場合のみエラーを返します.
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, APIError>;
+}
+
+impl BlockingClient {
+    /// 既定の設定で`base_url`に接続する同期クライアントを作成する.
+    pub fn new_blocking(base_url: &str) -> Result<Self, APIError> {
+        VoiceVoxClient::builder(base_url).build_blocking()
+    }
+}
+
+impl Default for BlockingClient {
+    fn default() -> Self {
+        Self::new_blocking(DEFAULT_BASE_URL).unwrap()
+    }
+}
+
+impl<T: BlockingTransport> VoiceVoxClient<T> {
+    /// エンドポイントを同期的に呼び出す.
+    ///
+    /// 各リクエスト構造体の`call_blocking`と同じです.
+    pub fn send_blocking<E: Endpoint>(&self, endpoint: E) -> Result<E::Response, E::Error> {
+        self.check(&endpoint)?;
+        let request = self.http_request(endpoint.request()?)?;
+        let response = self.execute_blocking_with(request, E::IDEMPOTENT)?;
+        endpoint.response(response)
+    }
+
+    /// `execute`と同じ規則で再試行しながら同期的に送信する.
+    pub fn execute_blocking(&self, request: HttpRequest) -> Result<HttpResponse, APIError> {
+        let idempotent = request.method != Method::Post;
+        self.execute_blocking_with(request, idempotent)
+    }
+
+    fn execute_blocking_with(
+        &self,
+        request: HttpRequest,
+        idempotent: bool,
+    ) -> Result<HttpResponse, APIError> {
+        let mut attempt = 0;
+        loop {
+            let result = self.transport().execute(request.clone());
+            match self.retry_policy().next_delay(attempt, &result, idempotent) {
+                Some(delay) => std::thread::sleep(delay),
+                None => return result,
+            }
+            attempt += 1;
+        }
+    }
+
+    /// `wait_until_ready`と同じく`/version`が応答するまで同期的に問い合わせる.
+    pub fn wait_until_ready_blocking(&self, timeout: Duration) -> Result<EngineVersion, APIError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match Version::detect_blocking(self) {
+                Ok(version) => return Ok(version),
+                Err(e) if Instant::now() + READY_POLL_INTERVAL > deadline => return Err(e),
+                Err(_) => std::thread::sleep(READY_POLL_INTERVAL),
+            }
+        }
+    }
+
+    /// `negotiate`と同じリクエストを同期的に送信する.
+    pub fn negotiate_blocking(self) -> Result<Self, APIError> {
+        let capabilities = EngineCapabilities::detect_blocking(&self)?;
+        Ok(self.with_capabilities(capabilities))
+    }
+}
@@ -0,0 +1,143 @@
+//! 送信中のリクエストの中断.
+//!
+//! `CancellationToken::cancel`を呼ぶと,トークンを渡したリクエストはレスポンスを待たずに
+//! 接続を閉じて`APIError::Cancelled`を返します.
+//! `CancellableSynthesis`は接続が閉じられるとエンジン側の合成も停止します.
+//!
+//! ```ignore
+//! let token = CancellationToken::new();
+//! let stop = token.clone();
+//! // Stopボタンなど
+//! stop.cancel();
+//! let result = synthesis.call_cancellable(&client, &token).await;
+//! ```
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::{pin, Pin},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    wakers: Mutex<Wakers>,
+}
+
+/// 中断を待っているタスク.
+#[derive(Debug, Default)]
+struct Wakers {
+    next_id: u64,
+    wakers: HashMap<u64, Waker>,
+}
+
+/// リクエストを中断するためのトークン.
+///
+/// 複製したトークンは同じ状態を共有します.一度中断したトークンは元に戻りません.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// このトークンを渡した全てのリクエストを中断する.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        let wakers = std::mem::take(&mut self.wakers().wakers);
+        wakers.into_values().for_each(Waker::wake);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 中断されるまで待つ.
+    ///
+    /// 中断される前に破棄した場合は待っていたタスクの登録を取り消すので,
+    /// 長く使うトークンでも終わったリクエストの`Waker`は残りません.
+    pub fn cancelled(&self) -> Cancelled<'_> {
+        Cancelled {
+            token: self,
+            id: None,
+        }
+    }
+
+    /// `future`が完了するか中断されるまで待つ.
+    ///
+    /// 中断された場合は`future`を破棄して`None`を返します.
+    pub async fn run<F: Future>(&self, future: F) -> Option<F::Output> {
+        let mut future = pin!(future);
+        let mut cancelled = self.cancelled();
+        std::future::poll_fn(|cx| {
+            if Pin::new(&mut cancelled).poll(cx).is_ready() {
+                return Poll::Ready(None);
+            }
+            future.as_mut().poll(cx).map(Some)
+        })
+        .await
+    }
+
+    fn wakers(&self) -> std::sync::MutexGuard<'_, Wakers> {
+        self.inner.wakers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// `CancellationToken::cancelled`が返す,中断されると完了する`Future`.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Cancelled<'a> {
+    token: &'a CancellationToken,
+    /// 登録した`Waker`のID.
+    id: Option<u64>,
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        let mut wakers = self.token.wakers();
+        let id = match self.id {
+            Some(id) => id,
+            None => {
+                let id = wakers.next_id;
+                wakers.next_id += 1;
+                id
+            }
+        };
+        match wakers.wakers.get_mut(&id) {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            Some(waker) => waker.clone_from(cx.waker()),
+            // 未登録か,`cancel`で取り出された.
+            None => {
+                wakers.wakers.insert(id, cx.waker().clone());
+            }
+        }
+        drop(wakers);
+        self.id = Some(id);
+        // 登録している間に中断された場合.
+        match self.token.is_cancelled() {
+            true => Poll::Ready(()),
+            false => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for Cancelled<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.token.wakers().wakers.remove(&id);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! 接続しているエンジンが対応している機能.
+//!
+//! `EngineCapabilities`をクライアントに設定すると,
+//! 対応していない機能を使うリクエストは送信する前に`APIError::Unsupported`になります.
+//!
+//! ```ignore
+//! let client = VoiceVoxClient::new("http://localhost:50021")?.negotiate().await?;
+//! ```
+
+use crate::{
+    api::{EngineManifest, Version},
+    api_schema::{self, AudioQuery, SupportedFeatures},
+    client::VoiceVoxClient,
+    endpoint::Endpoint,
+    error::APIError,
+    transport::Transport,
+    version::EngineVersion,
+};
+use std::fmt;
+
+/// リクエストが必要とする機能.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// 新しいエンジンで追加されたエンドポイント.
+    Endpoint {
+        name: &'static str,
+        since: EngineVersion,
+    },
+    AdjustMoraPitch,
+    AdjustPhonemeLength,
+    AdjustSpeedScale,
+    AdjustPitchScale,
+    AdjustIntonationScale,
+    AdjustVolumeScale,
+    InterrogativeUpspeak,
+    SynthesisMorphing,
+}
+
+impl Feature {
+    /// クエリのうち既定値から変更されている項目に必要な機能.
+    pub fn of_query(audio_query: &AudioQuery) -> Vec<Feature> {
+        [
+            (Feature::AdjustSpeedScale, audio_query.speedScale != 1.0),
+            (Feature::AdjustPitchScale, audio_query.pitchScale != 0.0),
+            (
+                Feature::AdjustIntonationScale,
+                audio_query.intonationScale != 1.0,
+            ),
+            (Feature::AdjustVolumeScale, audio_query.volumeScale != 1.0),
+        ]
+        .into_iter()
+        .filter_map(|(feature, used)| used.then_some(feature))
+        .collect()
+    }
+
+    /// エンドポイントが追加されたバージョン.
+    pub fn endpoint<E: Endpoint>() -> Option<Feature> {
+        let name = std::any::type_name::<E>();
+        E::SINCE.map(|since| Feature::Endpoint {
+            name: name.rsplit("::").next().unwrap_or(name),
+            since,
+        })
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Feature::Endpoint { name, since } => write!(f, "{name} (added in {since})"),
+            Feature::AdjustMoraPitch => f.write_str("adjusting mora pitch"),
+            Feature::AdjustPhonemeLength => f.write_str("adjusting phoneme length"),
+            Feature::AdjustSpeedScale => f.write_str("adjusting speed scale"),
+            Feature::AdjustPitchScale => f.write_str("adjusting pitch scale"),
+            Feature::AdjustIntonationScale => f.write_str("adjusting intonation scale"),
+            Feature::AdjustVolumeScale => f.write_str("adjusting volume scale"),
+            Feature::InterrogativeUpspeak => f.write_str("interrogative upspeak"),
+            Feature::SynthesisMorphing => f.write_str("synthesis morphing"),
+        }
+    }
+}
+
+/// エンジンのバージョンとマニフェストから求めた対応している機能.
+#[derive(Debug, Clone)]
+pub struct EngineCapabilities {
+    pub version: EngineVersion,
+    pub features: SupportedFeatures,
+    /// マニフェストのエンジンのUUID. マニフェストから求めていない場合は`None`.
+    pub engine_uuid: Option<String>,
+}
+
+impl EngineCapabilities {
+    pub fn new(version: EngineVersion, features: SupportedFeatures) -> Self {
+        Self {
+            version,
+            features,
+            engine_uuid: None,
+        }
+    }
+
+    pub fn from_manifest(manifest: &api_schema::EngineManifest, version: EngineVersion) -> Self {
+        Self {
+            engine_uuid: Some(manifest.uuid.clone()),
+            ..Self::new(version, manifest.supported_features.clone())
+        }
+    }
+
+    /// エンジンのバージョンとマニフェストを取得する.
+    pub async fn detect<T: Transport>(client: &VoiceVoxClient<T>) -> Result<Self, APIError> {
+        let version = Version::detect(client).await?;
+        let manifest = client.send(EngineManifest).await?;
+        Ok(Self::from_manifest(&manifest, version))
+    }
+
+    /// `detect`と同じリクエストを同期的に送信する.
+    #[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+    pub fn detect_blocking<T: crate::blocking::BlockingTransport>(
+        client: &VoiceVoxClient<T>,
+    ) -> Result<Self, APIError> {
+        let version = Version::detect_blocking(client)?;
+        let manifest = client.send_blocking(EngineManifest)?;
+        Ok(Self::from_manifest(&manifest, version))
+    }
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        let features = &self.features;
+        match feature {
+            Feature::Endpoint { since, .. } => self.version >= since,
+            Feature::AdjustMoraPitch => features.adjust_mora_pitch,
+            Feature::AdjustPhonemeLength => features.adjust_phoneme_length,
+            Feature::AdjustSpeedScale => features.adjust_speed_scale,
+            Feature::AdjustPitchScale => features.adjust_pitch_scale,
+            Feature::AdjustIntonationScale => features.adjust_intonation_scale,
+            Feature::AdjustVolumeScale => features.adjust_volume_scale,
+            Feature::InterrogativeUpspeak => features.interrogative_upspeak,
+            Feature::SynthesisMorphing => features.synthesis_morphing,
+        }
+    }
+
+    /// リクエストに必要な機能に対応しているかを確かめる.
+    pub fn check<E: Endpoint>(&self, endpoint: &E) -> Result<(), APIError> {
+        match Feature::endpoint::<E>()
+            .into_iter()
+            .chain(endpoint.features())
+            .find(|feature| !self.supports(*feature))
+        {
+            Some(feature) => Err(APIError::Unsupported {
+                feature,
+                version: self.version,
+            }),
+            None => Ok(()),
+        }
+    }
+}
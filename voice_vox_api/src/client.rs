@@ -0,0 +1,340 @@
+//! 接続先エンジンとクライアント.
+
+use crate::{
+    cancel::CancellationToken,
+    capabilities::EngineCapabilities,
+    endpoint::{Endpoint, EndpointRequest},
+    error::APIError,
+    retry::RetryPolicy,
+    transport::{HttpRequest, HttpResponse, Method, Transport, TransportConfig},
+    version::EngineVersion,
+};
+use std::time::Duration;
+
+/// 接続先を指定しなかった場合のエンジンのアドレス.
+pub const DEFAULT_BASE_URL: &str = "http://localhost:50021";
+
+/// `wait_until_ready`が問い合わせる間隔.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) const READY_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 型引数を省略した場合のバックエンド.
+///
+/// `backend_reqwest`が有効な場合はreqwest,そうでなければsurfを使います.
+#[cfg(feature = "backend_reqwest")]
+pub type DefaultTransport = crate::reqwest_api::ReqwestTransport;
+#[cfg(all(not(feature = "backend_reqwest"), feature = "backend_surf"))]
+pub type DefaultTransport = crate::surf_api::SurfTransport;
+
+/// VoiceVox engineへの接続.
+///
+/// ベースURLと全てのリクエストに付与するヘッダ,リクエストを送信する`Transport`を保持します.
+/// 複数のエンジンを扱う場合はエンジンごとに作成し,`registry::EngineRegistry`に登録してください.
+#[derive(Debug, Clone)]
+pub struct VoiceVoxClient<T = DefaultTransport> {
+    base_url: String,
+    default_headers: Vec<(String, String)>,
+    transport: T,
+    capabilities: Option<EngineCapabilities>,
+    retry: RetryPolicy,
+}
+
+impl VoiceVoxClient {
+    /// 既定の設定で`base_url`に接続するクライアントを作成する.
+    pub fn new(base_url: &str) -> Result<Self, APIError> {
+        Self::builder(base_url).build()
+    }
+
+    pub fn builder(base_url: &str) -> VoiceVoxClientBuilder {
+        VoiceVoxClientBuilder::new(base_url)
+    }
+}
+
+impl Default for VoiceVoxClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL).unwrap()
+    }
+}
+
+impl<T: Transport> VoiceVoxClient<T> {
+    /// エンドポイントを呼び出す.
+    ///
+    /// 各リクエスト構造体の`call`と同じです.
+    pub async fn send<E: Endpoint>(&self, endpoint: E) -> Result<E::Response, E::Error> {
+        self.check(&endpoint)?;
+        let request = self.http_request(endpoint.request()?)?;
+        let response = self.execute_with(request, E::IDEMPOTENT).await?;
+        endpoint.response(response)
+    }
+
+    /// 再試行の設定に従ってリクエストを送信する.
+    ///
+    /// `POST`は冪等でないものとして扱います.再試行しても失敗した場合は最後の結果を返します.
+    pub async fn execute(&self, request: HttpRequest) -> Result<HttpResponse, APIError> {
+        let idempotent = request.method != Method::Post;
+        self.execute_with(request, idempotent).await
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn execute_with(
+        &self,
+        request: HttpRequest,
+        idempotent: bool,
+    ) -> Result<HttpResponse, APIError> {
+        let mut attempt = 0;
+        loop {
+            let result = self.transport.execute(request.clone()).await;
+            match self.retry.next_delay(attempt, &result, idempotent) {
+                Some(delay) => self.transport.sleep(delay).await,
+                None => return result,
+            }
+            attempt += 1;
+        }
+    }
+
+    /// wasmでは待つためのタイマーがないので再試行しない.
+    #[cfg(target_arch = "wasm32")]
+    async fn execute_with(
+        &self,
+        request: HttpRequest,
+        _idempotent: bool,
+    ) -> Result<HttpResponse, APIError> {
+        self.transport.execute(request).await
+    }
+
+    /// `/version`が応答するまで問い合わせる.
+    ///
+    /// 起動したばかりのエンジンの準備ができるまで待つために使います.
+    /// `timeout`を過ぎても応答しない場合は最後のエラーを返します.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn wait_until_ready(&self, timeout: Duration) -> Result<EngineVersion, APIError> {
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            match crate::api::Version::detect(self).await {
+                Ok(version) => return Ok(version),
+                Err(e) if std::time::Instant::now() + READY_POLL_INTERVAL > deadline => {
+                    return Err(e)
+                }
+                Err(_) => self.transport.sleep(READY_POLL_INTERVAL).await,
+            }
+        }
+    }
+
+    /// `token`で中断できる`send`.
+    ///
+    /// 中断するとリクエストを破棄して接続を閉じ,`APIError::Cancelled`を返します.
+    pub async fn send_cancellable<E: Endpoint>(
+        &self,
+        endpoint: E,
+        token: &CancellationToken,
+    ) -> Result<E::Response, E::Error> {
+        if token.is_cancelled() {
+            return Err(APIError::Cancelled.into());
+        }
+        token
+            .run(self.send(endpoint))
+            .await
+            .unwrap_or_else(|| Err(APIError::Cancelled.into()))
+    }
+
+    /// エンジンが対応している機能を取得して設定する.
+    pub async fn negotiate(self) -> Result<Self, APIError> {
+        let capabilities = EngineCapabilities::detect(&self).await?;
+        Ok(self.with_capabilities(capabilities))
+    }
+}
+
+impl<T> VoiceVoxClient<T> {
+    /// 送信する前に確かめる機能を設定する.
+    pub fn with_capabilities(mut self, capabilities: EngineCapabilities) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
+    pub fn capabilities(&self) -> Option<&EngineCapabilities> {
+        self.capabilities.as_ref()
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// 設定されている機能でリクエストを送信できるかを確かめる.
+    pub fn check<E: Endpoint>(&self, endpoint: &E) -> Result<(), APIError> {
+        match &self.capabilities {
+            Some(capabilities) => capabilities.check(endpoint),
+            None => Ok(()),
+        }
+    }
+
+    /// 末尾の`/`を含まないベースURL.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// 相対パスのリクエストをこのクライアントの接続先に向けたリクエストにする.
+    pub fn http_request(&self, request: EndpointRequest) -> Result<HttpRequest, APIError> {
+        let url = format!("{}{}", self.base_url, request.path);
+        let url = if request.query.is_empty() {
+            url::Url::parse(&url)
+        } else {
+            url::Url::parse_with_params(&url, &request.query)
+        }
+        .map_err(|e| APIError::InvalidRequest(format!("{url}: {e}")))?;
+        let mut headers = self.default_headers.clone();
+        if request.body.is_some() {
+            headers.push(("Content-Type".to_owned(), request.content_type.to_owned()));
+        }
+        Ok(HttpRequest {
+            method: request.method,
+            url,
+            headers,
+            body: request.body,
+        })
+    }
+}
+
+/// `VoiceVoxClient`の設定.
+///
+/// ベースURLにはスキーム,ホスト,ポートに加えてパスのプレフィックスを含められます.
+/// スキームを省略した場合は`http://`として扱います.
+///
+/// ```ignore
+/// let client = VoiceVoxClient::builder("https://example.com/voicevox")
+///     .timeout(std::time::Duration::from_secs(30))
+///     .default_header("Authorization", "Bearer token")
+///     .build()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct VoiceVoxClientBuilder {
+    base_url: String,
+    transport: TransportConfig,
+    default_headers: Vec<(String, String)>,
+    retry: RetryPolicy,
+}
+
+impl VoiceVoxClientBuilder {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_owned(),
+            transport: TransportConfig::default(),
+            default_headers: Vec::new(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// リクエスト全体のタイムアウト.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.transport.timeout = Some(timeout);
+        self
+    }
+
+    /// 接続確立までのタイムアウト.
+    ///
+    /// surfバックエンドでは無視されます.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.transport.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// 接続エラーやタイムアウトなどで失敗したリクエストを再試行する.
+    ///
+    /// `timeout`と`connect_timeout`は1回の送信ごとに適用されます.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// 全てのリクエストに付与するヘッダ.
+    pub fn default_header(mut self, name: &str, value: &str) -> Self {
+        self.default_headers
+            .push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// 既定のバックエンドでクライアントを作成する.
+    pub fn build(self) -> Result<VoiceVoxClient, APIError> {
+        self.build_with()
+    }
+
+    /// バックエンドを指定してクライアントを作成する.
+    pub fn build_with<T: Transport>(self) -> Result<VoiceVoxClient<T>, APIError> {
+        let transport = T::new(&self.transport)?;
+        self.build_with_transport(transport)
+    }
+
+    /// 非同期ランタイムを使わずに呼び出すクライアントを作成する.
+    #[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+    pub fn build_blocking(self) -> Result<crate::blocking::BlockingClient, APIError> {
+        self.build_blocking_with()
+    }
+
+    /// 同期のバックエンドを指定してクライアントを作成する.
+    #[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+    pub fn build_blocking_with<T: crate::blocking::BlockingTransport>(
+        self,
+    ) -> Result<VoiceVoxClient<T>, APIError> {
+        let transport = T::new(&self.transport)?;
+        self.build_with_transport(transport)
+    }
+
+    /// 作成済みのバックエンドを使ってクライアントを作成する.
+    ///
+    /// タイムアウトの設定は使われません.
+    pub fn build_with_transport<T>(self, transport: T) -> Result<VoiceVoxClient<T>, APIError> {
+        let base_url = self
+            .normalized_base_url()
+            .map_err(APIError::InvalidConfig)?;
+        for (name, value) in &self.default_headers {
+            let valid_name = !name.is_empty()
+                && name
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b));
+            if !valid_name || value.bytes().any(|b| b == b'\r' || b == b'\n') {
+                return Err(APIError::InvalidConfig(format!(
+                    "invalid header {name}: {value}"
+                )));
+            }
+        }
+        Ok(VoiceVoxClient {
+            base_url,
+            default_headers: self.default_headers,
+            transport,
+            capabilities: None,
+            retry: self.retry,
+        })
+    }
+
+    /// 末尾の`/`を取り除いたベースURLを返す.
+    fn normalized_base_url(&self) -> Result<String, String> {
+        let base_url = self.base_url.trim();
+        let base_url = if base_url.contains("://") {
+            base_url.to_owned()
+        } else {
+            format!("http://{base_url}")
+        };
+        let url = url::Url::parse(&base_url).map_err(|e| format!("{base_url}: {e}"))?;
+        match url.scheme() {
+            "http" | "https" => {}
+            scheme => return Err(format!("{base_url}: unsupported scheme {scheme}")),
+        }
+        match url.host_str() {
+            Some(host) if !host.is_empty() => {}
+            _ => return Err(format!("{base_url}: missing host")),
+        }
+        if url.query().is_some() || url.fragment().is_some() {
+            return Err(format!("{base_url}: query and fragment are not allowed"));
+        }
+        Ok(url.as_str().trim_end_matches('/').to_owned())
+    }
+}
+
+impl Default for VoiceVoxClientBuilder {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL)
+    }
+}
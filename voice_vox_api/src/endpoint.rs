@@ -0,0 +1,119 @@
+//! バックエンドに依存しないエンドポイントの定義.
+
+use crate::{
+    capabilities::Feature,
+    error::APIError,
+    transport::{HttpResponse, Method},
+    version::EngineVersion,
+};
+use serde::Serialize;
+
+/// APIの1つのエンドポイント.
+///
+/// メソッド,パス,クエリ,ボディの組み立てとレスポンスの解釈だけを定義し,
+/// 送信は`VoiceVoxClient`が`Transport`を使って行います.
+pub trait Endpoint {
+    type Response;
+    type Error: From<APIError>;
+
+    /// このエンドポイントが追加されたエンジンのバージョン.
+    ///
+    /// `None`は対応している全てのバージョンで使えることを表します.
+    const SINCE: Option<EngineVersion> = None;
+
+    /// 同じリクエストを2回送ってもエンジンの状態が1回の場合と変わらない.
+    ///
+    /// `false`のエンドポイントはタイムアウトやエラーのステータスでは再試行しません.
+    /// `RetryPolicy::retry_non_idempotent`を参照してください.
+    const IDEMPOTENT: bool = false;
+
+    fn request(&self) -> Result<EndpointRequest, Self::Error>;
+
+    /// このリクエストが使うエンジンの機能.
+    ///
+    /// クライアントに`EngineCapabilities`が設定されている場合は送信する前に確かめます.
+    fn features(&self) -> Vec<Feature> {
+        Vec::new()
+    }
+
+    fn response(self, response: HttpResponse) -> Result<Self::Response, Self::Error>;
+}
+
+/// ベースURLからの相対パスで表したリクエスト.
+#[derive(Debug, Clone)]
+pub struct EndpointRequest {
+    pub method: Method,
+    /// `/`から始まるパス.
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+    /// ボディの`Content-Type`.
+    pub content_type: &'static str,
+}
+
+impl EndpointRequest {
+    pub fn new(method: Method, path: impl Into<String>) -> Self {
+        Self {
+            method,
+            path: path.into(),
+            query: Vec::new(),
+            body: None,
+            content_type: "application/json",
+        }
+    }
+
+    pub fn get(path: impl Into<String>) -> Self {
+        Self::new(Method::Get, path)
+    }
+
+    pub fn post(path: impl Into<String>) -> Self {
+        Self::new(Method::Post, path)
+    }
+
+    pub fn put(path: impl Into<String>) -> Self {
+        Self::new(Method::Put, path)
+    }
+
+    pub fn delete(path: impl Into<String>) -> Self {
+        Self::new(Method::Delete, path)
+    }
+
+    pub fn query(mut self, key: &str, value: impl ToString) -> Self {
+        self.query.push((key.to_owned(), value.to_string()));
+        self
+    }
+
+    /// 値がある場合だけクエリに追加する.
+    pub fn query_opt(self, key: &str, value: Option<impl ToString>) -> Self {
+        match value {
+            Some(value) => self.query(key, value),
+            None => self,
+        }
+    }
+
+    /// `core_version`が指定されている場合だけクエリに追加する.
+    pub fn core_version(self, core_version: &Option<String>) -> Self {
+        self.query_opt("core_version", core_version.as_ref())
+    }
+
+    pub fn json<B: Serialize + ?Sized>(mut self, body: &B) -> Result<Self, APIError> {
+        self.body =
+            Some(serde_json::to_vec(body).map_err(|e| APIError::InvalidRequest(e.to_string()))?);
+        Ok(self)
+    }
+
+    /// `application/x-www-form-urlencoded`のボディ.
+    pub fn form<'a>(self, fields: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(fields)
+            .finish();
+        self.bytes(body.into_bytes(), "application/x-www-form-urlencoded")
+    }
+
+    /// そのまま送るボディ.
+    pub fn bytes(mut self, body: Vec<u8>, content_type: &'static str) -> Self {
+        self.body = Some(body);
+        self.content_type = content_type;
+        self
+    }
+}
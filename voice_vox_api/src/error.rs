@@ -0,0 +1,150 @@
+//! API呼び出しのエラー.
+//!
+//! どのバックエンドでも同じエラー型を返します.
+
+use crate::{
+    api_schema::{HttpValidationError, StyleId},
+    capabilities::Feature,
+    kana::KanaError,
+    version::EngineVersion,
+};
+use std::{error::Error, fmt, sync::Arc};
+
+/// HTTPクライアントが返したエラー.
+pub type TransportError = Arc<dyn Error + Send + Sync>;
+
+/// API呼び出しが失敗した理由.
+///
+/// エンジンから応答があった場合はステータスコードとレスポンスボディを保持します.
+#[derive(Debug, Clone)]
+pub enum APIError {
+    /// 422 リクエストのパラメータが不正.
+    Validation(HttpValidationError),
+    /// 想定していないステータスコードが返された.
+    Status { status: u16, body: String },
+    /// レスポンスボディを解釈できなかった.
+    Decode {
+        status: u16,
+        body: String,
+        message: String,
+    },
+    /// エンジンに接続できなかった.
+    Connect(TransportError),
+    /// 応答が時間内に返らなかった.
+    Timeout(TransportError),
+    /// その他の通信エラー.
+    Transport(TransportError),
+    /// ベースURLやヘッダなどクライアントの設定が不正.
+    InvalidConfig(String),
+    /// リクエストを組み立てられなかった.
+    InvalidRequest(String),
+    /// 接続しているエンジンが対応していない機能を使おうとした.
+    Unsupported {
+        feature: Feature,
+        version: EngineVersion,
+    },
+    /// 登録されていないエンジンを指定した.
+    UnknownEngine(String),
+    /// どのエンジンにもないスタイルIDを指定した.
+    UnknownStyle(StyleId),
+    /// 複数のエンジンが同じIDのスタイルを持っている.
+    AmbiguousStyle {
+        style_id: StyleId,
+        engines: Vec<String>,
+    },
+    /// `CancellationToken`で中断された.
+    Cancelled,
+    /// エンジンが返したzipを展開できなかった.
+    InvalidArchive(String),
+}
+
+impl APIError {
+    /// エンジンが返したステータスコード.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            APIError::Validation(_) => Some(422),
+            APIError::Status { status, .. } | APIError::Decode { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// エンジンが返したレスポンスボディ.
+    pub fn body(&self) -> Option<&str> {
+        match self {
+            APIError::Status { body, .. } | APIError::Decode { body, .. } => Some(body),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for APIError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            APIError::Validation(e) => write!(f, "validation error: {e}"),
+            APIError::Status { status, body } => {
+                write!(f, "unexpected status {status}: {body}")
+            }
+            APIError::Decode {
+                status, message, ..
+            } => write!(f, "failed to decode response (status {status}): {message}"),
+            APIError::Connect(e) => write!(f, "failed to connect to engine: {e}"),
+            APIError::Timeout(e) => write!(f, "request timed out: {e}"),
+            APIError::Transport(e) => write!(f, "transport error: {e}"),
+            APIError::InvalidConfig(e) => write!(f, "invalid client config: {e}"),
+            APIError::InvalidRequest(e) => write!(f, "invalid request: {e}"),
+            APIError::Unsupported { feature, version } => {
+                write!(f, "engine {version} does not support {feature}")
+            }
+            APIError::UnknownEngine(engine_id) => write!(f, "unknown engine {engine_id}"),
+            APIError::UnknownStyle(style_id) => write!(f, "no engine has style {style_id}"),
+            APIError::AmbiguousStyle { style_id, engines } => write!(
+                f,
+                "style {style_id} is provided by several engines: {}",
+                engines.join(", ")
+            ),
+            APIError::Cancelled => f.write_str("request was cancelled"),
+            APIError::InvalidArchive(e) => write!(f, "invalid zip archive: {e}"),
+        }
+    }
+}
+
+impl Error for APIError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            APIError::Connect(e) | APIError::Timeout(e) | APIError::Transport(e) => Some(&**e),
+            _ => None,
+        }
+    }
+}
+
+/// `AccentPhrases`のエラー.
+#[derive(Debug, Clone)]
+pub enum AccentPhrasesErrors {
+    /// 400 読み仮名の解析に失敗した.
+    KanaParseError(KanaError),
+    ApiError(APIError),
+}
+
+impl From<APIError> for AccentPhrasesErrors {
+    fn from(e: APIError) -> Self {
+        AccentPhrasesErrors::ApiError(e)
+    }
+}
+
+impl fmt::Display for AccentPhrasesErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccentPhrasesErrors::KanaParseError(e) => write!(f, "failed to parse kana: {e}"),
+            AccentPhrasesErrors::ApiError(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for AccentPhrasesErrors {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AccentPhrasesErrors::KanaParseError(e) => Some(e),
+            AccentPhrasesErrors::ApiError(e) => Some(e),
+        }
+    }
+}
@@ -0,0 +1,454 @@
+//! AquesTalkライクな記法の読み仮名.
+//!
+//! エンジンの`kana_parser`と同じ規則でアクセント句と読み仮名を相互に変換します.
+//!
+//! * アクセント句は`/`(無音なし)か`、`(無音あり)で区切る.
+//! * `'`はアクセント位置を表し,直前のモーラにアクセントがある.
+//! * `_`を付けたモーラは無声化する.
+//! * アクセント句末の`？`は疑問文を表す.
+
+use crate::api_schema::{AccentPhrase, ErrorName, Mora, ParseKanaBadRequest, Phoneme};
+use std::{fmt, ops::Range};
+
+/// 無音ありの区切り.
+pub const PAUSE_DELIMITER: char = '、';
+/// 無音なしの区切り.
+pub const NOPAUSE_DELIMITER: char = '/';
+/// 無声化.
+pub const UNVOICE_SYMBOL: char = '_';
+/// アクセント位置.
+pub const ACCENT_SYMBOL: char = '\'';
+/// 疑問文.
+pub const WIDE_INTERROGATION_MARK: char = '？';
+
+/// 読み仮名と子音,母音の対応.
+///
+/// 子音がない場合は空文字列です.
+const MORAS: [(&str, &str, &str); 162] = [
+    ("ヴォ", "v", "o"),
+    ("ヴェ", "v", "e"),
+    ("ヴィ", "v", "i"),
+    ("ヴァ", "v", "a"),
+    ("ヴ", "v", "u"),
+    ("ン", "", "N"),
+    ("ワ", "w", "a"),
+    ("ロ", "r", "o"),
+    ("レ", "r", "e"),
+    ("ル", "r", "u"),
+    ("リョ", "ry", "o"),
+    ("リュ", "ry", "u"),
+    ("リャ", "ry", "a"),
+    ("リェ", "ry", "e"),
+    ("リ", "r", "i"),
+    ("ラ", "r", "a"),
+    ("ヨ", "y", "o"),
+    ("ユ", "y", "u"),
+    ("ヤ", "y", "a"),
+    ("モ", "m", "o"),
+    ("メ", "m", "e"),
+    ("ム", "m", "u"),
+    ("ミョ", "my", "o"),
+    ("ミュ", "my", "u"),
+    ("ミャ", "my", "a"),
+    ("ミェ", "my", "e"),
+    ("ミ", "m", "i"),
+    ("マ", "m", "a"),
+    ("ポ", "p", "o"),
+    ("ボ", "b", "o"),
+    ("ホ", "h", "o"),
+    ("ペ", "p", "e"),
+    ("ベ", "b", "e"),
+    ("ヘ", "h", "e"),
+    ("プ", "p", "u"),
+    ("ブ", "b", "u"),
+    ("フォ", "f", "o"),
+    ("フェ", "f", "e"),
+    ("フィ", "f", "i"),
+    ("ファ", "f", "a"),
+    ("フ", "f", "u"),
+    ("ピョ", "py", "o"),
+    ("ピュ", "py", "u"),
+    ("ピャ", "py", "a"),
+    ("ピェ", "py", "e"),
+    ("ピ", "p", "i"),
+    ("ビョ", "by", "o"),
+    ("ビュ", "by", "u"),
+    ("ビャ", "by", "a"),
+    ("ビェ", "by", "e"),
+    ("ビ", "b", "i"),
+    ("ヒョ", "hy", "o"),
+    ("ヒュ", "hy", "u"),
+    ("ヒャ", "hy", "a"),
+    ("ヒェ", "hy", "e"),
+    ("ヒ", "h", "i"),
+    ("パ", "p", "a"),
+    ("バ", "b", "a"),
+    ("ハ", "h", "a"),
+    ("ノ", "n", "o"),
+    ("ネ", "n", "e"),
+    ("ヌ", "n", "u"),
+    ("ニョ", "ny", "o"),
+    ("ニュ", "ny", "u"),
+    ("ニャ", "ny", "a"),
+    ("ニェ", "ny", "e"),
+    ("ニ", "n", "i"),
+    ("ナ", "n", "a"),
+    ("ドゥ", "d", "u"),
+    ("ド", "d", "o"),
+    ("トゥ", "t", "u"),
+    ("ト", "t", "o"),
+    ("デョ", "dy", "o"),
+    ("デュ", "dy", "u"),
+    ("デャ", "dy", "a"),
+    ("ディ", "d", "i"),
+    ("デ", "d", "e"),
+    ("テョ", "ty", "o"),
+    ("テュ", "ty", "u"),
+    ("テャ", "ty", "a"),
+    ("ティ", "t", "i"),
+    ("テ", "t", "e"),
+    ("ツォ", "ts", "o"),
+    ("ツェ", "ts", "e"),
+    ("ツィ", "ts", "i"),
+    ("ツァ", "ts", "a"),
+    ("ツ", "ts", "u"),
+    ("ッ", "", "cl"),
+    ("チョ", "ch", "o"),
+    ("チュ", "ch", "u"),
+    ("チャ", "ch", "a"),
+    ("チェ", "ch", "e"),
+    ("チ", "ch", "i"),
+    ("ダ", "d", "a"),
+    ("タ", "t", "a"),
+    ("ゾ", "z", "o"),
+    ("ソ", "s", "o"),
+    ("ゼ", "z", "e"),
+    ("セ", "s", "e"),
+    ("ズィ", "z", "i"),
+    ("ズ", "z", "u"),
+    ("スィ", "s", "i"),
+    ("ス", "s", "u"),
+    ("ジョ", "j", "o"),
+    ("ジュ", "j", "u"),
+    ("ジャ", "j", "a"),
+    ("ジェ", "j", "e"),
+    ("ジ", "j", "i"),
+    ("ショ", "sh", "o"),
+    ("シュ", "sh", "u"),
+    ("シャ", "sh", "a"),
+    ("シェ", "sh", "e"),
+    ("シ", "sh", "i"),
+    ("ザ", "z", "a"),
+    ("サ", "s", "a"),
+    ("ゴ", "g", "o"),
+    ("コ", "k", "o"),
+    ("ゲ", "g", "e"),
+    ("ケ", "k", "e"),
+    ("グヮ", "gw", "a"),
+    ("グ", "g", "u"),
+    ("クヮ", "kw", "a"),
+    ("ク", "k", "u"),
+    ("ギョ", "gy", "o"),
+    ("ギュ", "gy", "u"),
+    ("ギャ", "gy", "a"),
+    ("ギェ", "gy", "e"),
+    ("ギ", "g", "i"),
+    ("キョ", "ky", "o"),
+    ("キュ", "ky", "u"),
+    ("キャ", "ky", "a"),
+    ("キェ", "ky", "e"),
+    ("キ", "k", "i"),
+    ("ガ", "g", "a"),
+    ("カ", "k", "a"),
+    ("オ", "", "o"),
+    ("エ", "", "e"),
+    ("ウォ", "w", "o"),
+    ("ウェ", "w", "e"),
+    ("ウィ", "w", "i"),
+    ("ウ", "", "u"),
+    ("イェ", "y", "e"),
+    ("イ", "", "i"),
+    ("ア", "", "a"),
+    ("ヴョ", "by", "o"),
+    ("ヴュ", "by", "u"),
+    ("ヴャ", "by", "a"),
+    ("ヲ", "", "o"),
+    ("ヱ", "", "e"),
+    ("ヰ", "", "i"),
+    ("ヮ", "w", "a"),
+    ("ョ", "y", "o"),
+    ("ュ", "y", "u"),
+    ("ヅ", "z", "u"),
+    ("ヂ", "j", "i"),
+    ("ヶ", "k", "e"),
+    ("ャ", "y", "a"),
+    ("ォ", "", "o"),
+    ("ェ", "", "e"),
+    ("ゥ", "", "u"),
+    ("ィ", "", "i"),
+    ("ァ", "", "a"),
+];
+
+/// 読み仮名を解析できなかった.
+///
+/// `text`と`position`はエンジンが返す`error_args`に対応します.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KanaError {
+    pub name: ErrorName,
+    /// 原因になった文字列.
+    pub text: Option<String>,
+    /// 空だったアクセント句の番号(1から数える).
+    pub position: Option<usize>,
+    /// 入力のうち原因になった範囲(バイト単位).
+    ///
+    /// 空のアクセント句では区切りの直後の空の範囲になります.
+    pub range: Option<Range<usize>>,
+}
+
+impl KanaError {
+    /// エンジンが返した400のエラーを解釈する.
+    ///
+    /// `kana`にはリクエストした読み仮名を渡します.
+    /// 手元で解析しても同じエラーになる場合は`range`も埋めます.
+    /// 知らない`error_name`の場合は`None`を返します.
+    pub fn from_bad_request(bad_request: &ParseKanaBadRequest, kana: &str) -> Option<Self> {
+        let name = serde_json::from_value(bad_request.error_name.clone().into()).ok()?;
+        let range = match parse(kana) {
+            Err(e) if e.name == name => e.range,
+            _ => None,
+        };
+        Some(Self {
+            name,
+            text: bad_request.error_args.get("text").cloned(),
+            position: bad_request
+                .error_args
+                .get("position")
+                .and_then(|position| position.parse().ok()),
+            range,
+        })
+    }
+
+    fn text(name: ErrorName, kana: &str, text: &str) -> Self {
+        let start = offset(kana, text);
+        Self {
+            name,
+            text: Some(text.to_owned()),
+            position: None,
+            range: Some(start..start + text.len()),
+        }
+    }
+
+    fn position(position: usize, start: usize) -> Self {
+        Self {
+            name: ErrorName::EmptyPhrase,
+            text: None,
+            position: Some(position),
+            range: Some(start..start),
+        }
+    }
+
+    fn accent(name: ErrorName, kana: &str, phrase: &str, accent: &str) -> Self {
+        let start = offset(kana, accent);
+        Self {
+            range: Some(start..start + ACCENT_SYMBOL.len_utf8()),
+            ..Self::text(name, kana, phrase)
+        }
+    }
+}
+
+/// `kana`の部分文字列`part`の開始位置.
+fn offset(kana: &str, part: &str) -> usize {
+    part.as_ptr() as usize - kana.as_ptr() as usize
+}
+
+/// エンジンと同じメッセージ.
+impl fmt::Display for KanaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = self.text.as_deref().unwrap_or_default();
+        match self.name {
+            ErrorName::UnknownText => write!(f, "判別できない読み仮名があります: {text}"),
+            ErrorName::AccentTop => write!(f, "句頭にアクセントは置けません: {text}"),
+            ErrorName::AccentTwice => write!(
+                f,
+                "1つのアクセント句に二つ以上のアクセントは置けません: {text}"
+            ),
+            ErrorName::AccentNotFound => write!(
+                f,
+                "アクセントを指定していないアクセント句があります: {text}"
+            ),
+            ErrorName::EmptyPhrase => write!(
+                f,
+                "{}番目のアクセント句が空白です",
+                self.position.unwrap_or_default()
+            ),
+            ErrorName::InterrogationMarkNotAtEnd => {
+                write!(f, "アクセント句末以外に「？」は置けません: {text}")
+            }
+            ErrorName::InfiniteLoop => write!(
+                f,
+                "処理時に無限ループになってしまいました...バグ報告をお願いします。"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for KanaError {}
+
+/// 読み仮名1つ分のモーラ.
+///
+/// `_`から始まる場合は無声化した母音(大文字)になります.
+/// 長さと音高は0で,子音がある場合だけ`consonant_length`が`Some`になります.
+pub fn mora(text: &str) -> Option<Mora> {
+    let (unvoiced, text) = match text.strip_prefix(UNVOICE_SYMBOL) {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+    let (text, consonant, vowel) = MORAS.iter().find(|(kana, _, _)| *kana == text)?;
+    let consonant: Option<Phoneme> = consonant.parse().ok();
+    let vowel: Phoneme = vowel.parse().ok()?;
+    Some(Mora {
+        text: text.to_string(),
+        consonant_length: consonant.as_ref().map(|_| 0.0),
+        consonant,
+        vowel: if unvoiced { vowel.devoiced()? } else { vowel },
+        vowel_length: 0.0,
+        pitch: 0.0,
+    })
+}
+
+/// 子音と母音に対応するカタカナ.
+///
+/// 無声化した母音は有声の母音と同じカナになります.同じ音素になるカナが複数ある場合は代表的なものを返します.
+pub fn text(consonant: Option<&Phoneme>, vowel: &Phoneme) -> Option<&'static str> {
+    let consonant = consonant.map_or("", Phoneme::as_str);
+    let vowel = vowel.voiced();
+    MORAS
+        .iter()
+        .find(|(_, c, v)| *c == consonant && *v == vowel.as_str())
+        .map(|(text, _, _)| *text)
+}
+
+/// 読み仮名をアクセント句に変換する.
+///
+/// 返すアクセント句は長さと音高が0のままなので,
+/// 合成する前に`MoraData`などで埋めてください.
+pub fn parse(kana: &str) -> Result<Vec<AccentPhrase>, KanaError> {
+    if kana.is_empty() {
+        return Err(KanaError::position(1, 0));
+    }
+    let mut accent_phrases = Vec::new();
+    let mut rest = kana;
+    loop {
+        let end = rest
+            .find([PAUSE_DELIMITER, NOPAUSE_DELIMITER])
+            .unwrap_or(rest.len());
+        let phrase = &rest[..end];
+        let delimiter = rest[end..].chars().next();
+        if phrase.is_empty() {
+            return Err(KanaError::position(
+                accent_phrases.len() + 1,
+                offset(kana, rest),
+            ));
+        }
+        let body = phrase
+            .strip_suffix(WIDE_INTERROGATION_MARK)
+            .unwrap_or(phrase);
+        if body.contains(WIDE_INTERROGATION_MARK) {
+            return Err(KanaError::text(
+                ErrorName::InterrogationMarkNotAtEnd,
+                kana,
+                phrase,
+            ));
+        }
+        let mut accent_phrase = accent_phrase(kana, body)?;
+        accent_phrase.is_interrogative = Some(body.len() != phrase.len());
+        if delimiter == Some(PAUSE_DELIMITER) {
+            accent_phrase.pause_mora = Some(Mora {
+                text: PAUSE_DELIMITER.to_string(),
+                consonant: None,
+                consonant_length: None,
+                vowel: Phoneme::Pau,
+                vowel_length: 0.0,
+                pitch: 0.0,
+            });
+        }
+        accent_phrases.push(accent_phrase);
+        match delimiter {
+            Some(delimiter) => rest = &rest[end + delimiter.len_utf8()..],
+            None => return Ok(accent_phrases),
+        }
+    }
+}
+
+/// 区切りと疑問符を除いたアクセント句を解析する.
+///
+/// 各位置でアクセント記号までの最長一致するモーラを選びます.
+fn accent_phrase(kana: &str, phrase: &str) -> Result<AccentPhrase, KanaError> {
+    let mut moras = Vec::new();
+    let mut accent = None;
+    let mut rest = phrase;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix(ACCENT_SYMBOL) {
+            if moras.is_empty() {
+                return Err(KanaError::accent(ErrorName::AccentTop, kana, phrase, rest));
+            }
+            if accent.is_some() {
+                return Err(KanaError::accent(
+                    ErrorName::AccentTwice,
+                    kana,
+                    phrase,
+                    rest,
+                ));
+            }
+            accent = Some(moras.len() as i32);
+            rest = after;
+            continue;
+        }
+        let stack = &rest[..rest.find(ACCENT_SYMBOL).unwrap_or(rest.len())];
+        let (len, mora) = stack
+            .char_indices()
+            .rev()
+            .find_map(|(i, c)| {
+                let len = i + c.len_utf8();
+                Some((len, mora(&stack[..len])?))
+            })
+            .ok_or_else(|| KanaError::text(ErrorName::UnknownText, kana, stack))?;
+        moras.push(mora);
+        rest = &rest[len..];
+    }
+    let accent = accent.ok_or_else(|| KanaError::text(ErrorName::AccentNotFound, kana, phrase))?;
+    Ok(AccentPhrase {
+        moras,
+        accent,
+        pause_mora: None,
+        is_interrogative: None,
+    })
+}
+
+/// アクセント句を読み仮名に変換する.
+///
+/// `AudioQuery.kana`と同じ形式で,`parse`で元のアクセント句の構造に戻せます.
+pub fn create(accent_phrases: &[AccentPhrase]) -> String {
+    let mut kana = String::new();
+    for (i, accent_phrase) in accent_phrases.iter().enumerate() {
+        for (j, mora) in accent_phrase.moras.iter().enumerate() {
+            if mora.vowel.is_devoiced() {
+                kana.push(UNVOICE_SYMBOL);
+            }
+            kana.push_str(&mora.text);
+            if j as i32 + 1 == accent_phrase.accent {
+                kana.push(ACCENT_SYMBOL);
+            }
+        }
+        if accent_phrase.is_interrogative == Some(true) {
+            kana.push(WIDE_INTERROGATION_MARK);
+        }
+        if i + 1 < accent_phrases.len() {
+            kana.push(match accent_phrase.pause_mora {
+                Some(_) => PAUSE_DELIMITER,
+                None => NOPAUSE_DELIMITER,
+            });
+        }
+    }
+    kana
+}
@@ -1,16 +1,38 @@
 //!
 //! VoiceVox 0.13.3 api implementation.
 //!
+//! Endpoints added by newer engines are also available,
+//! check `version::EngineVersion::supports` before calling them.
+//!
 #[cfg(all(not(feature = "backend_surf"), not(feature = "backend_reqwest")))]
 compile_error!("you need to enable backend_surf or backend_reqwest");
-#[cfg(not(feature = "backend_surf"))]
+#[cfg(feature = "backend_reqwest")]
 pub mod reqwest_api;
 #[cfg(feature = "backend_surf")]
 pub mod surf_api;
 
-#[cfg(all(not(feature = "backend_surf"), feature = "backend_reqwest"))]
-pub use reqwest_api as api;
-#[cfg(all(not(feature = "backend_reqwest"), feature = "backend_surf"))]
-pub use surf_api as api;
-
+pub mod api;
 pub mod api_schema;
+pub mod audio;
+pub mod batch;
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub mod blocking;
+pub mod cancel;
+pub mod capabilities;
+pub mod client;
+pub mod endpoint;
+pub mod error;
+pub mod kana;
+#[cfg(all(feature = "mock", not(target_arch = "wasm32")))]
+pub mod mock;
+pub mod registry;
+pub mod retry;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod speaker_cache;
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub mod supervisor;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod synthesis_cache;
+pub mod transport;
+pub mod user_dict;
+pub mod version;
@@ -0,0 +1,1382 @@
+//! テスト用のVoiceVox engineの代わり.
+//!
+//! 決まったデータだけを返すエンジンをローカルのポートで起動します.
+//! 実際のエンジンを起動できない環境で`voice_vox_api`を使うコードを試すために使います.
+//!
+//! * 音声合成は正弦波のwavを返します.
+//! * アイコンと立ち絵は1x1のpngです.
+//! * 不正なパラメータには実際のエンジンと同じく422のエラーを返します.
+//! * プリセット,ユーザー辞書,初期化済みの話者はエンジンごとに保持されます.
+
+use crate::{
+    api_schema::{
+        full_width_surface, AccentPhrase, AudioQuery, CorsPolicyMode, FrameAudioQuery,
+        FramePhoneme, Mora, Phoneme, Preset, PresetId, Score, Setting, Speaker, SpeakerInfoRaw,
+        SpeakerStyle, SpeakerSupportPermittedSynthesisMorphing, SpeakerSupportedFeatures,
+        SpeakerUuid, StyleId, StyleInfoRaw, StyleType,
+    },
+    audio::{Wav, WavError},
+    kana::{self, KanaError},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io::{self, BufRead, BufReader, Cursor, Write},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+/// `/version`が返すバージョン.
+pub const ENGINE_VERSION: &str = "0.16.0";
+/// `start`や`bind`で起動したエンジンが`/engine_manifest`で返すUUID.
+pub const ENGINE_UUID: &str = "c7b58856-bd56-4aa1-afb7-b8415f824b06";
+/// 1x1の透明なpng.
+pub const ICON_PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+/// 話者の名前,UUID,スタイル,モーフィングの許可.
+type MockSpeaker = (
+    &'static str,
+    &'static str,
+    &'static [(&'static str, i32)],
+    Morphing,
+);
+
+const SPEAKERS: [MockSpeaker; 3] = [
+    (
+        "モック話者A",
+        "00000000-0000-4000-8000-000000000001",
+        &[("ノーマル", 0), ("あまあま", 1)],
+        Morphing::All,
+    ),
+    (
+        "モック話者B",
+        "00000000-0000-4000-8000-000000000002",
+        &[("ノーマル", 2)],
+        Morphing::SelfOnly,
+    ),
+    (
+        "モック話者C",
+        "00000000-0000-4000-8000-000000000003",
+        &[("ノーマル", 3)],
+        Morphing::Nothing,
+    ),
+];
+
+/// 歌手の名前,UUID.
+const SINGER: (&str, &str) = ("モック歌手", "00000000-0000-4000-8000-000000000004");
+/// `/sing_frame_audio_query`に使う歌唱指導用のスタイル.
+pub const SINGING_TEACHER_STYLE: StyleId = StyleId(6000);
+/// `/frame_synthesis`に使うスタイル.
+pub const FRAME_DECODE_STYLE: StyleId = StyleId(3000);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Morphing {
+    All,
+    SelfOnly,
+    Nothing,
+}
+
+/// カタカナの行と子音.
+const KANA_ROWS: [(&str, Option<Phoneme>); 16] = [
+    ("アイウエオ", None),
+    ("カキクケコ", Some(Phoneme::K)),
+    ("ガギグゲゴ", Some(Phoneme::G)),
+    ("サシスセソ", Some(Phoneme::S)),
+    ("ザジズゼゾ", Some(Phoneme::Z)),
+    ("タチツテト", Some(Phoneme::T)),
+    ("ダヂヅデド", Some(Phoneme::D)),
+    ("ナニヌネノ", Some(Phoneme::N)),
+    ("ハヒフヘホ", Some(Phoneme::H)),
+    ("バビブベボ", Some(Phoneme::B)),
+    ("パピプペポ", Some(Phoneme::P)),
+    ("マミムメモ", Some(Phoneme::M)),
+    ("ヤ　ユ　ヨ", Some(Phoneme::Y)),
+    ("ラリルレロ", Some(Phoneme::R)),
+    ("ワ　　　ヲ", Some(Phoneme::W)),
+    ("ァィゥェォ", None),
+];
+const VOWELS: [Phoneme; 5] = [Phoneme::A, Phoneme::I, Phoneme::U, Phoneme::E, Phoneme::O];
+
+/// ローカルで動くモックのエンジン.
+///
+/// 破棄すると停止します.
+///
+/// ```ignore
+/// let engine = MockEngine::start()?;
+/// let client = VoiceVoxClient::new(&engine.base_url())?;
+/// ```
+pub struct MockEngine {
+    state: Arc<Mutex<State>>,
+    addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockEngine {
+    /// 空いているポートで起動する.
+    pub fn start() -> io::Result<Self> {
+        Self::bind("127.0.0.1:0")
+    }
+
+    /// `/engine_manifest`が`uuid`を返すエンジンを空いているポートで起動する.
+    ///
+    /// 複数のエンジンを扱うコードを試すために使います.
+    pub fn start_as(uuid: &str) -> io::Result<Self> {
+        Self::serve("127.0.0.1:0", uuid)
+    }
+
+    /// 指定したアドレスで起動する.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::serve(addr, ENGINE_UUID)
+    }
+
+    fn serve(addr: impl ToSocketAddrs, uuid: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State {
+            uuid: uuid.to_owned(),
+            ..State::default()
+        }));
+        let stopped = Arc::new(AtomicBool::new(false));
+        // 接続ごとにスレッドを立てるので,keep-aliveの接続が残っていても新しい接続を待たせません.
+        let thread = std::thread::spawn({
+            let state = state.clone();
+            let stopped = stopped.clone();
+            move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    let state = state.clone();
+                    let stopped = stopped.clone();
+                    std::thread::spawn(move || serve_connection(stream, &state, &stopped));
+                }
+            }
+        });
+        Ok(Self {
+            state,
+            addr,
+            stopped,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// `VoiceVoxClient`に渡すベースURL.
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// 音声合成のレスポンスを返すまで`delay`だけ待つ.
+    ///
+    /// タイムアウトや中断を試すために使います.
+    pub fn set_synthesis_delay(&self, delay: Duration) {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .synthesis_delay = delay;
+    }
+
+    /// 次の`count`個のリクエストに`status`を返す.
+    ///
+    /// モデルを読み込んでいる間のエンジンや再試行を試すために使います.
+    pub fn fail_next(&self, count: usize, status: u16) {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .failures = (count, status);
+    }
+
+    /// 停止するまでリクエストを処理し続ける.
+    pub fn wait(mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MockEngine {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // 接続して`accept`で待っているスレッドを起こす.
+        let mut addr = self.addr;
+        if addr.ip().is_unspecified() {
+            addr.set_ip(match addr {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        let _ = TcpStream::connect(addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// エンジンごとに保持する状態.
+#[derive(Default)]
+struct State {
+    uuid: String,
+    presets: Vec<Preset>,
+    user_dict: BTreeMap<String, Value>,
+    initialized: BTreeSet<i32>,
+    next_word: u64,
+    setting: Option<Setting>,
+    installed_libraries: BTreeMap<String, Value>,
+    synthesis_delay: Duration,
+    /// 失敗させる残りのリクエストの数とステータスコード.
+    failures: (usize, u16),
+}
+
+/// 返すレスポンス.
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl From<WavError> for Reply {
+    fn from(e: WavError) -> Self {
+        Reply::detail(500, json!(e.to_string()))
+    }
+}
+
+impl Reply {
+    fn json(value: &impl Serialize) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: serde_json::to_vec(value).unwrap(),
+        }
+    }
+
+    fn wav(body: Vec<u8>) -> Self {
+        Self {
+            status: 200,
+            content_type: "audio/wav",
+            body,
+        }
+    }
+
+    fn no_content() -> Self {
+        Self {
+            status: 204,
+            content_type: "application/json",
+            body: Vec::new(),
+        }
+    }
+
+    fn html(body: String) -> Self {
+        Self {
+            status: 200,
+            content_type: "text/html; charset=utf-8",
+            body: body.into_bytes(),
+        }
+    }
+
+    /// FastAPIと同じ形式の`{"detail": ...}`.
+    fn detail(status: u16, detail: Value) -> Self {
+        Self {
+            status,
+            ..Self::json(&json!({ "detail": detail }))
+        }
+    }
+
+    /// 422 パラメータの検証エラー.
+    fn validation(loc: &[&str], msg: &str, error_type: &str) -> Self {
+        Self::detail(422, json!([{ "loc": loc, "msg": msg, "type": error_type }]))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Method {
+    Get,
+    Post,
+    Put,
+    Delete,
+    Other,
+}
+
+/// 受け取ったリクエスト.
+struct Request {
+    method: Method,
+    url: String,
+    body: Vec<u8>,
+    /// レスポンスを返した後に接続を閉じる.
+    close: bool,
+}
+
+/// 1つの接続で受け取ったリクエストを順番に処理する.
+///
+/// エンジンが停止した後に届いたリクエストには応答せずに接続を閉じます.
+fn serve_connection(stream: TcpStream, state: &Mutex<State>, stopped: &AtomicBool) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(stream);
+    loop {
+        let request = match read_request(&mut reader, &mut writer) {
+            Ok(Some(request)) => request,
+            Ok(None) => return,
+            Err(e) => {
+                let reply = Reply::detail(400, json!(e.to_string()));
+                let _ = write_reply(&mut writer, &reply, true);
+                return;
+            }
+        };
+        if stopped.load(Ordering::SeqCst) {
+            return;
+        }
+        let reply = handle(state, &request);
+        if write_reply(&mut writer, &reply, request.close).is_err() || request.close {
+            return;
+        }
+    }
+}
+
+/// HTTP/1.1のリクエストを1つ読む.接続が閉じられた場合は`None`.
+fn read_request(reader: &mut impl BufRead, writer: &mut impl Write) -> io::Result<Option<Request>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_owned());
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(url), Some(version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid("malformed request line"));
+    };
+    let method = match method {
+        "GET" => Method::Get,
+        "POST" => Method::Post,
+        "PUT" => Method::Put,
+        "DELETE" => Method::Delete,
+        _ => Method::Other,
+    };
+    let mut request = Request {
+        method,
+        url: url.to_owned(),
+        body: Vec::new(),
+        close: version == "HTTP/1.0",
+    };
+    let mut content_length = 0;
+    let mut chunked = false;
+    let mut expect_continue = false;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(invalid("malformed header"));
+        };
+        let value = value.trim();
+        let has_token = |token: &str| {
+            value
+                .split(',')
+                .any(|v| v.trim().eq_ignore_ascii_case(token))
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "content-length" => {
+                content_length = value
+                    .parse()
+                    .map_err(|_| invalid("invalid content-length"))?
+            }
+            "transfer-encoding" => chunked = has_token("chunked"),
+            "connection" if has_token("close") => request.close = true,
+            "connection" if has_token("keep-alive") => request.close = false,
+            "expect" => expect_continue = value.eq_ignore_ascii_case("100-continue"),
+            _ => {}
+        }
+    }
+    if expect_continue {
+        writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+        writer.flush()?;
+    }
+    if chunked {
+        loop {
+            line.clear();
+            reader.read_line(&mut line)?;
+            let size = line.trim_end().split(';').next().unwrap_or_default();
+            let size =
+                usize::from_str_radix(size, 16).map_err(|_| invalid("invalid chunk size"))?;
+            if size == 0 {
+                // トレーラーは読み飛ばす.
+                loop {
+                    line.clear();
+                    if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+                        break;
+                    }
+                }
+                break;
+            }
+            let start = request.body.len();
+            request.body.resize(start + size, 0);
+            reader.read_exact(&mut request.body[start..])?;
+            let mut crlf = [0; 2];
+            reader.read_exact(&mut crlf)?;
+        }
+    } else {
+        request.body.resize(content_length, 0);
+        reader.read_exact(&mut request.body)?;
+    }
+    Ok(Some(request))
+}
+
+fn write_reply(writer: &mut impl Write, reply: &Reply, close: bool) -> io::Result<()> {
+    let reason = match reply.status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        422 => "Unprocessable Entity",
+        500 => "Internal Server Error",
+        _ => "",
+    };
+    let mut head = format!(
+        "HTTP/1.1 {} {reason}\r\nContent-Type: {}\r\nContent-Length: {}\r\n",
+        reply.status,
+        reply.content_type,
+        reply.body.len()
+    );
+    if close {
+        head.push_str("Connection: close\r\n");
+    }
+    head.push_str("\r\n");
+    writer.write_all(head.as_bytes())?;
+    writer.write_all(&reply.body)?;
+    writer.flush()
+}
+
+fn handle(state: &Mutex<State>, request: &Request) -> Reply {
+    let (path, query) = request.url.split_once('?').unwrap_or((&request.url, ""));
+    let query: HashMap<String, String> = url::form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    if let (count @ 1.., status) = &mut state.failures {
+        *count -= 1;
+        return Reply::detail(*status, json!("mock failure"));
+    }
+    let reply =
+        route(&mut state, request.method, path, &query, &request.body).unwrap_or_else(|e| e);
+    let delay = match path {
+        "/synthesis" | "/cancellable_synthesis" | "/multi_synthesis" | "/synthesis_morphing" => {
+            state.synthesis_delay
+        }
+        _ => Duration::ZERO,
+    };
+    drop(state);
+    std::thread::sleep(delay);
+    reply
+}
+
+fn route(
+    state: &mut State,
+    method: Method,
+    path: &str,
+    query: &HashMap<String, String>,
+    body: &[u8],
+) -> Result<Reply, Reply> {
+    match (method, path) {
+        (Method::Post, "/audio_query") => {
+            let speaker = query_style(query, "speaker")?;
+            let text = query_str(query, "text")?;
+            Ok(Reply::json(&audio_query(text, speaker)))
+        }
+        (Method::Post, "/audio_query_from_preset") => {
+            let preset_id = PresetId(query_parse(query, "preset_id")?);
+            let text = query_str(query, "text")?;
+            let preset = state
+                .presets
+                .iter()
+                .find(|preset| preset.id == preset_id)
+                .ok_or_else(|| {
+                    Reply::validation(
+                        &["query", "preset_id"],
+                        "該当するプリセットIDが見つかりません",
+                        "value_error",
+                    )
+                })?;
+            let query = AudioQuery {
+                speedScale: preset.speedScale,
+                pitchScale: preset.pitchScale,
+                intonationScale: preset.intonationScale,
+                volumeScale: preset.volumeScale,
+                prePhonemeLength: preset.prePhonemeLength,
+                postPhonemeLength: preset.postPhonemeLength,
+                ..audio_query(text, preset.style_id.0)
+            };
+            Ok(Reply::json(&query))
+        }
+        (Method::Post, "/accent_phrases") => {
+            let speaker = query_style(query, "speaker")?;
+            let text = query_str(query, "text")?;
+            let accent_phrases = if query_bool(query, "is_kana")?.unwrap_or(false) {
+                parse_kana(text, speaker).map_err(kana_error)?
+            } else {
+                accent_phrases(text, speaker)
+            };
+            Ok(Reply::json(&accent_phrases))
+        }
+        (Method::Post, "/validate_kana") => {
+            kana::parse(query_str(query, "text")?).map_err(kana_error)?;
+            Ok(Reply::json(&true))
+        }
+        (Method::Post, "/mora_data" | "/mora_length" | "/mora_pitch") => {
+            let speaker = query_style(query, "speaker")?;
+            let mut accent_phrases: Vec<AccentPhrase> = body_json(body)?;
+            for mora in accent_phrases
+                .iter_mut()
+                .flat_map(|ap| ap.moras.iter_mut().chain(ap.pause_mora.as_mut()))
+            {
+                if path != "/mora_pitch" {
+                    set_length(mora);
+                }
+                if path != "/mora_length" {
+                    set_pitch(mora, speaker);
+                }
+            }
+            Ok(Reply::json(&accent_phrases))
+        }
+        (Method::Post, "/synthesis" | "/cancellable_synthesis") => {
+            query_style(query, "speaker")?;
+            query_bool(query, "enable_interrogative_upspeak")?;
+            let audio_query = audio_query_body(body)?;
+            Ok(Reply::wav(synthesis(&audio_query)?))
+        }
+        (Method::Post, "/multi_synthesis") => {
+            query_style(query, "speaker")?;
+            let audio_queries: Vec<AudioQuery> = body_json(body)?;
+            let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+            let options = zip::write::FileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            let internal = |e: &dyn std::fmt::Display| Reply::detail(500, json!(e.to_string()));
+            for (i, audio_query) in audio_queries.iter().enumerate() {
+                check_audio_query(audio_query)?;
+                let wav = synthesis(audio_query)?;
+                archive
+                    .start_file(format!("{:03}.wav", i + 1), options)
+                    .map_err(|e| internal(&e))?;
+                archive.write_all(&wav).map_err(|e| internal(&e))?;
+            }
+            let body = archive.finish().map_err(|e| internal(&e))?.into_inner();
+            Ok(Reply {
+                status: 200,
+                content_type: "application/zip",
+                body,
+            })
+        }
+        (Method::Post, "/synthesis_morphing") => {
+            let base = query_style(query, "base_speaker")?;
+            let target = query_style(query, "target_speaker")?;
+            let morph_rate = query_parse::<f64>(query, "morph_rate")?;
+            if !(0.0..=1.0).contains(&morph_rate) {
+                return Err(Reply::validation(
+                    &["query", "morph_rate"],
+                    "morph_rateは0.0から1.0の範囲で指定してください",
+                    "value_error",
+                ));
+            }
+            let audio_query = audio_query_body(body)?;
+            if !is_morphable(base, target) {
+                return Err(Reply::detail(
+                    400,
+                    json!("指定された話者ペアでのモーフィングはできません"),
+                ));
+            }
+            Ok(Reply::wav(synthesis(&audio_query)?))
+        }
+        (Method::Post, "/morphable_targets") => {
+            let base_styles: Vec<i32> = body_json(body)?;
+            let mut targets = Vec::new();
+            for base in base_styles {
+                if find_style(base).is_none() {
+                    return Err(Reply::validation(
+                        &["body"],
+                        "該当する話者が見つかりません",
+                        "value_error",
+                    ));
+                }
+                let target: BTreeMap<String, Value> = style_ids()
+                    .map(|target| {
+                        let is_morphable = is_morphable(base, target);
+                        (target.to_string(), json!({ "is_morphable": is_morphable }))
+                    })
+                    .collect();
+                targets.push(target);
+            }
+            Ok(Reply::json(&targets))
+        }
+        (Method::Post, "/connect_waves") => {
+            let waves: Vec<String> = body_json(body)?;
+            let invalid = || {
+                Reply::validation(
+                    &["body"],
+                    "wavファイルを読み込めませんでした",
+                    "value_error",
+                )
+            };
+            let mut connected: Option<Wav> = None;
+            for wave in waves {
+                let wave = STANDARD.decode(wave).map_err(|_| invalid())?;
+                let wave = Wav::parse(&wave).map_err(|_| invalid())?;
+                match &mut connected {
+                    Some(connected)
+                        if (
+                            connected.sample_rate,
+                            connected.channels,
+                            connected.bits_per_sample,
+                        ) == (wave.sample_rate, wave.channels, wave.bits_per_sample) =>
+                    {
+                        connected.samples.extend(wave.samples)
+                    }
+                    Some(_) => return Err(invalid()),
+                    None => connected = Some(wave),
+                }
+            }
+            let connected =
+                connected.unwrap_or_else(|| Wav::silence(24000, 1, 16, Default::default()));
+            Ok(Reply::wav(connected.to_bytes()?))
+        }
+        (Method::Get, "/singers") => Ok(Reply::json(&json!([{
+            "supported_features": { "permitted_synthesis_morphing": "NOTHING" },
+            "name": SINGER.0,
+            "speaker_uuid": SINGER.1,
+            "styles": [
+                { "name": "人間ver.", "id": SINGING_TEACHER_STYLE, "type": "singing_teacher" },
+                { "name": "ノーマル", "id": FRAME_DECODE_STYLE, "type": "frame_decode" },
+            ],
+            "version": ENGINE_VERSION,
+        }]))),
+        (Method::Get, "/singer_info") => {
+            if query_str(query, "speaker_uuid")? != SINGER.1 {
+                return Err(Reply::validation(
+                    &["query", "speaker_uuid"],
+                    "該当する話者が見つかりません",
+                    "value_error",
+                ));
+            }
+            Ok(Reply::json(&style_info(
+                SINGER.0,
+                &[SINGING_TEACHER_STYLE.0, FRAME_DECODE_STYLE.0],
+            )))
+        }
+        (Method::Post, "/sing_frame_audio_query") => {
+            query_sing_style(query, SINGING_TEACHER_STYLE)?;
+            let score: Score = body_json(body)?;
+            Ok(Reply::json(&sing_frame_audio_query(&score)?))
+        }
+        (Method::Post, "/frame_synthesis") => {
+            query_sing_style(query, FRAME_DECODE_STYLE)?;
+            let query: FrameAudioQuery = body_json(body)?;
+            let frames: i32 = query.phonemes.iter().map(|p| p.frame_length).sum();
+            if query.f0.len() != frames as usize || query.volume.len() != frames as usize {
+                return Err(Reply::detail(
+                    400,
+                    json!("f0とvolumeの長さが音素の長さと一致しません"),
+                ));
+            }
+            Ok(Reply::wav(frame_synthesis(&query)?))
+        }
+        (Method::Get, "/presets") => Ok(Reply::json(&state.presets)),
+        (Method::Post, "/add_preset") => {
+            let mut preset: Preset = body_json(body)?;
+            if state.presets.iter().any(|p| p.id == preset.id) {
+                preset.id = PresetId(state.presets.iter().map(|p| p.id.0).max().unwrap_or(0) + 1);
+            }
+            let id = preset.id;
+            state.presets.push(preset);
+            Ok(Reply::json(&id))
+        }
+        (Method::Post, "/update_preset") => {
+            let preset: Preset = body_json(body)?;
+            let id = preset.id;
+            let slot = state
+                .presets
+                .iter_mut()
+                .find(|p| p.id == id)
+                .ok_or_else(|| {
+                    Reply::validation(&["body"], "更新先のプリセットが存在しません", "value_error")
+                })?;
+            *slot = preset;
+            Ok(Reply::json(&id))
+        }
+        (Method::Post, "/delete_preset") => {
+            let id = PresetId(query_parse(query, "id")?);
+            let len = state.presets.len();
+            state.presets.retain(|p| p.id != id);
+            if state.presets.len() == len {
+                return Err(Reply::validation(
+                    &["query", "id"],
+                    "削除対象のプリセットが存在しません",
+                    "value_error",
+                ));
+            }
+            Ok(Reply::no_content())
+        }
+        (Method::Get, "/version") => Ok(Reply::json(&ENGINE_VERSION)),
+        (Method::Get, "/core_versions") => Ok(Reply::json(&[ENGINE_VERSION])),
+        (Method::Get, "/speakers") => Ok(Reply::json(&speakers())),
+        (Method::Get, "/speaker_info") => {
+            let speaker_uuid = query_str(query, "speaker_uuid")?;
+            let info = speaker_info(speaker_uuid).ok_or_else(|| {
+                Reply::validation(
+                    &["query", "speaker_uuid"],
+                    "該当する話者が見つかりません",
+                    "value_error",
+                )
+            })?;
+            Ok(Reply::json(&info))
+        }
+        (Method::Get, "/supported_devices") => Ok(Reply::json(&json!({
+            "cpu": true,
+            "cuda": false,
+            "dml": false,
+        }))),
+        (Method::Get, "/downloadable_libraries") => Ok(Reply::json(&[library()])),
+        (Method::Get, "/installed_libraries") => Ok(Reply::json(&state.installed_libraries)),
+        (Method::Post, path) if path.starts_with("/install_library/") => {
+            let uuid = &path["/install_library/".len()..];
+            let mut library = library();
+            if library["uuid"] != uuid {
+                return Err(library_not_found());
+            }
+            if !body.starts_with(b"PK") {
+                return Err(Reply::detail(422, json!("不正なZIPファイルです")));
+            }
+            library["uninstallable"] = json!(true);
+            state.installed_libraries.insert(uuid.to_owned(), library);
+            Ok(Reply::no_content())
+        }
+        (Method::Post, path) if path.starts_with("/uninstall_library/") => {
+            state
+                .installed_libraries
+                .remove(&path["/uninstall_library/".len()..])
+                .ok_or_else(library_not_found)?;
+            Ok(Reply::no_content())
+        }
+        (Method::Post, "/initialize_speaker") => {
+            let speaker = query_style(query, "speaker")?;
+            query_bool(query, "skip_reinit")?;
+            state.initialized.insert(speaker);
+            Ok(Reply::no_content())
+        }
+        (Method::Get, "/is_initialized_speaker") => {
+            let speaker = query_style(query, "speaker")?;
+            Ok(Reply::json(&state.initialized.contains(&speaker)))
+        }
+        (Method::Get, "/engine_manifest") => Ok(Reply::json(&engine_manifest(&state.uuid))),
+        (Method::Get, "/user_dict") => Ok(Reply::json(&state.user_dict)),
+        (Method::Post, "/user_dict_word") => {
+            let word = user_dict_word(query)?;
+            state.next_word += 1;
+            let uuid = format!("00000000-0000-4000-8000-{:012x}", state.next_word);
+            state.user_dict.insert(uuid.clone(), word);
+            Ok(Reply::json(&uuid))
+        }
+        (Method::Put, path) if path.starts_with("/user_dict_word/") => {
+            let word = user_dict_word(query)?;
+            let slot = state
+                .user_dict
+                .get_mut(&path["/user_dict_word/".len()..])
+                .ok_or_else(word_not_found)?;
+            *slot = word;
+            Ok(Reply::no_content())
+        }
+        (Method::Delete, path) if path.starts_with("/user_dict_word/") => {
+            state
+                .user_dict
+                .remove(&path["/user_dict_word/".len()..])
+                .ok_or_else(word_not_found)?;
+            Ok(Reply::no_content())
+        }
+        (Method::Post, "/import_user_dict") => {
+            let over_ride = query_bool(query, "override")?.ok_or_else(|| missing("override"))?;
+            let dictionary: BTreeMap<String, Value> = body_json(body)?;
+            for (uuid, word) in dictionary {
+                if over_ride || !state.user_dict.contains_key(&uuid) {
+                    state.user_dict.insert(uuid, word);
+                }
+            }
+            Ok(Reply::no_content())
+        }
+        (Method::Get, "/setting") => {
+            let setting = state.setting.clone().unwrap_or(Setting {
+                cors_policy_mode: CorsPolicyMode::Localapps,
+                allow_origin: None,
+            });
+            Ok(Reply::html(format!(
+                "<html><body><form method=\"post\">\
+                 <select name=\"cors_policy_mode\"><option selected>{}</option></select>\
+                 <input name=\"allow_origin\" value=\"{}\">\
+                 </form></body></html>",
+                setting.cors_policy_mode.as_str(),
+                setting.allow_origin.unwrap_or_default(),
+            )))
+        }
+        (Method::Post, "/setting") => {
+            let form: HashMap<String, String> =
+                url::form_urlencoded::parse(body).into_owned().collect();
+            let cors_policy_mode =
+                match form.get("cors_policy_mode").map(String::as_str) {
+                    Some("all") => CorsPolicyMode::All,
+                    Some("localapps") => CorsPolicyMode::Localapps,
+                    Some(_) => return Err(Reply::validation(
+                        &["body", "cors_policy_mode"],
+                        "value is not a valid enumeration member; permitted: 'all', 'localapps'",
+                        "type_error.enum",
+                    )),
+                    None => {
+                        return Err(Reply::validation(
+                            &["body", "cors_policy_mode"],
+                            "field required",
+                            "value_error.missing",
+                        ))
+                    }
+                };
+            state.setting = Some(Setting {
+                cors_policy_mode,
+                allow_origin: form.get("allow_origin").cloned(),
+            });
+            Ok(Reply::no_content())
+        }
+        _ => Err(Reply::detail(404, json!("Not Found"))),
+    }
+}
+
+fn missing(key: &str) -> Reply {
+    Reply::validation(&["query", key], "field required", "value_error.missing")
+}
+
+fn library_not_found() -> Reply {
+    Reply::detail(404, json!("指定された音声ライブラリが見つかりません"))
+}
+
+/// 読み仮名の解析エラーをエンジンと同じ400のレスポンスにする.
+fn kana_error(e: KanaError) -> Reply {
+    let error_args = match e.position {
+        Some(position) => json!({ "position": position.to_string() }),
+        None => json!({ "text": e.text }),
+    };
+    Reply::detail(
+        400,
+        json!({
+            "text": e.to_string(),
+            "error_name": e.name,
+            "error_args": error_args,
+        }),
+    )
+}
+
+fn word_not_found() -> Reply {
+    Reply::validation(
+        &["path", "word_uuid"],
+        "UUIDに該当するワードが見つかりませんでした",
+        "value_error",
+    )
+}
+
+fn query_str<'a>(query: &'a HashMap<String, String>, key: &str) -> Result<&'a str, Reply> {
+    query
+        .get(key)
+        .map(String::as_str)
+        .ok_or_else(|| missing(key))
+}
+
+fn query_parse<T: std::str::FromStr>(
+    query: &HashMap<String, String>,
+    key: &str,
+) -> Result<T, Reply> {
+    query_str(query, key)?
+        .parse()
+        .map_err(|_| Reply::validation(&["query", key], "value is not a valid value", "type_error"))
+}
+
+fn query_bool(query: &HashMap<String, String>, key: &str) -> Result<Option<bool>, Reply> {
+    match query.get(key) {
+        Some(_) => query_parse(query, key).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// 存在するスタイルIDを読み取る.
+fn query_style(query: &HashMap<String, String>, key: &str) -> Result<i32, Reply> {
+    let style = query_parse(query, key)?;
+    match find_style(style) {
+        Some(_) => Ok(style),
+        None => Err(Reply::validation(
+            &["query", key],
+            "該当する話者が見つかりません",
+            "value_error",
+        )),
+    }
+}
+
+fn body_json<T: DeserializeOwned>(body: &[u8]) -> Result<T, Reply> {
+    serde_json::from_slice(body)
+        .map_err(|e| Reply::validation(&["body"], &e.to_string(), "value_error.jsondecode"))
+}
+
+fn audio_query_body(body: &[u8]) -> Result<AudioQuery, Reply> {
+    let audio_query = body_json(body)?;
+    check_audio_query(&audio_query)?;
+    Ok(audio_query)
+}
+
+fn check_audio_query(audio_query: &AudioQuery) -> Result<(), Reply> {
+    if audio_query.outputSamplingRate <= 0 || audio_query.speedScale <= 0.0 {
+        return Err(Reply::validation(
+            &["body"],
+            "outputSamplingRateとspeedScaleは正の値を指定してください",
+            "value_error",
+        ));
+    }
+    Ok(())
+}
+
+/// 歌唱用のスタイルは種類ごとに1つだけ用意している.
+fn query_sing_style(query: &HashMap<String, String>, style: StyleId) -> Result<(), Reply> {
+    match query_parse::<i32>(query, "speaker")? {
+        speaker if speaker == style.0 => Ok(()),
+        _ => Err(Reply::validation(
+            &["query", "speaker"],
+            "該当する話者が見つかりません",
+            "value_error",
+        )),
+    }
+}
+
+fn style_ids() -> impl Iterator<Item = i32> {
+    SPEAKERS
+        .iter()
+        .flat_map(|(_, _, styles, _)| styles.iter().map(|(_, id)| *id))
+}
+
+/// スタイルIDから話者の番号を探す.
+fn find_style(style: i32) -> Option<usize> {
+    SPEAKERS
+        .iter()
+        .position(|(_, _, styles, _)| styles.iter().any(|(_, id)| *id == style))
+}
+
+fn is_morphable(base: i32, target: i32) -> bool {
+    let (Some(base), Some(target)) = (find_style(base), find_style(target)) else {
+        return false;
+    };
+    let allowed = |speaker: usize| match SPEAKERS[speaker].3 {
+        Morphing::All => true,
+        Morphing::SelfOnly => base == target,
+        Morphing::Nothing => false,
+    };
+    allowed(base) && allowed(target)
+}
+
+fn speakers() -> Vec<Speaker> {
+    SPEAKERS
+        .iter()
+        .map(|(name, uuid, styles, morphing)| Speaker {
+            supported_features: SpeakerSupportedFeatures {
+                permitted_synthesis_morphing: match morphing {
+                    Morphing::All => SpeakerSupportPermittedSynthesisMorphing::ALL,
+                    Morphing::SelfOnly => SpeakerSupportPermittedSynthesisMorphing::SELF_ONLY,
+                    Morphing::Nothing => SpeakerSupportPermittedSynthesisMorphing::NOTHING,
+                },
+                extra: Default::default(),
+            },
+            name: name.to_string(),
+            speaker_uuid: SpeakerUuid::from(*uuid),
+            styles: styles
+                .iter()
+                .map(|(name, id)| SpeakerStyle {
+                    name: name.to_string(),
+                    id: StyleId(*id),
+                    style_type: StyleType::Talk,
+                    extra: Default::default(),
+                })
+                .collect(),
+            version: Some(ENGINE_VERSION.to_owned()),
+            extra: Default::default(),
+        })
+        .collect()
+}
+
+fn speaker_info(speaker_uuid: &str) -> Option<SpeakerInfoRaw> {
+    let (name, _, styles, _) = SPEAKERS.iter().find(|s| s.1 == speaker_uuid)?;
+    let ids: Vec<i32> = styles.iter().map(|(_, id)| *id).collect();
+    Some(style_info(name, &ids))
+}
+
+fn style_info(name: &str, styles: &[i32]) -> SpeakerInfoRaw {
+    SpeakerInfoRaw {
+        policy: format!("# {name}\n\nモックの話者です."),
+        portrait: ICON_PNG.to_owned(),
+        style_infos: styles
+            .iter()
+            .map(|id| StyleInfoRaw {
+                id: StyleId(*id),
+                icon: ICON_PNG.to_owned(),
+                voice_samples: (0..3)
+                    .map(|i| {
+                        let sample = synthesis(&audio_query(&"アイウ"[..(i + 1) * 3], *id))
+                            .expect("既定のクエリはwavにできる");
+                        STANDARD.encode(sample)
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+/// ダウンロードできるモックの音声ライブラリ.
+fn library() -> Value {
+    let (name, uuid, _, _) = SPEAKERS[2];
+    json!({
+        "name": "モックライブラリ",
+        "uuid": "00000000-0000-4000-8000-0000000000ff",
+        "version": "0.0.1",
+        "download_url": "http://127.0.0.1/mock_library.zip",
+        "bytes": 1024,
+        "speakers": [{
+            "speaker": speakers().into_iter().find(|s| s.name == name),
+            "speaker_info": speaker_info(uuid),
+        }],
+    })
+}
+
+fn engine_manifest(uuid: &str) -> Value {
+    json!({
+        "manifest_version": "0.13.1",
+        "name": "Mock Engine",
+        "brand_name": "Mock",
+        "uuid": uuid,
+        "url": "http://127.0.0.1/",
+        "icon": ICON_PNG,
+        "default_sampling_rate": 24000,
+        "term_of_service": "モックのエンジンです.",
+        "update_infos": [{
+            "version": ENGINE_VERSION,
+            "descriptions": ["モックのエンジンを追加"],
+            "contributors": [],
+        }],
+        "dependency_licenses": [{
+            "name": "Mock",
+            "version": ENGINE_VERSION,
+            "license": "MIT",
+            "text": "",
+        }],
+        "downloadable_libraries_path": "/downloadable_libraries",
+        "downloadable_libraries_url": "http://127.0.0.1/downloadable_libraries",
+        "supported_features": {
+            "adjust_mora_pitch": true,
+            "adjust_phoneme_length": true,
+            "adjust_speed_scale": true,
+            "adjust_pitch_scale": true,
+            "adjust_intonation_scale": true,
+            "adjust_volume_scale": true,
+            "interrogative_upspeak": true,
+            "synthesis_morphing": true,
+            "manage_library": true,
+        },
+    })
+}
+
+/// ユーザー辞書の単語をクエリから作る.
+fn user_dict_word(query: &HashMap<String, String>) -> Result<Value, Reply> {
+    let surface = query_str(query, "surface")?;
+    let pronunciation = query_str(query, "pronunciation")?;
+    let accent_type = query_parse::<i32>(query, "accent_type")?;
+    let priority = match query.get("priority") {
+        Some(_) => query_parse::<i32>(query, "priority")?,
+        None => 5,
+    };
+    if !(0..=10).contains(&priority) {
+        return Err(Reply::validation(
+            &["query", "priority"],
+            "ensure this value is less than or equal to 10",
+            "value_error.number.not_le",
+        ));
+    }
+    if pronunciation.is_empty() || !pronunciation.chars().all(is_katakana) {
+        return Err(Reply::validation(
+            &["query", "pronunciation"],
+            "発音は有効なカタカナでなくてはいけません。",
+            "value_error",
+        ));
+    }
+    let mora_count = pronunciation
+        .chars()
+        .filter(|c| !"ァィゥェォャュョヮ".contains(*c))
+        .count() as i32;
+    if !(0..=mora_count).contains(&accent_type) {
+        return Err(Reply::validation(
+            &["query", "accent_type"],
+            "誤ったアクセント型です。",
+            "value_error",
+        ));
+    }
+    let (context_id, part_of_speech) = match query.get("word_type").map(String::as_str) {
+        None | Some("PROPER_NOUN") => (1348, ["名詞", "固有名詞", "一般", "*"]),
+        Some("COMMON_NOUN") => (1345, ["名詞", "一般", "*", "*"]),
+        Some("VERB") => (642, ["動詞", "自立", "*", "*"]),
+        Some("ADJECTIVE") => (20, ["形容詞", "自立", "*", "*"]),
+        Some("SUFFIX") => (1358, ["名詞", "接尾", "一般", "*"]),
+        Some(_) => {
+            return Err(Reply::validation(
+                &["query", "word_type"],
+                "value is not a valid enumeration member",
+                "type_error.enum",
+            ))
+        }
+    };
+    Ok(json!({
+        "surface": full_width_surface(surface),
+        "priority": priority,
+        "context_id": context_id,
+        "part_of_speech": part_of_speech[0],
+        "part_of_speech_detail_1": part_of_speech[1],
+        "part_of_speech_detail_2": part_of_speech[2],
+        "part_of_speech_detail_3": part_of_speech[3],
+        "inflectional_type": "*",
+        "inflectional_form": "*",
+        "stem": "*",
+        "yomi": pronunciation,
+        "pronunciation": pronunciation,
+        "accent_type": accent_type,
+        "mora_count": mora_count,
+        "accent_associative_rule": "*",
+    }))
+}
+
+fn is_katakana(c: char) -> bool {
+    ('ァ'..='ヴ').contains(&c) || c == 'ー'
+}
+
+/// テキストの1文字を1モーラとして扱ったクエリ.
+fn audio_query(text: &str, speaker: i32) -> AudioQuery {
+    let accent_phrases = accent_phrases(text, speaker);
+    let kana = kana::create(&accent_phrases);
+    AudioQuery {
+        accent_phrases,
+        kana: Some(kana),
+        ..AudioQuery::default()
+    }
+}
+
+/// 句読点でアクセント句に区切り,アクセントは先頭のモーラに置く.
+fn accent_phrases(text: &str, speaker: i32) -> Vec<AccentPhrase> {
+    let phrases: Vec<&str> = text
+        .split(['、', '。', '，', '．'])
+        .filter(|phrase| !phrase.is_empty())
+        .collect();
+    let count = phrases.len();
+    phrases
+        .into_iter()
+        .enumerate()
+        .map(|(i, phrase)| {
+            let is_interrogative = phrase.ends_with(['？', '?']);
+            let moras = phrase
+                .trim_end_matches(['？', '?'])
+                .chars()
+                .map(|c| mora(c, speaker))
+                .collect();
+            AccentPhrase {
+                moras,
+                accent: 1,
+                pause_mora: (i + 1 < count).then(pause_mora),
+                is_interrogative: Some(is_interrogative),
+            }
+        })
+        .collect()
+}
+
+/// 読み仮名を解析し,長さと音高を埋める.
+fn parse_kana(text: &str, speaker: i32) -> Result<Vec<AccentPhrase>, KanaError> {
+    let mut accent_phrases = kana::parse(text)?;
+    for mora in accent_phrases
+        .iter_mut()
+        .flat_map(|ap| ap.moras.iter_mut().chain(ap.pause_mora.as_mut()))
+    {
+        set_length(mora);
+        set_pitch(mora, speaker);
+    }
+    Ok(accent_phrases)
+}
+
+fn mora(text: char, speaker: i32) -> Mora {
+    let (consonant, vowel) = match text {
+        'ン' => (None, Phoneme::MoraicN),
+        'ッ' => (None, Phoneme::Cl),
+        _ => KANA_ROWS
+            .iter()
+            .find_map(|(row, consonant)| {
+                let column = row.chars().position(|c| c == text)?;
+                Some((consonant.clone(), VOWELS[column].clone()))
+            })
+            .unwrap_or((None, Phoneme::A)),
+    };
+    let mut mora = Mora {
+        text: text.to_string(),
+        consonant,
+        consonant_length: None,
+        vowel,
+        vowel_length: 0.0,
+        pitch: 0.0,
+    };
+    set_length(&mut mora);
+    set_pitch(&mut mora, speaker);
+    mora
+}
+
+fn pause_mora() -> Mora {
+    Mora {
+        text: "、".to_owned(),
+        consonant: None,
+        consonant_length: None,
+        vowel: Phoneme::Pau,
+        vowel_length: 0.3,
+        pitch: 0.0,
+    }
+}
+
+fn set_length(mora: &mut Mora) {
+    mora.consonant_length = mora.consonant.as_ref().map(|_| 0.05);
+    mora.vowel_length = if mora.vowel == Phoneme::Pau { 0.3 } else { 0.1 };
+}
+
+/// 無声化したモーラと無音の音高は0にする.
+fn set_pitch(mora: &mut Mora, speaker: i32) {
+    let voiced = mora.vowel.is_voiced_vowel() || mora.vowel == Phoneme::MoraicN;
+    mora.pitch = if voiced {
+        5.5 + 0.1 * speaker as f64
+    } else {
+        0.0
+    };
+}
+
+/// 音符ごとに子音を2フレーム,残りを母音にする.
+fn sing_frame_audio_query(score: &Score) -> Result<FrameAudioQuery, Reply> {
+    let mut query = FrameAudioQuery {
+        f0: Vec::new(),
+        volume: Vec::new(),
+        phonemes: Vec::new(),
+        volumeScale: 1.0,
+        outputSamplingRate: 24000,
+        outputStereo: false,
+    };
+    for note in &score.notes {
+        let frame_length = note.frame_length.max(0);
+        let (phonemes, f0) = match (note.key, note.lyric.as_str()) {
+            (None, "") => (vec![(Phoneme::Pau, frame_length)], 0.0),
+            (Some(key), lyric) => {
+                let mora = kana::mora(lyric)
+                    .filter(|_| !lyric.starts_with(kana::UNVOICE_SYMBOL))
+                    .ok_or_else(|| Reply::detail(400, json!(format!("無効な歌詞です: {lyric}"))))?;
+                let consonant = match mora.consonant {
+                    Some(consonant) => vec![(consonant, 2.min(frame_length - 1).max(0))],
+                    None => Vec::new(),
+                };
+                let vowel_length = frame_length - consonant.iter().map(|c| c.1).sum::<i32>();
+                let mut phonemes = consonant;
+                phonemes.push((mora.vowel, vowel_length));
+                (phonemes, 440.0 * 2f64.powf((key - 69) as f64 / 12.0))
+            }
+            (None, lyric) => {
+                return Err(Reply::detail(
+                    400,
+                    json!(format!("休符に歌詞は指定できません: {lyric}")),
+                ))
+            }
+        };
+        for (phoneme, frames) in phonemes {
+            query.phonemes.push(FramePhoneme {
+                phoneme,
+                frame_length: frames,
+                note_id: note.id.clone(),
+            });
+        }
+        let volume = if f0 > 0.0 { 0.3 } else { 0.0 };
+        query
+            .f0
+            .extend(std::iter::repeat_n(f0, frame_length as usize));
+        query
+            .volume
+            .extend(std::iter::repeat_n(volume, frame_length as usize));
+    }
+    Ok(query)
+}
+
+/// フレームごとの基本周波数と音量に従って正弦波を鳴らす.
+fn frame_synthesis(query: &FrameAudioQuery) -> Result<Vec<u8>, WavError> {
+    let sample_rate = query.outputSamplingRate as u32;
+    let channels = if query.outputStereo { 2 } else { 1 };
+    let samples_per_frame = sample_rate as f64 / FrameAudioQuery::FRAME_RATE;
+    let samples = (query.f0.len() as f64 * samples_per_frame).round() as usize;
+    let mut phase = 0.0;
+    let samples = (0..samples)
+        .flat_map(|i| {
+            let frame = ((i as f64 / samples_per_frame) as usize).min(query.f0.len() - 1);
+            phase += std::f64::consts::TAU * query.f0[frame] / sample_rate as f64;
+            let amplitude =
+                (query.volume[frame] * query.volumeScale).clamp(0.0, 1.0) * i16::MAX as f64;
+            let sample = (amplitude * phase.sin()) as i32;
+            std::iter::repeat_n(sample, channels as usize)
+        })
+        .collect();
+    Wav {
+        sample_rate,
+        channels,
+        bits_per_sample: 16,
+        samples,
+    }
+    .to_bytes()
+}
+
+/// クエリの長さだけ正弦波を鳴らす.
+fn synthesis(audio_query: &AudioQuery) -> Result<Vec<u8>, WavError> {
+    let phonemes: f64 = audio_query
+        .accent_phrases
+        .iter()
+        .flat_map(|ap| ap.moras.iter().chain(ap.pause_mora.as_ref()))
+        .map(|mora| mora.consonant_length.unwrap_or(0.0) + mora.vowel_length)
+        .sum();
+    let seconds = (audio_query.prePhonemeLength + audio_query.postPhonemeLength + phonemes)
+        / audio_query.speedScale;
+    let sample_rate = audio_query.outputSamplingRate as u32;
+    let channels = if audio_query.outputStereo { 2 } else { 1 };
+    let frames = (seconds.max(0.0) * sample_rate as f64).round() as usize;
+    let frequency = 440.0 * 2f64.powf(audio_query.pitchScale);
+    let amplitude = (0.3 * audio_query.volumeScale).clamp(0.0, 1.0) * i16::MAX as f64;
+    let samples = (0..frames)
+        .flat_map(|frame| {
+            let t = frame as f64 / sample_rate as f64;
+            let sample = (amplitude * (std::f64::consts::TAU * frequency * t).sin()) as i32;
+            std::iter::repeat_n(sample, channels as usize)
+        })
+        .collect();
+    Wav {
+        sample_rate,
+        channels,
+        bits_per_sample: 16,
+        samples,
+    }
+    .to_bytes()
+}
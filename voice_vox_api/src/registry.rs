@@ -0,0 +1,267 @@
+//! 複数のエンジンをまとめて扱う.
+//!
+//! エンジンはマニフェストの`uuid`で区別します.
+//! スタイルIDはエンジンごとに振られるので,エンジンのUUIDとスタイルIDの組でスタイルを指定します.
+//!
+//! ```ignore
+//! let mut registry = EngineRegistry::new();
+//! registry.add(VoiceVoxClient::new("http://localhost:50021")?).await?;
+//! registry.add(VoiceVoxClient::new("http://localhost:50031")?).await?;
+//! let style = registry.route(StyleId(0))?;
+//! let audio_query = registry.audio_query(&style, "こんにちは").await?;
+//! let wav = registry.synthesis(&style, audio_query).await?;
+//! ```
+
+use crate::{
+    api::{self, EngineManifest, Speakers, Version},
+    api_schema::{self, Speaker, SpeakerStyle, StyleId},
+    capabilities::EngineCapabilities,
+    client::{DefaultTransport, VoiceVoxClient},
+    endpoint::Endpoint,
+    error::APIError,
+    transport::Transport,
+    version::EngineVersion,
+};
+
+/// エンジンのUUIDとスタイルIDの組.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EngineStyleId {
+    pub engine_id: String,
+    pub style_id: StyleId,
+}
+
+/// エンジンのUUIDを付けた話者.
+#[derive(Debug, Clone)]
+pub struct EngineSpeaker {
+    pub engine_id: String,
+    pub speaker: Speaker,
+}
+
+impl EngineSpeaker {
+    /// エンジンのUUIDを付けたスタイル.
+    pub fn styles(&self) -> impl Iterator<Item = (EngineStyleId, &SpeakerStyle)> {
+        self.speaker.styles.iter().map(|style| {
+            let id = EngineStyleId {
+                engine_id: self.engine_id.clone(),
+                style_id: style.id,
+            };
+            (id, style)
+        })
+    }
+}
+
+/// 登録されているエンジン.
+#[derive(Debug, Clone)]
+pub struct RegisteredEngine<T = DefaultTransport> {
+    pub manifest: api_schema::EngineManifest,
+    pub speakers: Vec<Speaker>,
+    client: VoiceVoxClient<T>,
+}
+
+impl<T> RegisteredEngine<T> {
+    /// マニフェストの`uuid`.
+    pub fn id(&self) -> &str {
+        &self.manifest.uuid
+    }
+
+    /// 対応している機能が設定されたクライアント.
+    pub fn client(&self) -> &VoiceVoxClient<T> {
+        &self.client
+    }
+
+    pub fn has_style(&self, style_id: StyleId) -> bool {
+        self.speakers
+            .iter()
+            .any(|speaker| speaker.styles.iter().any(|style| style.id == style_id))
+    }
+}
+
+/// マニフェストの`uuid`で区別したエンジンの集まり.
+///
+/// 登録した順に並びます.
+#[derive(Debug, Clone)]
+pub struct EngineRegistry<T = DefaultTransport> {
+    engines: Vec<RegisteredEngine<T>>,
+}
+
+impl<T> Default for EngineRegistry<T> {
+    fn default() -> Self {
+        Self {
+            engines: Vec::new(),
+        }
+    }
+}
+
+impl<T> EngineRegistry<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 取得済みのマニフェストと話者でエンジンを登録する.
+    ///
+    /// 同じUUIDのエンジンが登録されている場合は置き換えます.
+    pub fn insert(
+        &mut self,
+        client: VoiceVoxClient<T>,
+        manifest: api_schema::EngineManifest,
+        speakers: Vec<Speaker>,
+    ) -> &RegisteredEngine<T> {
+        let engine = RegisteredEngine {
+            manifest,
+            speakers,
+            client,
+        };
+        let index = match self.engines.iter().position(|e| e.id() == engine.id()) {
+            Some(index) => {
+                self.engines[index] = engine;
+                index
+            }
+            None => {
+                self.engines.push(engine);
+                self.engines.len() - 1
+            }
+        };
+        &self.engines[index]
+    }
+
+    /// 登録を解除する.
+    pub fn remove(&mut self, engine_id: &str) -> Option<RegisteredEngine<T>> {
+        let index = self.engines.iter().position(|e| e.id() == engine_id)?;
+        Some(self.engines.remove(index))
+    }
+
+    pub fn engine(&self, engine_id: &str) -> Option<&RegisteredEngine<T>> {
+        self.engines.iter().find(|e| e.id() == engine_id)
+    }
+
+    pub fn engines(&self) -> &[RegisteredEngine<T>] {
+        &self.engines
+    }
+
+    /// 全てのエンジンの話者.
+    pub fn speakers(&self) -> Vec<EngineSpeaker> {
+        self.engines
+            .iter()
+            .flat_map(|engine| {
+                engine.speakers.iter().map(|speaker| EngineSpeaker {
+                    engine_id: engine.id().to_owned(),
+                    speaker: speaker.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// スタイルIDを持っているエンジンを探す.
+    ///
+    /// 複数のエンジンが同じIDのスタイルを持っている場合は`APIError::AmbiguousStyle`になります.
+    pub fn route(&self, style_id: StyleId) -> Result<EngineStyleId, APIError> {
+        let engines: Vec<&str> = self
+            .engines
+            .iter()
+            .filter(|engine| engine.has_style(style_id))
+            .map(RegisteredEngine::id)
+            .collect();
+        match engines[..] {
+            [] => Err(APIError::UnknownStyle(style_id)),
+            [engine_id] => Ok(EngineStyleId {
+                engine_id: engine_id.to_owned(),
+                style_id,
+            }),
+            _ => Err(APIError::AmbiguousStyle {
+                style_id,
+                engines: engines.into_iter().map(str::to_owned).collect(),
+            }),
+        }
+    }
+
+    /// スタイルを持っているエンジンのクライアント.
+    pub fn client_for(&self, style: &EngineStyleId) -> Result<&VoiceVoxClient<T>, APIError> {
+        let engine = self
+            .engine(&style.engine_id)
+            .ok_or_else(|| APIError::UnknownEngine(style.engine_id.clone()))?;
+        if !engine.has_style(style.style_id) {
+            return Err(APIError::UnknownStyle(style.style_id));
+        }
+        Ok(engine.client())
+    }
+
+    /// マニフェストから求めた機能をクライアントに設定して登録する.
+    fn register(
+        &mut self,
+        client: VoiceVoxClient<T>,
+        version: EngineVersion,
+        manifest: api_schema::EngineManifest,
+        speakers: Vec<Speaker>,
+    ) -> &RegisteredEngine<T> {
+        let capabilities = EngineCapabilities::from_manifest(&manifest, version);
+        self.insert(client.with_capabilities(capabilities), manifest, speakers)
+    }
+}
+
+impl<T: Transport> EngineRegistry<T> {
+    /// エンジンのバージョン,マニフェスト,話者を取得して登録する.
+    pub async fn add(
+        &mut self,
+        client: VoiceVoxClient<T>,
+    ) -> Result<&RegisteredEngine<T>, APIError> {
+        let version = Version::detect(&client).await?;
+        let manifest = client.send(EngineManifest).await?;
+        let speakers = client.send(Speakers { core_version: None }).await?;
+        Ok(self.register(client, version, manifest, speakers))
+    }
+
+    /// 登録されているエンジンにリクエストを送信する.
+    pub async fn send<E: Endpoint>(
+        &self,
+        engine_id: &str,
+        endpoint: E,
+    ) -> Result<E::Response, E::Error> {
+        let engine = self
+            .engine(engine_id)
+            .ok_or_else(|| APIError::UnknownEngine(engine_id.to_owned()))?;
+        engine.client().send(endpoint).await
+    }
+
+    /// スタイルを持っているエンジンでクエリを作成する.
+    pub async fn audio_query(
+        &self,
+        style: &EngineStyleId,
+        text: &str,
+    ) -> Result<api_schema::AudioQuery, APIError> {
+        let endpoint = api::AudioQuery {
+            text: text.to_owned(),
+            speaker: style.style_id,
+            core_version: None,
+        };
+        self.client_for(style)?.send(endpoint).await
+    }
+
+    /// スタイルを持っているエンジンで音声合成する.
+    pub async fn synthesis(
+        &self,
+        style: &EngineStyleId,
+        audio_query: api_schema::AudioQuery,
+    ) -> Result<Vec<u8>, APIError> {
+        let endpoint = api::Synthesis {
+            speaker: style.style_id,
+            enable_interrogative_upspeak: None,
+            core_version: None,
+            audio_query,
+        };
+        self.client_for(style)?.send(endpoint).await
+    }
+}
+
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+impl<T: crate::blocking::BlockingTransport> EngineRegistry<T> {
+    /// `add`と同じリクエストを同期的に送信して登録する.
+    pub fn add_blocking(
+        &mut self,
+        client: VoiceVoxClient<T>,
+    ) -> Result<&RegisteredEngine<T>, APIError> {
+        let version = Version::detect_blocking(&client)?;
+        let manifest = client.send_blocking(EngineManifest)?;
+        let speakers = client.send_blocking(Speakers { core_version: None })?;
+        Ok(self.register(client, version, manifest, speakers))
+    }
+}
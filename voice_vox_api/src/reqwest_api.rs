@@ -1,931 +1,128 @@
-//! definition of VoiceVox openapi path section
-//!
+//! reqwestを使ったバックエンド.
 //!
 
-use crate::api_schema::{
-    self, AccentPhrase, AccentPhrasesResponse, EngineManifestRaw, HttpValidationError,
-    MorphableTargetInfo, ParseKanaBadRequest, WordType,
+use crate::{
+    error::APIError,
+    transport::{HttpRequest, HttpResponse, Method, Transport, TransportConfig},
 };
 
-use base64::{engine, Engine};
-use once_cell::race::OnceBox;
-use reqwest::{Error, StatusCode};
-use std::{collections::HashMap, convert::TryInto, io::ErrorKind};
-
-pub type CoreVersion = Option<String>;
+use std::{sync::Arc, time::Duration};
 
-///シングルトン reqwest::Client
-static CLIENT: OnceBox<reqwest::Client> = once_cell::race::OnceBox::new();
-
-///クライアントのシングルトンの作成/取得を行う.
-fn client() -> &'static reqwest::Client {
-    CLIENT.get_or_init(|| Box::new(reqwest::Client::new()))
-}
-
-/// # 音声合成用のクエリを作成する
-///
-/// クエリの初期値を得ます。ここで得られたクエリはそのまま音声合成に利用できます。各値の意味はSchemasを参照してください。
-///
+/// reqwest::Clientでリクエストを送信する.
 #[derive(Debug, Clone)]
-pub struct AudioQuery {
-    pub text: String,
-    pub speaker: i32,
-    pub core_version: CoreVersion,
+pub struct ReqwestTransport {
+    client: reqwest::Client,
 }
 
-impl AudioQuery {
-    pub async fn call(self, server: &str) -> Result<crate::api_schema::AudioQuery, APIError> {
-        let request = client()
-            .post(format!("http://{}/audio_query", server))
-            .query(&[("speaker", self.speaker)])
-            .add_core_version(&&self.core_version)
-            .query(&[("text", self.text)])
-            .build()?;
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.json::<_>().await?),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
-        }
-    }
-}
-
-///
-/// # 音声合成用のクエリをプリセットを用いて作成する
-/// クエリの初期値を得ます。ここで得られたクエリはそのまま音声合成に利用できます。各値の意味は`Schemas`を参照してください。
-///
-///
-#[derive(Debug, Clone)]
-pub struct AudioQueryFromPreset {
-    pub text: String,
-    pub preset_id: i32,
-    pub core_version: CoreVersion,
-}
-
-impl AudioQueryFromPreset {
-    pub async fn call(self, server: &str) -> Result<crate::api_schema::AudioQuery, APIError> {
-        let request = client()
-            .post(format!("http://{}/audio_query_from_preset", server))
-            .query(&[("preset_id", self.preset_id)])
-            .add_core_version(&&self.core_version)
-            .query(&[("text", self.text)])
-            .build()?;
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.json::<_>().await?),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
-        }
+impl ReqwestTransport {
+    /// 設定済みのreqwest::Clientを使う.
+    pub fn from_client(client: reqwest::Client) -> Self {
+        Self { client }
     }
 }
 
-/// # テキストからアクセント句を得る
-/// テキストからアクセント句を得ます。
-///
-/// is_kanaが`true`のとき、テキストは次のようなAquesTalkライクな記法に従う読み仮名として処理されます。デフォルトは`false`です。
-///
-/// * 全てのカナはカタカナで記述される
-/// * アクセント句は`/`または`、`で区切る。`、`で区切った場合に限り無音区間が挿入される。
-/// * カナの手前に`_`を入れるとそのカナは無声化される
-/// * アクセント位置を`'`で指定する。全てのアクセント句にはアクセント位置を1つ指定する必要がある。
-/// * アクセント句末に`？`(全角)を入れることにより疑問文の発音ができる。
-///
-#[derive(Debug, Clone)]
-pub struct AccentPhrases {
-    pub text: String,
-    pub speaker: i32,
-    pub is_kana: Option<bool>,
-    pub core_version: CoreVersion,
-}
-
-#[derive(Debug)]
-pub enum AccentPhrasesErrors {
-    KanaParseError(ParseKanaBadRequest),
-    ApiError(APIError),
-}
-
-impl From<reqwest::Error> for AccentPhrasesErrors {
-    fn from(e: Error) -> Self {
-        AccentPhrasesErrors::ApiError(e.into())
-    }
-}
-
-impl From<reqwest::StatusCode> for AccentPhrasesErrors {
-    fn from(e: reqwest::StatusCode) -> Self {
-        AccentPhrasesErrors::ApiError(e.into())
-    }
-}
-
-impl AccentPhrases {
-    pub async fn call(self, server: &str) -> Result<AccentPhrasesResponse, AccentPhrasesErrors> {
-        let request = client()
-            .post(format!("http://{}/accent_phrases", server))
-            .query(&[("speaker", self.speaker)])
-            .add_core_version(&self.core_version)
-            .query(&[("is_kana", self.is_kana.unwrap_or(false))])
-            .query(&[("text", self.text)])
-            .build()?;
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.json::<_>().await?),
-            StatusCode::BAD_REQUEST => {
-                Err(AccentPhrasesErrors::KanaParseError(res.json::<_>().await?))
+impl Transport for ReqwestTransport {
+    fn new(config: &TransportConfig) -> Result<Self, APIError> {
+        let builder = reqwest::Client::builder();
+        #[cfg(not(target_arch = "wasm32"))]
+        let builder = {
+            let builder = match config.timeout {
+                Some(timeout) => builder.timeout(timeout),
+                None => builder,
+            };
+            match config.connect_timeout {
+                Some(timeout) => builder.connect_timeout(timeout),
+                None => builder,
             }
-            StatusCode::UNPROCESSABLE_ENTITY => Err(AccentPhrasesErrors::ApiError(
-                APIError::Validation(res.json::<_>().await?),
-            )),
-            x => Err(x.into()),
-        }
-    }
-}
-
-///アクセント句から音高を得る
-#[derive(Debug, Clone)]
-pub struct MoraData {
-    //in query
-    pub speaker: i32,
-    pub core_version: CoreVersion,
-    //in body
-    pub accent_phrases: Vec<AccentPhrase>,
-}
-
-impl MoraData {
-    pub async fn call(self, server: &str) -> Result<Vec<AccentPhrase>, APIError> {
-        let request = client()
-            .post(format!("http://{}/mora_data", server))
-            .query(&[("speaker", self.speaker)])
-            .add_core_version(&self.core_version)
-            .json(&self.accent_phrases)
-            .build()?;
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.json::<_>().await?),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
-        }
-    }
-}
-
-/// # アクセント句から音素長を得る
-#[derive(Debug, Clone)]
-pub struct MoraLength {
-    // in query.
-    pub speaker: i32,
-    pub core_version: CoreVersion,
-    // in body.
-    pub accent_phrases: Vec<AccentPhrase>,
-}
-
-impl MoraLength {
-    pub async fn call(self, server: &str) -> Result<Vec<AccentPhrase>, APIError> {
-        let request = client()
-            .post(format!("http://{}/mora_length", server))
-            .query(&[("speaker", self.speaker)])
-            .add_core_version(&self.core_version)
-            .json(&self.accent_phrases)
-            .build()?;
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.json::<_>().await?),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
-        }
-    }
-}
-
-/// # アクセント句から音素長を得る
-#[derive(Debug, Clone)]
-pub struct MoraPitch {
-    // in query.
-    pub speaker: i32,
-    pub core_version: CoreVersion,
-    // in body.
-    pub accent_phrases: Vec<AccentPhrase>,
-}
-
-impl MoraPitch {
-    pub async fn call(self, server: &str) -> Result<Vec<AccentPhrase>, APIError> {
-        let request = client()
-            .post(format!("http://{}/mora_pitch", server))
-            .query(&[("speaker", self.speaker)])
-            .add_core_version(&self.core_version)
-            .json(&self.accent_phrases)
-            .build()?;
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.json::<_>().await?),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
-        }
-    }
-}
-
-/// # 音声合成する
-#[derive(Debug, Clone)]
-pub struct Synthesis {
-    // in query
-    pub speaker: i32,
-    pub enable_interrogative_upspeak: Option<bool>,
-    pub core_version: CoreVersion,
-    // in body json.
-    pub audio_query: crate::api_schema::AudioQuery,
-}
-
-impl Synthesis {
-    pub async fn call(self, server: &str) -> Result<Vec<u8>, APIError> {
-        let request = client()
-            .post(format!("http://{}/synthesis", server))
-            .query(&[("speaker", self.speaker)])
-            .query(&[(
-                "enable_interrogative_upspeak",
-                self.enable_interrogative_upspeak.unwrap_or(true),
-            )])
-            .add_core_version(&self.core_version)
-            .json(&self.audio_query)
-            .build()?;
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.bytes().await.unwrap_or_default().to_vec()),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
-        }
-    }
-}
-
-/// # 音声合成する（キャンセル可能）
-#[derive(Debug, Clone)]
-pub struct CancellableSynthesis {
-    // in query
-    pub speaker: i32,
-    pub core_version: CoreVersion,
-    // in body json.
-    pub audio_query: crate::api_schema::AudioQuery,
-}
-
-impl CancellableSynthesis {
-    pub async fn call(self, server: &str) -> Result<Vec<u8>, APIError> {
-        let request = client()
-            .post(format!("http://{}/cancellable_synthesis", server))
-            .query(&[("speaker", self.speaker)])
-            .add_core_version(&self.core_version)
-            .json(&self.audio_query)
-            .build()?;
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.bytes().await.unwrap_or_default().to_vec()),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
-        }
-    }
-}
-
-/// # まとめて音声合成する
-///
-/// 複数のwavがzipでまとめられて返されます.
-#[derive(Debug, Clone)]
-pub struct MultiSynthesis {
-    // in query
-    pub speaker: i32,
-    pub core_version: CoreVersion,
-    // in body json.
-    pub audio_query: Vec<crate::api_schema::AudioQuery>,
-}
-
-impl MultiSynthesis {
-    pub async fn call(self, server: &str) -> Result<Vec<u8>, APIError> {
-        let request = client()
-            .post(format!("http://{}/multi_synthesis", server))
-            .query(&[("speaker", self.speaker)])
-            .add_core_version(&self.core_version)
-            .json(&self.audio_query)
-            .build()
-            .unwrap();
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.bytes().await.unwrap_or_default().to_vec()),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
-        }
-    }
-}
-
-/// # 2人の話者でモーフィングした音声を合成する
-///
-/// 指定された2人の話者で音声を合成、指定した割合でモーフィングした音声を得ます。 モーフィングの割合はmorph_rateで指定でき、0.0でベースの話者、1.0でターゲットの話者に近づきます。
-#[derive(Debug, Clone)]
-pub struct SynthesisMorphing {
-    // in query
-    pub base_speaker: i32,
-    pub target_speaker: i32,
-    pub morph_rate: f64,
-    pub core_version: CoreVersion,
-    // in body json.
-    pub audio_query: crate::api_schema::AudioQuery,
-}
-
-/// 指定されたベース話者に対してエンジン内の各話者がモーフィング機能を利用可能か返します。
-/// モーフィングの許可/禁止は/speakersのspeaker.supported_features.synthesis_morphingに記載されています。
-/// プロパティが存在しない場合は、モーフィングが許可されているとみなします。
-pub struct MorpableTargets {
-    // in request json.
-    pub style_id: Vec<i32>,
-    // in query
-    pub core_version: CoreVersion,
-}
-
-impl MorpableTargets {
-    pub async fn call(
-        self,
-        server: &str,
-    ) -> Result<Vec<HashMap<i32, MorphableTargetInfo>>, APIError> {
-        let request = client()
-            .post(format!("http://{}/morphable_targets", server))
-            .add_core_version(&self.core_version)
-            .json(&self.style_id)
-            .build()?;
-        let res = client().execute(request).await?;
-        match res.status() {
-            StatusCode::OK => Ok(res
-                .json::<Vec<HashMap<String, MorphableTargetInfo>>>()
-                .await
-                .map(|mut results| {
-                    results
-                        .drain(..)
-                        .map(|mut morphable_targets| {
-                            morphable_targets
-                                .drain()
-                                .map(|(style_id, mti)| (style_id.parse::<i32>().unwrap(), mti))
-                                .collect()
-                        })
-                        .collect()
-                })?),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
-        }
-    }
-}
-
-impl SynthesisMorphing {
-    pub async fn call(self, server: &str) -> Result<Vec<u8>, APIError> {
-        let request = client()
-            .post(format!("http://{}/synthesis_morphing", server))
-            .query(&[
-                ("base_speaker", self.base_speaker),
-                ("target_speaker", self.target_speaker),
-            ])
-            .query(&[("morph_rate", self.morph_rate)])
-            .add_core_version(&self.core_version)
-            .json(&self.audio_query)
-            .build()
-            .unwrap();
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.bytes().await.unwrap_or_default().to_vec()),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
-        }
-    }
-}
-
-/// # base64エンコードされた複数のwavデータを一つに結合する
-///
-/// base64エンコードされたwavデータを一纏めにし、wavファイルで返します。
-/// 返されるwavはbase64デコードを行います.
-///
-#[derive(Debug, Clone)]
-pub struct ConnectWaves {
-    pub waves: Vec<Vec<u8>>,
-}
-
-impl ConnectWaves {
-    pub async fn call(self, server: &str) -> Result<Vec<u8>, APIError> {
-        let mut buffer = Vec::new();
-        for wave in self.waves {
-            buffer.push(engine::general_purpose::STANDARD.encode(wave));
-        }
-        let request = client()
-            .post(format!("http://{}/connect_waves", server))
-            .json(&buffer)
-            .build()
-            .unwrap();
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(engine::general_purpose::STANDARD
-                .decode(res.text().await?.as_bytes())
-                .unwrap_or_default()),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
-        }
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct Presets;
-
-impl Presets {
-    pub async fn call(self, server: &str) -> Result<Vec<crate::api_schema::Preset>, APIError> {
-        let request = client()
-            .get(format!("http://{}/presets", server))
-            .build()
-            .unwrap();
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.json::<Vec<crate::api_schema::Preset>>().await?),
-            x => Err(x.into()),
-        }
-    }
-}
-///新しいプリセットを追加します
-///
-/// # Parameters
-///
-/// preset: Preset 新しいプリセット。 プリセットIDが既存のものと重複している場合は、新規のプリセットIDが採番されます。
-///
-/// # Returns
-///
-/// id: int 追加したプリセットのプリセットID
-pub struct AddPreset {
-    pub preset: api_schema::Preset,
-}
-impl AddPreset {
-    pub async fn call(self, server: &str) -> Result<i32, APIError> {
-        let request = client()
-            .post(format!("http://{}/add_preset", server))
-            .json(&self.preset)
-            .build()
-            .unwrap();
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.json::<i32>().await?),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
-        }
-    }
-}
-///既存のプリセットを更新します
-/// # Parameters
-///
-/// preset: Preset 更新するプリセット。 プリセットIDが更新対象と一致している必要があります。
-///
-/// # Returns
-///
-/// id: int 更新したプリセットのプリセットID
-pub struct UpdatePreset {
-    pub preset: api_schema::Preset,
-}
-impl UpdatePreset {
-    pub async fn call(self, server: &str) -> Result<i32, APIError> {
-        let request = client()
-            .post(format!("http://{}/update_preset", server))
-            .json(&self.preset)
-            .build()
-            .unwrap();
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.json::<i32>().await?),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
-        }
+        };
+        #[cfg(target_arch = "wasm32")]
+        let _ = config;
+        Ok(Self::from_client(builder.build()?))
     }
-}
 
-///既存のプリセットを削除します
-///
-///Parameters
-///
-///id: int 削除するプリセットのプリセットID
-///
-pub struct DeletePreset {
-    pub preset_id: i32,
-}
-impl DeletePreset {
-    pub async fn call(self, server: &str) -> Result<(), APIError> {
-        let request = client()
-            .post(format!("http://{}/delete_preset", server))
-            .query(&[("id", self.preset_id)])
-            .build()
-            .unwrap();
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::NO_CONTENT => Ok(()),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
+    async fn execute(&self, request: HttpRequest) -> Result<HttpResponse, APIError> {
+        let mut builder = self.client.request(method(request.method), request.url);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
         }
-    }
-}
-#[derive(Debug, Clone)]
-pub struct Version;
-
-impl Version {
-    pub async fn call(self, server: &str) -> Result<Option<String>, APIError> {
-        let request = client()
-            .get(format!("http://{}/version", server))
-            .build()
-            .unwrap();
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.json::<Option<String>>().await?),
-            x => Err(x.into()),
+        if let Some(body) = request.body {
+            builder = builder.body(body);
         }
+        let res = builder.send().await?;
+        let status = res.status().as_u16();
+        let body = res.bytes().await?.to_vec();
+        Ok(HttpResponse { status, body })
     }
-}
-
-#[derive(Debug, Clone)]
-pub struct CoreVersions;
 
-impl CoreVersions {
-    pub async fn call(self, server: &str) -> Result<Vec<String>, APIError> {
-        let request = client()
-            .get(format!("http://{}/core_versions", server))
-            .build()
-            .unwrap();
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res.json::<Vec<String>>().await?),
-            x => Err(x.into()),
-        }
+    /// reqwestの非同期クライアントと同じくtokioのタイマーを使います.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await
     }
 }
 
+/// reqwest::blocking::Clientでリクエストを送信する.
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
 #[derive(Debug, Clone)]
-pub struct Speakers {
-    pub core_version: CoreVersion,
+pub struct ReqwestBlockingTransport {
+    client: reqwest::blocking::Client,
 }
 
-impl Speakers {
-    pub async fn call(self, server: &str) -> Result<Vec<crate::api_schema::Speaker>, APIError> {
-        let request = client()
-            .get(format!("http://{}/speakers", server))
-            .add_core_version(&self.core_version)
-            .build()
-            .unwrap();
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            StatusCode::OK => Ok(res.json::<Vec<crate::api_schema::Speaker>>().await?),
-            x => Err(x.into()),
-        }
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+impl ReqwestBlockingTransport {
+    /// 設定済みのreqwest::blocking::Clientを使う.
+    pub fn from_client(client: reqwest::blocking::Client) -> Self {
+        Self { client }
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct SpeakerInfo {
-    pub speaker_uuid: String,
-    pub core_version: CoreVersion,
-}
-
-impl SpeakerInfo {
-    pub async fn call(self, server: &str) -> Result<crate::api_schema::SpeakerInfo, APIError> {
-        let req = client()
-            .get(format!("http://{}/speaker_info", server))
-            .query(&[("speaker_uuid", self.speaker_uuid)])
-            .add_core_version(&self.core_version)
-            .build()
-            .unwrap();
-        let res = client().execute(req).await.unwrap();
-        match res.status() {
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            StatusCode::OK => res
-                .json::<crate::api_schema::SpeakerInfoRaw>()
-                .await?
-                .try_into()
-                .map_err(|_| APIError::Io(std::io::Error::from(ErrorKind::InvalidData))),
-            x => Err(x.into()),
-        }
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+impl crate::blocking::BlockingTransport for ReqwestBlockingTransport {
+    fn new(config: &TransportConfig) -> Result<Self, APIError> {
+        let builder = reqwest::blocking::Client::builder().timeout(config.timeout);
+        let builder = match config.connect_timeout {
+            Some(timeout) => builder.connect_timeout(timeout),
+            None => builder,
+        };
+        Ok(Self::from_client(builder.build()?))
     }
-}
-
-#[derive(Debug, Clone)]
-pub struct SupportedDevices {
-    pub core_version: CoreVersion,
-}
 
-impl SupportedDevices {
-    pub async fn call(self, server: &str) -> Result<crate::api_schema::SupportedDevices, APIError> {
-        let request = client()
-            .get(format!("http://{}/supported_devices", server))
-            .add_core_version(&self.core_version)
-            .build()
-            .unwrap();
-        let res = client().execute(request).await.unwrap();
-        match res.status() {
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            StatusCode::OK => Ok(res.json::<crate::api_schema::SupportedDevices>().await?),
-            x => Err(x.into()),
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, APIError> {
+        let mut builder = self.client.request(method(request.method), request.url);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
         }
-    }
-}
-
-pub trait AddCoreVersion {
-    fn add_core_version(self, core_version: &CoreVersion) -> Self;
-}
-
-impl AddCoreVersion for reqwest::RequestBuilder {
-    fn add_core_version(self, core_version: &CoreVersion) -> Self {
-        if let Some(cv) = &core_version {
-            self.query(&[("core_version", cv)])
-        } else {
-            self
+        if let Some(body) = request.body {
+            builder = builder.body(body);
         }
+        let res = builder.send()?;
+        let status = res.status().as_u16();
+        let body = res.bytes()?.to_vec();
+        Ok(HttpResponse { status, body })
     }
 }
 
-/// Clone is implemented but Io and Reqwest errors will converted to Unknown error.
-///
-#[derive(Debug)]
-pub enum APIError {
-    Validation(HttpValidationError),
-    Io(std::io::Error),
-    Reqwest(reqwest::Error),
-    Unknown,
-}
-
-impl Clone for APIError {
-    fn clone(&self) -> Self {
-        match self {
-            Self::Validation(arg0) => Self::Validation(arg0.clone()),
-            Self::Io(_) => Self::Unknown,
-            Self::Reqwest(_) => Self::Unknown,
-            Self::Unknown => Self::Unknown,
-        }
+fn method(method: Method) -> reqwest::Method {
+    match method {
+        Method::Get => reqwest::Method::GET,
+        Method::Post => reqwest::Method::POST,
+        Method::Put => reqwest::Method::PUT,
+        Method::Delete => reqwest::Method::DELETE,
     }
 }
 
 impl From<reqwest::Error> for APIError {
-    fn from(e: Error) -> Self {
-        APIError::Reqwest(e)
-    }
-}
-
-impl Into<APIError> for std::io::Error {
-    fn into(self) -> APIError {
-        APIError::Io(self)
-    }
-}
-
-impl From<StatusCode> for APIError {
-    fn from(_: StatusCode) -> Self {
-        APIError::Unknown
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct DownloadableLibraries;
-
-impl DownloadableLibraries {
-    pub async fn call(
-        self,
-        server: &str,
-    ) -> Result<crate::api_schema::DownloadableLibraries, APIError> {
-        let req = client()
-            .get(format!("http://{}/downloadble_libraries", server))
-            .build()
-            .unwrap();
-        let res = client().execute(req).await.unwrap();
-        match res.status() {
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            StatusCode::OK => res
-                .json::<crate::api_schema::DownloadableLibrariesRaw>()
-                .await?
-                .try_into()
-                .map_err(|_| APIError::Io(std::io::Error::from(ErrorKind::InvalidData))),
-            x => Err(x.into()),
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            return APIError::Timeout(Arc::new(e));
         }
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct InitializeSpeaker {
-    pub speaker: i32,
-    pub core_version: CoreVersion,
-}
-
-impl InitializeSpeaker {
-    pub async fn call(self, server: &str) -> Result<(), APIError> {
-        let req = client()
-            .post(format!("http://{}/initialize_speaker", server))
-            .query(&[("speaker", self.speaker)])
-            .add_core_version(&self.core_version)
-            .build()
-            .unwrap();
-        let res = client().execute(req).await.unwrap();
-        match res.status() {
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            StatusCode::NO_CONTENT => Ok(()),
-            x => Err(x.into()),
+        #[cfg(not(target_arch = "wasm32"))]
+        if e.is_connect() {
+            return APIError::Connect(Arc::new(e));
         }
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct IsInitializedSpeaker {
-    pub speaker: i32,
-    pub core_version: CoreVersion,
-}
-
-impl IsInitializedSpeaker {
-    pub async fn call(self, server: &str) -> Result<bool, APIError> {
-        let req = client()
-            .get(format!("http://{}/is_initialized_speaker", server))
-            .query(&[("speaker", self.speaker)])
-            .add_core_version(&self.core_version)
-            .build()
-            .unwrap();
-        let res = client().execute(req).await.unwrap();
-        match res.status() {
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            StatusCode::OK => Ok(res.json::<bool>().await?),
-            x => Err(x.into()),
-        }
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct EngineManifest;
-
-impl EngineManifest {
-    pub async fn call(self, server: &str) -> Result<crate::api_schema::EngineManifest, APIError> {
-        let req = client()
-            .get(format!("http://{}/engine_manifest", server))
-            .build()
-            .unwrap();
-        let res = client().execute(req).await.unwrap();
-        match res.status() {
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            StatusCode::OK => res
-                .json::<EngineManifestRaw>()
-                .await?
-                .try_into()
-                .map_err(|_| APIError::Io(std::io::Error::from(ErrorKind::InvalidData))),
-            x => Err(x.into()),
-        }
-    }
-}
-
-/// Get registered word list from user dictionary.
-///
-/// This result contains word UUID and definition.
-///
-#[derive(Debug, Clone)]
-pub struct UserDict;
-
-impl UserDict {
-    pub async fn call(
-        self,
-        server: &str,
-    ) -> Result<HashMap<String, crate::api_schema::UserDictWord>, APIError> {
-        let req = client()
-            .get(format!("http://{}/user_dict", server))
-            .build()
-            .unwrap();
-        let res = client().execute(req).await.unwrap();
-        match res.status() {
-            StatusCode::OK => Ok(res
-                .json::<HashMap<String, crate::api_schema::UserDictWord>>()
-                .await?),
-            x => Err(x.into()),
-        }
-    }
-}
-
-/// Add word to user dictionary.
-///
-/// This result returns word UUID.
-///
-#[derive(Debug, Clone)]
-pub struct UserDictWord {
-    ///言葉の表層形
-    pub surface: String,
-    ///言葉の発音(カタカナ)
-    pub pronunciation: String,
-    /// アクセント型(音が下がる場所)
-    pub accent_type: i32,
-    pub word_type: Option<WordType>,
-    ///単語の優先度
-    pub priority: Option<i32>,
-}
-
-impl UserDictWord {
-    pub async fn call(self, server: &str) -> Result<String, APIError> {
-        let req = client()
-            .post(format!("http://{}/user_dict_word", server))
-            .query(&[
-                ("surface", self.surface),
-                ("pronunciation", self.pronunciation),
-            ])
-            .query(&[("accent_type", self.accent_type)]);
-        let req = if let Some(priority) = self.priority {
-            req.query(&[("priority", priority)])
-        } else {
-            req
-        };
-        let req = if let Some(word_type) = self.word_type {
-            req.query(&[("word_type", word_type.to_string())])
-        } else {
-            req
-        }
-        .build()
-        .unwrap();
-        let res = client().execute(req).await.unwrap();
-        match res.status() {
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            StatusCode::OK => Ok(res.text().await?),
-            x => Err(x.into()),
-        }
-    }
-}
-
-/// rewrite word on user dictionary.
-///
-#[derive(Debug, Clone)]
-pub struct RewriteUserDictWord {
-    /// word uuid
-    pub uuid: String,
-    ///言葉の表層形
-    pub surface: String,
-    ///言葉の発音(カタカナ)
-    pub pronunciation: String,
-    /// アクセント型(音が下がる場所)
-    pub accent_type: i32,
-    pub word_type: Option<WordType>,
-    ///単語の優先度
-    pub priority: Option<i32>,
-}
-
-impl RewriteUserDictWord {
-    pub async fn call(self, server: &str) -> Result<(), APIError> {
-        let req = client()
-            .put(format!("http://{}/user_dict_word/{}", server, self.uuid))
-            .query(&[
-                ("surface", self.surface),
-                ("pronunciation", self.pronunciation),
-            ])
-            .query(&[("accent_type", self.accent_type)]);
-        let req = if let Some(priority) = self.priority {
-            req.query(&[("priority", priority)])
-        } else {
-            req
-        };
-        let req = if let Some(word_type) = self.word_type {
-            req.query(&[("word_type", word_type.to_string())])
-        } else {
-            req
-        }
-        .build()
-        .unwrap();
-        let res = client().execute(req).await.unwrap();
-        match res.status() {
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            StatusCode::NO_CONTENT => Ok(()),
-            x => Err(x.into()),
-        }
-    }
-}
-
-/// delete word from user dictionary.
-///
-///
-#[derive(Debug, Clone)]
-pub struct DeleteUserDictWord {
-    /// word uuid
-    pub uuid: String,
-}
-
-impl DeleteUserDictWord {
-    pub async fn call(self, server: &str) -> Result<(), APIError> {
-        let req = client()
-            .delete(format!("http://{}/user_dict_word/{}", server, self.uuid))
-            .build()
-            .unwrap();
-        let res = client().execute(req).await.unwrap();
-        match res.status() {
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            StatusCode::NO_CONTENT => Ok(()),
-            x => Err(x.into()),
-        }
-    }
-}
-
-/// import user dictionary.
-///
-#[derive(Debug, Clone)]
-pub struct ImportUserDict {
-    pub over_ride: bool,
-    pub dictionary: HashMap<String, api_schema::UserDictWord>,
-}
-
-impl ImportUserDict {
-    pub async fn call(self, server: &str) -> Result<(), APIError> {
-        let req = client()
-            .post(format!("http://{}/import_user_dict", server))
-            .json(&self.dictionary)
-            .build()
-            .unwrap();
-        let res = client().execute(req).await?;
-        match res.status() {
-            StatusCode::NO_CONTENT => Ok(()),
-            StatusCode::UNPROCESSABLE_ENTITY => Err(APIError::Validation(res.json::<_>().await?)),
-            x => Err(x.into()),
+        if e.is_builder() {
+            return APIError::InvalidConfig(e.to_string());
         }
+        APIError::Transport(Arc::new(e))
     }
 }
@@ -0,0 +1,137 @@
+//! 失敗したリクエストの再試行.
+//!
+//! エンジンの起動直後はモデルの読み込みが終わるまで`InitializeSpeaker`や最初の`Synthesis`が
+//! 失敗したりタイムアウトしたりします.
+//! `VoiceVoxClientBuilder::retry`で設定すると,全てのリクエストを同じ規則で再試行します.
+//! `Endpoint::IDEMPOTENT`でない`AddPreset`や`UserDictWord`などは接続できなかった場合だけ再試行します.
+//! 待つ時間は`Transport::sleep`で計ります.wasmでは再試行しません.
+//!
+//! ```ignore
+//! let client = VoiceVoxClient::builder("http://localhost:50021")
+//!     .connect_timeout(Duration::from_secs(1))
+//!     .timeout(Duration::from_secs(60))
+//!     .retry(RetryPolicy::new(5))
+//!     .build()?;
+//! client.wait_until_ready(Duration::from_secs(30)).await?;
+//! ```
+
+use crate::{error::APIError, transport::HttpResponse};
+use std::time::Duration;
+
+/// 再試行の回数と間隔,再試行するエラー.
+///
+/// 間隔は`initial_backoff`から始めて失敗するたびに`multiplier`倍し,`max_backoff`で打ち切ります.
+/// 既定では再試行しません.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// 最初の送信に加えて送信する回数.
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// 再試行するステータスコード.
+    pub retry_statuses: Vec<u16>,
+    /// 接続できなかった場合に再試行する.
+    pub retry_connect: bool,
+    /// タイムアウトした場合に再試行する.
+    pub retry_timeout: bool,
+    /// 冪等でないリクエストもタイムアウトやエラーのステータスで再試行する.
+    ///
+    /// エンジンが処理を終えている場合もあるので,`UserDictWord`などは同じ内容が2回登録されることがあります.
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl RetryPolicy {
+    /// 接続エラー,タイムアウト,`500`,`502`,`503`,`504`を`max_retries`回まで再試行する.
+    ///
+    /// 冪等でないリクエストは接続エラーだけを再試行します.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            retry_statuses: vec![500, 502, 503, 504],
+            retry_connect: true,
+            retry_timeout: true,
+            retry_non_idempotent: false,
+        }
+    }
+
+    /// 再試行しない.
+    pub fn never() -> Self {
+        Self::new(0)
+    }
+
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    pub fn retry_statuses(mut self, statuses: impl IntoIterator<Item = u16>) -> Self {
+        self.retry_statuses = statuses.into_iter().collect();
+        self
+    }
+
+    pub fn retry_connect(mut self, retry: bool) -> Self {
+        self.retry_connect = retry;
+        self
+    }
+
+    pub fn retry_timeout(mut self, retry: bool) -> Self {
+        self.retry_timeout = retry;
+        self
+    }
+
+    pub fn retry_non_idempotent(mut self, retry: bool) -> Self {
+        self.retry_non_idempotent = retry;
+        self
+    }
+
+    /// `attempt`回目(0から数える)の再試行の前に待つ時間.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .max(1.0)
+            .powi(attempt.min(i32::MAX as u32) as i32);
+        Duration::try_from_secs_f64(self.initial_backoff.as_secs_f64() * factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    /// 送信の結果が再試行するべき失敗か.
+    ///
+    /// 接続できなかったリクエストはエンジンに届いていないので,`idempotent`でなくても再試行します.
+    pub fn is_retryable(&self, result: &Result<HttpResponse, APIError>, idempotent: bool) -> bool {
+        let resend = idempotent || self.retry_non_idempotent;
+        match result {
+            Ok(response) => resend && self.retry_statuses.contains(&response.status),
+            Err(APIError::Connect(_)) => self.retry_connect,
+            Err(APIError::Timeout(_)) => resend && self.retry_timeout,
+            Err(_) => false,
+        }
+    }
+
+    /// `attempt`回目の送信の結果を見て,再試行する場合は待つ時間を返す.
+    pub fn next_delay(
+        &self,
+        attempt: u32,
+        result: &Result<HttpResponse, APIError>,
+        idempotent: bool,
+    ) -> Option<Duration> {
+        (attempt < self.max_retries && self.is_retryable(result, idempotent))
+            .then(|| self.delay(attempt))
+    }
+}
@@ -0,0 +1,286 @@
+//! 話者の一覧と`SpeakerInfo`のディスクキャッシュ.
+//!
+//! エンジンのUUID,バージョン,話者のUUIDごとに,デコードした立ち絵,アイコン,ボイスサンプルを保存します.
+//! エンジンのバージョンが変わるまではエンジンに問い合わせずに返します.
+//!
+//! ```ignore
+//! let cache = SpeakerCache::open(cache_dir, &client).await?;
+//! for speaker in cache.speakers(&client).await? {
+//!     let info = cache.speaker_info(&client, &speaker.speaker_uuid).await?;
+//! }
+//! ```
+//!
+//! 保存先は次のようになります.
+//!
+//! ```text
+//! {root}/{engine uuid}/{version}/speakers.json
+//! {root}/{engine uuid}/{version}/{speaker uuid}/policy.md
+//! {root}/{engine uuid}/{version}/{speaker uuid}/portrait.png
+//! {root}/{engine uuid}/{version}/{speaker uuid}/styles.json
+//! {root}/{engine uuid}/{version}/{speaker uuid}/{style id}/icon.png
+//! {root}/{engine uuid}/{version}/{speaker uuid}/{style id}/voice_sample_{n}.wav
+//! ```
+
+use crate::{
+    api::{EngineManifest, SpeakerInfo, Speakers, Version},
+    api_schema::{self, Speaker, SpeakerUuid, StyleId, StyleInfo},
+    client::VoiceVoxClient,
+    error::APIError,
+    transport::Transport,
+    version::EngineVersion,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// `styles.json`に保存するスタイルの一覧.
+#[derive(Serialize, Deserialize)]
+struct CachedStyle {
+    id: StyleId,
+    voice_samples: usize,
+}
+
+/// 1つのエンジンのバージョンに対応するキャッシュ.
+#[derive(Debug, Clone)]
+pub struct SpeakerCache {
+    /// キャッシュしない場合は`None`.
+    dir: Option<PathBuf>,
+}
+
+impl SpeakerCache {
+    /// `root`以下にエンジンのキャッシュを置く.
+    ///
+    /// 開発版のエンジン(`latest`)はバージョンが変わらずに中身が変わるのでキャッシュしません.
+    pub fn new(root: impl AsRef<Path>, engine_id: &str, version: EngineVersion) -> Self {
+        let dir = match path_component(engine_id) {
+            Some(engine_id) if version != EngineVersion::LATEST => {
+                Some(root.as_ref().join(engine_id).join(version.to_string()))
+            }
+            _ => None,
+        };
+        Self { dir }
+    }
+
+    /// エンジンのUUIDとバージョンを取得して,古いバージョンのキャッシュを削除する.
+    pub async fn open<T: Transport>(
+        root: impl AsRef<Path>,
+        client: &VoiceVoxClient<T>,
+    ) -> Result<Self, APIError> {
+        let version = Version::detect(client).await?;
+        let manifest = client.send(EngineManifest).await?;
+        let cache = Self::new(root, &manifest.uuid, version);
+        let _ = cache.prune();
+        Ok(cache)
+    }
+
+    /// `open`と同じリクエストを同期的に送信する.
+    #[cfg(feature = "blocking")]
+    pub fn open_blocking<T: crate::blocking::BlockingTransport>(
+        root: impl AsRef<Path>,
+        client: &VoiceVoxClient<T>,
+    ) -> Result<Self, APIError> {
+        let version = Version::detect_blocking(client)?;
+        let manifest = client.send_blocking(EngineManifest)?;
+        let cache = Self::new(root, &manifest.uuid, version);
+        let _ = cache.prune();
+        Ok(cache)
+    }
+
+    /// キャッシュのディレクトリ.キャッシュしない場合は`None`.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// 同じエンジンの他のバージョンのキャッシュを削除する.
+    pub fn prune(&self) -> io::Result<()> {
+        let Some((dir, Some(engine_dir))) = self.dir.as_ref().map(|dir| (dir, dir.parent())) else {
+            return Ok(());
+        };
+        if !engine_dir.exists() {
+            return Ok(());
+        }
+        for entry in fs::read_dir(engine_dir)? {
+            let path = entry?.path();
+            if &path != dir {
+                fs::remove_dir_all(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// キャッシュ済みの話者の一覧.
+    pub fn load_speakers(&self) -> Option<Vec<Speaker>> {
+        let json = fs::read(self.dir.as_ref()?.join("speakers.json")).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+
+    pub fn store_speakers(&self, speakers: &[Speaker]) -> io::Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        fs::create_dir_all(dir)?;
+        let json = serde_json::to_vec(speakers).map_err(io::Error::other)?;
+        write_atomic(&dir.join("speakers.json"), &json)
+    }
+
+    /// キャッシュ済みの話者の情報.壊れている場合も`None`を返します.
+    pub fn load_speaker_info(&self, speaker_uuid: &SpeakerUuid) -> Option<api_schema::SpeakerInfo> {
+        let dir = self.speaker_dir(speaker_uuid)?;
+        let styles = fs::read(dir.join("styles.json")).ok()?;
+        let styles: Vec<CachedStyle> = serde_json::from_slice(&styles).ok()?;
+        let style_infos = styles
+            .into_iter()
+            .map(|style| {
+                let style_dir = dir.join(style.id.to_string());
+                Ok(StyleInfo {
+                    id: style.id,
+                    icon: fs::read(style_dir.join("icon.png"))?,
+                    voice_samples: (0..style.voice_samples)
+                        .map(|i| fs::read(style_dir.join(format!("voice_sample_{i}.wav"))))
+                        .collect::<io::Result<_>>()?,
+                })
+            })
+            .collect::<io::Result<_>>()
+            .ok()?;
+        Some(api_schema::SpeakerInfo {
+            policy: fs::read_to_string(dir.join("policy.md")).ok()?,
+            portrait: fs::read(dir.join("portrait.png")).ok()?,
+            style_infos,
+        })
+    }
+
+    /// 話者の情報を保存する.
+    ///
+    /// 一時ディレクトリに書き出してから置き換えるので,途中で失敗しても壊れたキャッシュは残りません.
+    pub fn store_speaker_info(
+        &self,
+        speaker_uuid: &SpeakerUuid,
+        info: &api_schema::SpeakerInfo,
+    ) -> io::Result<()> {
+        let Some(dir) = self.speaker_dir(speaker_uuid) else {
+            return Ok(());
+        };
+        let temp = dir.with_extension("tmp");
+        if temp.exists() {
+            fs::remove_dir_all(&temp)?;
+        }
+        fs::create_dir_all(&temp)?;
+        fs::write(temp.join("policy.md"), &info.policy)?;
+        fs::write(temp.join("portrait.png"), &info.portrait)?;
+        for style in &info.style_infos {
+            let style_dir = temp.join(style.id.to_string());
+            fs::create_dir_all(&style_dir)?;
+            fs::write(style_dir.join("icon.png"), &style.icon)?;
+            for (i, sample) in style.voice_samples.iter().enumerate() {
+                fs::write(style_dir.join(format!("voice_sample_{i}.wav")), sample)?;
+            }
+        }
+        let styles: Vec<CachedStyle> = info
+            .style_infos
+            .iter()
+            .map(|style| CachedStyle {
+                id: style.id,
+                voice_samples: style.voice_samples.len(),
+            })
+            .collect();
+        let styles = serde_json::to_vec(&styles).map_err(io::Error::other)?;
+        fs::write(temp.join("styles.json"), styles)?;
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::rename(temp, dir)
+    }
+
+    /// キャッシュがなければエンジンから取得して保存する.
+    ///
+    /// 保存に失敗してもエンジンから取得した一覧を返します.
+    pub async fn speakers<T: Transport>(
+        &self,
+        client: &VoiceVoxClient<T>,
+    ) -> Result<Vec<Speaker>, APIError> {
+        if let Some(speakers) = self.load_speakers() {
+            return Ok(speakers);
+        }
+        let speakers = client.send(Speakers { core_version: None }).await?;
+        let _ = self.store_speakers(&speakers);
+        Ok(speakers)
+    }
+
+    /// キャッシュがなければエンジンから取得して保存する.
+    ///
+    /// 保存に失敗してもエンジンから取得した情報を返します.
+    pub async fn speaker_info<T: Transport>(
+        &self,
+        client: &VoiceVoxClient<T>,
+        speaker_uuid: &SpeakerUuid,
+    ) -> Result<api_schema::SpeakerInfo, APIError> {
+        if let Some(info) = self.load_speaker_info(speaker_uuid) {
+            return Ok(info);
+        }
+        let info = client
+            .send(SpeakerInfo {
+                speaker_uuid: speaker_uuid.clone(),
+                core_version: None,
+            })
+            .await?;
+        let _ = self.store_speaker_info(speaker_uuid, &info);
+        Ok(info)
+    }
+
+    /// `speakers`と同じリクエストを同期的に送信する.
+    #[cfg(feature = "blocking")]
+    pub fn speakers_blocking<T: crate::blocking::BlockingTransport>(
+        &self,
+        client: &VoiceVoxClient<T>,
+    ) -> Result<Vec<Speaker>, APIError> {
+        if let Some(speakers) = self.load_speakers() {
+            return Ok(speakers);
+        }
+        let speakers = client.send_blocking(Speakers { core_version: None })?;
+        let _ = self.store_speakers(&speakers);
+        Ok(speakers)
+    }
+
+    /// `speaker_info`と同じリクエストを同期的に送信する.
+    #[cfg(feature = "blocking")]
+    pub fn speaker_info_blocking<T: crate::blocking::BlockingTransport>(
+        &self,
+        client: &VoiceVoxClient<T>,
+        speaker_uuid: &SpeakerUuid,
+    ) -> Result<api_schema::SpeakerInfo, APIError> {
+        if let Some(info) = self.load_speaker_info(speaker_uuid) {
+            return Ok(info);
+        }
+        let info = client.send_blocking(SpeakerInfo {
+            speaker_uuid: speaker_uuid.clone(),
+            core_version: None,
+        })?;
+        let _ = self.store_speaker_info(speaker_uuid, &info);
+        Ok(info)
+    }
+
+    fn speaker_dir(&self, speaker_uuid: &SpeakerUuid) -> Option<PathBuf> {
+        Some(
+            self.dir
+                .as_ref()?
+                .join(path_component(speaker_uuid.as_str())?),
+        )
+    }
+}
+
+/// エンジンが返したUUIDをそのままパスに使えるかを確かめる.
+fn path_component(id: &str) -> Option<&str> {
+    let valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(id)
+}
+
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let temp = path.with_extension("tmp");
+    fs::write(&temp, contents)?;
+    fs::rename(temp, path)
+}
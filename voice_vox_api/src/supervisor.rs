@@ -0,0 +1,538 @@
+//! エンジンのプロセスの管理.
+//!
+//! 設定した実行ファイルをポートと引数を指定して起動し,`/version`が応答するまで待ちます.
+//! プロセスが終了したり応答しなくなったりした場合は起動し直し,破棄するとプロセスを停止します.
+//! 停止するときはunixでは`SIGTERM`,Windowsでは`CTRL_BREAK_EVENT`を送り,
+//! `shutdown_timeout`を過ぎても終了しない場合だけ強制終了します.
+//! 状態の変化は`watch`で受け取れます.
+//!
+//! ```ignore
+//! let engine = EngineSupervisor::start(EngineConfig::new("/opt/voicevox/run").port(50021))?;
+//! let client = VoiceVoxClient::new(&engine.base_url())?;
+//! let mut watcher = engine.watch();
+//! loop {
+//!     match watcher.changed().await {
+//!         EngineStatus::Ready(version) => println!("engine {version} is ready"),
+//!         status => println!("{status}"),
+//!     }
+//! }
+//! ```
+
+use crate::{
+    api::Version,
+    api_schema::Device,
+    blocking::BlockingClient,
+    client::{VoiceVoxClient, READY_POLL_INTERVAL},
+    version::EngineVersion,
+};
+use std::{
+    ffi::OsString,
+    fmt, io,
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    task::{Poll, Waker},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+/// 起動するエンジンの設定.
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    pub executable: PathBuf,
+    /// `--host`と`--port`の後に渡す引数.
+    pub args: Vec<OsString>,
+    pub host: String,
+    pub port: u16,
+    /// 起動してから`/version`が応答するまで待つ時間.
+    pub startup_timeout: Duration,
+    /// プロセスと`/version`を確かめる間隔.
+    pub health_interval: Duration,
+    /// `/version`に続けて失敗したら応答しなくなったとみなす回数.
+    pub health_failures: u32,
+    /// 続けて起動し直す回数.超えた場合は`restart`を呼ぶまで停止したままです.
+    pub max_restarts: u32,
+    /// 終了を求めてから強制終了するまで待つ時間.
+    pub shutdown_timeout: Duration,
+}
+
+impl EngineConfig {
+    /// `executable`を`127.0.0.1:50021`で起動する.
+    pub fn new(executable: impl Into<PathBuf>) -> Self {
+        Self {
+            executable: executable.into(),
+            args: Vec::new(),
+            host: "127.0.0.1".to_owned(),
+            port: 50021,
+            startup_timeout: Duration::from_secs(120),
+            health_interval: Duration::from_secs(2),
+            health_failures: 3,
+            max_restarts: 3,
+            shutdown_timeout: Duration::from_secs(10),
+        }
+    }
+
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I: IntoIterator<Item = S>, S: Into<OsString>>(mut self, args: I) -> Self {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// `device`を使うための引数を追加する.
+    pub fn device(self, device: Device) -> Self {
+        self.args(device.engine_args().iter().copied())
+    }
+
+    pub fn startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
+    pub fn health_interval(mut self, interval: Duration) -> Self {
+        self.health_interval = interval;
+        self
+    }
+
+    pub fn max_restarts(mut self, max_restarts: u32) -> Self {
+        self.max_restarts = max_restarts;
+        self
+    }
+
+    pub fn shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// 起動したエンジンに接続するためのベースURL.
+    pub fn base_url(&self) -> String {
+        format!("http://{}:{}", self.host, self.port)
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.executable);
+        command
+            .arg("--host")
+            .arg(&self.host)
+            .arg("--port")
+            .arg(self.port.to_string())
+            .args(&self.args)
+            .stdin(Stdio::null());
+        // `CTRL_BREAK_EVENT`をエンジンだけに送るためにプロセスグループを分ける.
+        #[cfg(windows)]
+        std::os::windows::process::CommandExt::creation_flags(
+            &mut command,
+            windows_sys::Win32::System::Threading::CREATE_NEW_PROCESS_GROUP,
+        );
+        command
+    }
+}
+
+/// エンジンのプロセスの状態.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineStatus {
+    /// 停止している.
+    Stopped,
+    /// 起動して`/version`が応答するのを待っている.
+    Starting,
+    /// `/version`が応答した.
+    Ready(EngineVersion),
+    /// プロセスが終了した,または起動や応答に失敗した.
+    ///
+    /// `restarting`が`true`なら起動し直します.
+    Crashed { reason: String, restarting: bool },
+}
+
+impl fmt::Display for EngineStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineStatus::Stopped => f.write_str("stopped"),
+            EngineStatus::Starting => f.write_str("starting"),
+            EngineStatus::Ready(version) => write!(f, "ready ({version})"),
+            EngineStatus::Crashed { reason, .. } => write!(f, "crashed: {reason}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Request {
+    Restart,
+    Shutdown,
+}
+
+struct SharedState {
+    status: EngineStatus,
+    /// 状態が変わるたびに増やす.
+    generation: u64,
+    wakers: Vec<Waker>,
+    request: Option<Request>,
+    pid: Option<u32>,
+}
+
+/// 監視するスレッドと共有する状態.
+struct Shared {
+    state: Mutex<SharedState>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, SharedState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_status(&self, status: EngineStatus) {
+        let mut state = self.lock();
+        state.status = status;
+        state.generation += 1;
+        let wakers = std::mem::take(&mut state.wakers);
+        drop(state);
+        self.changed.notify_all();
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// 停止の要求は再起動の要求で上書きしない.
+    fn request(&self, request: Request) {
+        let mut state = self.lock();
+        if state.request != Some(Request::Shutdown) {
+            state.request = Some(request);
+        }
+        drop(state);
+        self.changed.notify_all();
+    }
+
+    /// 要求があるまで最大`timeout`だけ待つ.`None`の場合は要求があるまで待ちます.
+    fn wait_request(&self, timeout: Option<Duration>) -> Option<Request> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut state = self.lock();
+        loop {
+            if let Some(request) = state.request.take() {
+                return Some(request);
+            }
+            state = match deadline {
+                Some(deadline) => {
+                    let timeout = deadline.checked_duration_since(Instant::now())?;
+                    self.changed
+                        .wait_timeout(state, timeout)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+                None => self.changed.wait(state).unwrap_or_else(|e| e.into_inner()),
+            };
+        }
+    }
+}
+
+/// エンジンのプロセスを起動して監視する.
+///
+/// 破棄するとプロセスを終了して,終了するまで待ちます.
+pub struct EngineSupervisor {
+    config: EngineConfig,
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl EngineSupervisor {
+    /// 監視するスレッドを立ててエンジンを起動する.
+    ///
+    /// 起動を待たずに返ります.準備ができたかは`status`や`watch`で確かめてください.
+    pub fn start(config: EngineConfig) -> io::Result<Self> {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(SharedState {
+                status: EngineStatus::Starting,
+                generation: 0,
+                wakers: Vec::new(),
+                request: None,
+                pid: None,
+            }),
+            changed: Condvar::new(),
+        });
+        let thread = std::thread::Builder::new()
+            .name("voicevox-engine-supervisor".to_owned())
+            .spawn({
+                let shared = shared.clone();
+                let config = config.clone();
+                move || supervise(&shared, &config)
+            })?;
+        Ok(Self {
+            config,
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+
+    /// `VoiceVoxClient`に渡すベースURL.
+    pub fn base_url(&self) -> String {
+        self.config.base_url()
+    }
+
+    pub fn status(&self) -> EngineStatus {
+        self.shared.lock().status.clone()
+    }
+
+    /// 動いているプロセスのID.
+    pub fn pid(&self) -> Option<u32> {
+        self.shared.lock().pid
+    }
+
+    /// プロセスを終了して起動し直す.
+    ///
+    /// 再起動の回数を超えて停止している場合も起動します.
+    pub fn restart(&self) {
+        self.shared.request(Request::Restart);
+    }
+
+    /// プロセスに終了を求めて,終了するまで待つ.
+    ///
+    /// `EngineConfig::shutdown_timeout`を過ぎても終了しない場合は強制終了します.
+    pub fn shutdown(self) {
+        drop(self);
+    }
+
+    /// 状態の変化を受け取る.
+    pub fn watch(&self) -> StatusWatcher {
+        StatusWatcher {
+            shared: self.shared.clone(),
+            seen: None,
+        }
+    }
+
+    /// `predicate`を満たす状態になるまで最大`timeout`だけ待つ.
+    ///
+    /// `predicate`は状態をロックしたまま呼ぶので,中から`EngineSupervisor`のメソッドを呼ばないでください.
+    pub fn wait_for(
+        &self,
+        timeout: Duration,
+        mut predicate: impl FnMut(&EngineStatus) -> bool,
+    ) -> Option<EngineStatus> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.lock();
+        loop {
+            if predicate(&state.status) {
+                return Some(state.status.clone());
+            }
+            let timeout = deadline.checked_duration_since(Instant::now())?;
+            state = self
+                .shared
+                .changed
+                .wait_timeout(state, timeout)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+}
+
+impl Drop for EngineSupervisor {
+    fn drop(&mut self) {
+        self.shared.request(Request::Shutdown);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// `EngineSupervisor::watch`で作成する状態の変化の受け取り口.
+#[derive(Clone)]
+pub struct StatusWatcher {
+    shared: Arc<Shared>,
+    seen: Option<u64>,
+}
+
+impl StatusWatcher {
+    /// 状態が変わるまで待って新しい状態を返す.
+    ///
+    /// 作成してから最初に呼んだ場合は現在の状態をすぐに返します.
+    /// 待っている間に何度か変わった場合は最後の状態だけを返します.
+    pub async fn changed(&mut self) -> EngineStatus {
+        std::future::poll_fn(|cx| {
+            let mut state = self.shared.lock();
+            if self.seen != Some(state.generation) {
+                self.seen = Some(state.generation);
+                return Poll::Ready(state.status.clone());
+            }
+            if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                state.wakers.push(cx.waker().clone());
+            }
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+/// 停止の要求があるまで起動と監視を繰り返す.
+fn supervise(shared: &Shared, config: &EngineConfig) {
+    let client = VoiceVoxClient::builder(&config.base_url())
+        .timeout(config.health_interval.max(Duration::from_secs(1)))
+        .build_blocking();
+    let client = match client {
+        Ok(client) => client,
+        Err(e) => {
+            shared.set_status(EngineStatus::Crashed {
+                reason: e.to_string(),
+                restarting: false,
+            });
+            while shared.wait_request(None) != Some(Request::Shutdown) {}
+            return;
+        }
+    };
+    let mut restarts = 0;
+    loop {
+        match run(shared, config, &client, &mut restarts) {
+            Ok(Request::Shutdown) => break,
+            Ok(Request::Restart) => restarts = 0,
+            Err(reason) => {
+                let restarting = restarts < config.max_restarts;
+                shared.set_status(EngineStatus::Crashed { reason, restarting });
+                let timeout = restarting.then_some(config.health_interval);
+                match shared.wait_request(timeout) {
+                    Some(Request::Shutdown) => break,
+                    Some(Request::Restart) => restarts = 0,
+                    None => restarts += 1,
+                }
+            }
+        }
+    }
+    shared.set_status(EngineStatus::Stopped);
+}
+
+/// プロセスを1回起動して,終了するか要求があるまで監視する.
+fn run(
+    shared: &Shared,
+    config: &EngineConfig,
+    client: &BlockingClient,
+    restarts: &mut u32,
+) -> Result<Request, String> {
+    shared.set_status(EngineStatus::Starting);
+    let mut child = config
+        .command()
+        .spawn()
+        .map_err(|e| format!("failed to launch {}: {e}", config.executable.display()))?;
+    shared.lock().pid = Some(child.id());
+    let result = monitor(shared, config, client, &mut child, restarts);
+    stop(&mut child, config.shutdown_timeout);
+    shared.lock().pid = None;
+    result
+}
+
+fn monitor(
+    shared: &Shared,
+    config: &EngineConfig,
+    client: &BlockingClient,
+    child: &mut Child,
+    restarts: &mut u32,
+) -> Result<Request, String> {
+    let exited = |child: &mut Child| match child.try_wait() {
+        Ok(Some(status)) => Err(format!("engine exited with {status}")),
+        Ok(None) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    };
+    let deadline = Instant::now() + config.startup_timeout;
+    loop {
+        exited(child)?;
+        if let Ok(version) = Version::detect_blocking(client) {
+            shared.set_status(EngineStatus::Ready(version));
+            *restarts = 0;
+            break;
+        }
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "engine did not answer /version within {:?}",
+                config.startup_timeout
+            ));
+        }
+        if let Some(request) = shared.wait_request(Some(READY_POLL_INTERVAL)) {
+            return Ok(request);
+        }
+    }
+    let mut failures = 0;
+    loop {
+        if let Some(request) = shared.wait_request(Some(config.health_interval)) {
+            return Ok(request);
+        }
+        exited(child)?;
+        match Version::detect_blocking(client) {
+            Ok(_) => failures = 0,
+            Err(_) => failures += 1,
+        }
+        if failures >= config.health_failures.max(1) {
+            return Err("engine stopped responding".to_owned());
+        }
+    }
+}
+
+/// 終了したか確かめる間隔.
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 終了を求めて最大`timeout`だけ待ち,終了しなければ強制終了する.
+fn stop(child: &mut Child, timeout: Duration) {
+    let running = |child: &mut Child| !matches!(child.try_wait(), Ok(Some(_)));
+    if terminate(child).is_ok() {
+        let deadline = Instant::now() + timeout;
+        while running(child) && Instant::now() < deadline {
+            std::thread::sleep(STOP_POLL_INTERVAL);
+        }
+    }
+    if running(child) {
+        let _ = child.kill();
+    }
+    let _ = child.wait();
+}
+
+/// `SIGTERM`を送る.
+#[cfg(unix)]
+fn terminate(child: &mut Child) -> io::Result<()> {
+    // 回収済みのプロセスのIDは他のプロセスに再利用されている可能性があるので送らない.
+    if child.try_wait()?.is_some() {
+        return Ok(());
+    }
+    let pid = libc::pid_t::try_from(child.id())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    // SAFETY: `kill`はポインタを受け取らないので,どの引数でもメモリ安全性は損なわれない.
+    // `Child`は`&mut`で借りているので`try_wait`からここまでの間に他から回収されることはなく,
+    // まだ回収していない子プロセスのIDは終了していても他のプロセスに再利用されない.
+    match unsafe { libc::kill(pid, libc::SIGTERM) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// エンジンのプロセスグループに`CTRL_BREAK_EVENT`を送る.
+///
+/// コンソールを持たないプロセスからは送れないので,その場合はすぐに強制終了します.
+#[cfg(windows)]
+fn terminate(child: &mut Child) -> io::Result<()> {
+    use windows_sys::Win32::System::Console::{GenerateConsoleCtrlEvent, CTRL_BREAK_EVENT};
+
+    // 終了したプロセスのIDは他のプロセスに再利用されている可能性があるので送らない.
+    if child.try_wait()?.is_some() {
+        return Ok(());
+    }
+    // SAFETY: `GenerateConsoleCtrlEvent`はポインタを受け取らないので,どの引数でもメモリ安全性は損なわれない.
+    // `Child`がプロセスのハンドルを開いたままなのでIDは再利用されず,
+    // `command`で`CREATE_NEW_PROCESS_GROUP`を指定したのでエンジンのプロセスグループだけを指す.
+    match unsafe { GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, child.id()) } {
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn terminate(_child: &mut Child) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
@@ -0,0 +1,450 @@
+//! 音声合成の結果のキャッシュ.
+//!
+//! クエリ,スタイル,疑問文の語尾上げ,モーフィングの設定,エンジンのUUIDとバージョンから求めたハッシュをキーに,
+//! 合成したwavをメモリと(設定した場合は)ディスクに保存します.
+//! 内容が変わっていない行を書き出し直すときはエンジンに送らずに返します.
+//!
+//! ```ignore
+//! let cache = SynthesisCache::new(64 << 20).with_disk(cache_dir, 1 << 30)?;
+//! let wav = cache.call(&client, synthesis).await?;
+//! ```
+
+use crate::{
+    api, cancel::CancellationToken, capabilities::EngineCapabilities, client::VoiceVoxClient,
+    endpoint::Endpoint, error::APIError, transport::Transport, version::EngineVersion,
+};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::SystemTime,
+};
+
+/// 合成の設定から求めたSHA-256.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CacheKey(pub [u8; 32]);
+
+impl CacheKey {
+    /// リクエストの内容を表すJSONとエンジンのUUID,バージョンから求める.
+    ///
+    /// スタイルIDはエンジンごとに振られるので,同じバージョンでもエンジンが違えば別のキーになります.
+    /// オブジェクトのキーは並べ替えてからハッシュするので,フィールドの順序には依存しません.
+    pub fn new(request: &Value, engine_uuid: &str, version: EngineVersion) -> Self {
+        let canonical = json!({
+            "engine_uuid": engine_uuid,
+            "engine_version": version.to_string(),
+            "request": canonicalize(request),
+        });
+        Self(Sha256::digest(canonical.to_string()).into())
+    }
+}
+
+/// オブジェクトのキーを再帰的に並べ替える.
+///
+/// `serde_json`の`preserve_order`が有効でも挿入した順序でシリアライズされないようにする.
+fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            let mut entries: Vec<_> = object.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), canonicalize(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.iter().map(canonicalize).collect()),
+        value => value.clone(),
+    }
+}
+
+impl fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+/// 結果をキャッシュできる音声合成のリクエスト.
+pub trait CacheableEndpoint: Endpoint<Response = Vec<u8>> {
+    /// 結果に影響する全てのパラメータ.
+    fn cache_request(&self) -> Value;
+
+    fn cache_key(&self, engine_uuid: &str, version: EngineVersion) -> CacheKey {
+        CacheKey::new(&self.cache_request(), engine_uuid, version)
+    }
+}
+
+impl CacheableEndpoint for api::Synthesis {
+    fn cache_request(&self) -> Value {
+        json!({
+            "endpoint": "synthesis",
+            "speaker": self.speaker,
+            "enable_interrogative_upspeak": self.enable_interrogative_upspeak,
+            "core_version": self.core_version,
+            "audio_query": self.audio_query,
+        })
+    }
+}
+
+impl CacheableEndpoint for api::CancellableSynthesis {
+    fn cache_request(&self) -> Value {
+        // 語尾上げを指定できないので`Synthesis`の既定と同じ結果になる.
+        json!({
+            "endpoint": "synthesis",
+            "speaker": self.speaker,
+            "enable_interrogative_upspeak": None::<bool>,
+            "core_version": self.core_version,
+            "audio_query": self.audio_query,
+        })
+    }
+}
+
+impl CacheableEndpoint for api::SynthesisMorphing {
+    fn cache_request(&self) -> Value {
+        json!({
+            "endpoint": "synthesis_morphing",
+            "base_speaker": self.base_speaker,
+            "target_speaker": self.target_speaker,
+            "morph_rate": self.morph_rate,
+            "core_version": self.core_version,
+            "audio_query": self.audio_query,
+        })
+    }
+}
+
+/// メモリ上のキャッシュ.最後に使った時刻の古いものから削除します.
+#[derive(Default)]
+struct MemoryCache {
+    entries: HashMap<CacheKey, (Vec<u8>, u64)>,
+    bytes: usize,
+    clock: u64,
+}
+
+impl MemoryCache {
+    fn get(&mut self, key: &CacheKey) -> Option<Vec<u8>> {
+        self.clock += 1;
+        let (wav, last_used) = self.entries.get_mut(key)?;
+        *last_used = self.clock;
+        Some(wav.clone())
+    }
+
+    fn insert(&mut self, key: CacheKey, wav: Vec<u8>, limit: usize) {
+        if wav.len() > limit {
+            return;
+        }
+        self.clock += 1;
+        self.bytes += wav.len();
+        if let Some((old, _)) = self.entries.insert(key, (wav, self.clock)) {
+            self.bytes -= old.len();
+        }
+        while self.bytes > limit {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| *key)
+            else {
+                break;
+            };
+            if let Some((wav, _)) = self.entries.remove(&oldest) {
+                self.bytes -= wav.len();
+            }
+        }
+    }
+}
+
+/// ディスク上のキャッシュ.更新日時の古いものから削除します.
+///
+/// 同じディレクトリを他のインスタンスやプロセスも使うので,合計の大きさは保存するたびにディレクトリから数え直します.
+struct DiskCache {
+    dir: PathBuf,
+    limit: u64,
+    write: Mutex<()>,
+}
+
+impl DiskCache {
+    fn open(dir: PathBuf, limit: u64) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            limit,
+            write: Mutex::new(()),
+        })
+    }
+
+    fn path(&self, key: &CacheKey) -> PathBuf {
+        let hex = key.to_string();
+        self.dir.join(&hex[..2]).join(format!("{hex}.wav"))
+    }
+
+    /// 保存されているwavのパス,大きさ,更新日時.
+    fn files(dir: &Path) -> io::Result<Vec<(PathBuf, u64, SystemTime)>> {
+        let mut files = Vec::new();
+        for shard in fs::read_dir(dir)? {
+            let shard = shard?;
+            if !shard.file_type()?.is_dir() {
+                continue;
+            }
+            for file in fs::read_dir(shard.path())? {
+                let file = file?;
+                let path = file.path();
+                if path.extension().is_some_and(|ext| ext == "wav") {
+                    // 一覧を取得した後に他のプロセスが削除したものは数えない.
+                    let metadata = match file.metadata() {
+                        Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                        metadata => metadata?,
+                    };
+                    files.push((path, metadata.len(), metadata.modified()?));
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    fn get(&self, key: &CacheKey) -> Option<Vec<u8>> {
+        let path = self.path(key);
+        let wav = fs::read(&path).ok()?;
+        // 使ったものを削除しにくくする.
+        if let Ok(file) = fs::File::options().append(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(wav)
+    }
+
+    fn insert(&self, key: &CacheKey, wav: &[u8]) -> io::Result<()> {
+        if wav.len() as u64 > self.limit {
+            return Ok(());
+        }
+        // このインスタンスからの書き込みと削除が入れ違わないようにする.
+        let _write = self.write.lock().unwrap_or_else(|e| e.into_inner());
+        let path = self.path(key);
+        fs::create_dir_all(path.parent().unwrap())?;
+        // 同じディレクトリを使う他のプロセスと一時ファイルが重ならないようにする.
+        static TEMP_ID: AtomicU64 = AtomicU64::new(0);
+        let temp = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            TEMP_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        if let Err(e) = fs::write(&temp, wav).and_then(|_| fs::rename(&temp, &path)) {
+            let _ = fs::remove_file(&temp);
+            return Err(e);
+        }
+        let mut files = Self::files(&self.dir)?;
+        let mut bytes: u64 = files.iter().map(|(_, len, _)| len).sum();
+        if bytes > self.limit {
+            files.sort_by_key(|(_, _, modified)| *modified);
+            for (file, len, _) in files {
+                if bytes <= self.limit {
+                    break;
+                }
+                // 他のプロセスが先に削除した場合も,もう数えなくてよい.
+                if file != path && (fs::remove_file(&file).is_ok() || !file.exists()) {
+                    bytes = bytes.saturating_sub(len);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&self) -> io::Result<()> {
+        let _write = self.write.lock().unwrap_or_else(|e| e.into_inner());
+        for (file, _, _) in Self::files(&self.dir)? {
+            match fs::remove_file(file) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `Synthesis`と`SynthesisMorphing`の結果のキャッシュ.
+///
+/// 複数のタスクから共有する場合は`Arc`に入れてください.
+/// キーにエンジンのUUIDを含むので,複数のエンジンで同じキャッシュやディレクトリを使えます.
+pub struct SynthesisCache {
+    memory: Mutex<MemoryCache>,
+    memory_limit: usize,
+    disk: Option<DiskCache>,
+}
+
+impl fmt::Debug for SynthesisCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SynthesisCache")
+            .field("memory_limit", &self.memory_limit)
+            .field("memory_bytes", &self.memory_bytes())
+            .field("disk", &self.disk.as_ref().map(|disk| &disk.dir))
+            .finish()
+    }
+}
+
+impl SynthesisCache {
+    /// メモリに`memory_limit`バイトまで保存するキャッシュを作成する.
+    pub fn new(memory_limit: usize) -> Self {
+        Self {
+            memory: Mutex::new(MemoryCache::default()),
+            memory_limit,
+            disk: None,
+        }
+    }
+
+    /// `dir`に`limit`バイトまで保存する.
+    pub fn with_disk(mut self, dir: impl Into<PathBuf>, limit: u64) -> io::Result<Self> {
+        self.disk = Some(DiskCache::open(dir.into(), limit)?);
+        Ok(self)
+    }
+
+    /// メモリになければディスクから読み込む.
+    pub fn get(&self, key: &CacheKey) -> Option<Vec<u8>> {
+        if let Some(wav) = self.memory().get(key) {
+            return Some(wav);
+        }
+        let wav = self.disk.as_ref()?.get(key)?;
+        self.memory().insert(*key, wav.clone(), self.memory_limit);
+        Some(wav)
+    }
+
+    /// 上限を超えた場合は古いものから削除する.
+    ///
+    /// ディスクへの書き込みに失敗してもメモリには保存されます.
+    pub fn insert(&self, key: CacheKey, wav: Vec<u8>) -> io::Result<()> {
+        let result = match &self.disk {
+            Some(disk) => disk.insert(&key, &wav),
+            None => Ok(()),
+        };
+        self.memory().insert(key, wav, self.memory_limit);
+        result
+    }
+
+    /// メモリとディスクのキャッシュを全て削除する.
+    pub fn clear(&self) -> io::Result<()> {
+        *self.memory() = MemoryCache::default();
+        match &self.disk {
+            Some(disk) => disk.clear(),
+            None => Ok(()),
+        }
+    }
+
+    /// メモリに保存しているwavの数.
+    pub fn len(&self) -> usize {
+        self.memory().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// メモリに保存しているwavの合計のバイト数.
+    pub fn memory_bytes(&self) -> usize {
+        self.memory().bytes
+    }
+
+    /// リクエストのキーを求める.
+    ///
+    /// エンジンのUUIDとバージョンは`negotiate`したクライアントから取得し,
+    /// 設定されていない場合は呼び出すたびに`/version`と`/engine_manifest`を問い合わせます.
+    /// 開発版のエンジン(`latest`)はバージョンが変わらずに中身が変わるので`None`を返し,キャッシュしません.
+    pub async fn key<T: Transport, E: CacheableEndpoint>(
+        client: &VoiceVoxClient<T>,
+        endpoint: &E,
+    ) -> Result<Option<CacheKey>, APIError> {
+        let capabilities = match client.capabilities() {
+            Some(capabilities) if capabilities.engine_uuid.is_some() => capabilities.clone(),
+            _ => EngineCapabilities::detect(client).await?,
+        };
+        Ok(cache_key(&capabilities, endpoint))
+    }
+
+    /// `key`と同じリクエストを同期的に送信する.
+    #[cfg(feature = "blocking")]
+    pub fn key_blocking<T: crate::blocking::BlockingTransport, E: CacheableEndpoint>(
+        client: &VoiceVoxClient<T>,
+        endpoint: &E,
+    ) -> Result<Option<CacheKey>, APIError> {
+        let capabilities = match client.capabilities() {
+            Some(capabilities) if capabilities.engine_uuid.is_some() => capabilities.clone(),
+            _ => EngineCapabilities::detect_blocking(client)?,
+        };
+        Ok(cache_key(&capabilities, endpoint))
+    }
+
+    /// キャッシュになければエンジンで合成して保存する.
+    pub async fn call<T: Transport, E: CacheableEndpoint>(
+        &self,
+        client: &VoiceVoxClient<T>,
+        endpoint: E,
+    ) -> Result<Vec<u8>, E::Error> {
+        let Some(key) = Self::key(client, &endpoint).await? else {
+            return client.send(endpoint).await;
+        };
+        if let Some(wav) = self.get(&key) {
+            return Ok(wav);
+        }
+        let wav = client.send(endpoint).await?;
+        let _ = self.insert(key, wav.clone());
+        Ok(wav)
+    }
+
+    /// `token`で中断できる`call`.
+    pub async fn call_cancellable<T: Transport, E: CacheableEndpoint>(
+        &self,
+        client: &VoiceVoxClient<T>,
+        endpoint: E,
+        token: &CancellationToken,
+    ) -> Result<Vec<u8>, E::Error> {
+        let Some(key) = token
+            .run(Self::key(client, &endpoint))
+            .await
+            .unwrap_or(Err(APIError::Cancelled))?
+        else {
+            return client.send_cancellable(endpoint, token).await;
+        };
+        if let Some(wav) = self.get(&key) {
+            return Ok(wav);
+        }
+        let wav = client.send_cancellable(endpoint, token).await?;
+        let _ = self.insert(key, wav.clone());
+        Ok(wav)
+    }
+
+    /// `call`と同じリクエストを同期的に送信する.
+    #[cfg(feature = "blocking")]
+    pub fn call_blocking<T: crate::blocking::BlockingTransport, E: CacheableEndpoint>(
+        &self,
+        client: &VoiceVoxClient<T>,
+        endpoint: E,
+    ) -> Result<Vec<u8>, E::Error> {
+        let Some(key) = Self::key_blocking(client, &endpoint)? else {
+            return client.send_blocking(endpoint);
+        };
+        if let Some(wav) = self.get(&key) {
+            return Ok(wav);
+        }
+        let wav = client.send_blocking(endpoint)?;
+        let _ = self.insert(key, wav.clone());
+        Ok(wav)
+    }
+
+    fn memory(&self) -> std::sync::MutexGuard<'_, MemoryCache> {
+        self.memory.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn cache_key<E: CacheableEndpoint>(
+    capabilities: &EngineCapabilities,
+    endpoint: &E,
+) -> Option<CacheKey> {
+    if capabilities.version == EngineVersion::LATEST {
+        return None;
+    }
+    let engine_uuid = capabilities.engine_uuid.as_deref().unwrap_or_default();
+    Some(endpoint.cache_key(engine_uuid, capabilities.version))
+}
@@ -0,0 +1,122 @@
+//! HTTPクライアントの抽象化.
+//!
+//! 各エンドポイントは`Transport`だけに依存するので,
+//! バックエンドを追加する場合はこのトレイトを実装するだけで全てのAPIが利用できます.
+
+use crate::{api_schema::HttpValidationError, error::APIError};
+use serde::de::DeserializeOwned;
+use std::{future::Future, time::Duration};
+
+/// HTTPメソッド.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Delete,
+}
+
+/// バックエンドに渡すリクエスト.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    /// クエリを含む完全なURL.
+    pub url: url::Url,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+/// バックエンドから受け取ったレスポンス.
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+/// バックエンドの作成に使う設定.
+#[derive(Debug, Clone, Default)]
+pub struct TransportConfig {
+    /// リクエスト全体のタイムアウト.
+    pub timeout: Option<Duration>,
+    /// 接続確立までのタイムアウト.
+    pub connect_timeout: Option<Duration>,
+}
+
+/// リクエストを送信するバックエンド.
+pub trait Transport: Sized {
+    /// 設定からバックエンドを作成する.
+    fn new(config: &TransportConfig) -> Result<Self, APIError>;
+
+    /// リクエストを送信してレスポンスボディを全て読み取る.
+    ///
+    /// ステータスコードによらずレスポンスを返し,通信自体が失敗した場合のみエラーを返します.
+    fn execute(&self, request: HttpRequest)
+        -> impl Future<Output = Result<HttpResponse, APIError>>;
+
+    /// バックエンドが使う非同期ランタイムのタイマーで`duration`だけ待つ.
+    ///
+    /// 再試行の間隔と`wait_until_ready`の問い合わせの間隔に使います.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()>;
+}
+
+impl HttpResponse {
+    /// レスポンスボディをJSONとして読み取る.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, APIError> {
+        serde_json::from_slice(&self.body).map_err(|e| self.decode_error(e))
+    }
+
+    /// ステータスコードが`200`ならJSONを,それ以外はエラーを返す.
+    pub fn ok_json<T: DeserializeOwned>(self) -> Result<T, APIError> {
+        match self.status {
+            200 => self.json(),
+            _ => Err(self.into_error()),
+        }
+    }
+
+    /// ステータスコードが`200`ならレスポンスボディを,それ以外はエラーを返す.
+    pub fn ok_bytes(self) -> Result<Vec<u8>, APIError> {
+        match self.status {
+            200 => Ok(self.body),
+            _ => Err(self.into_error()),
+        }
+    }
+
+    /// ステータスコードが`204`(または`200`)なら`()`を,それ以外はエラーを返す.
+    pub fn no_content(self) -> Result<(), APIError> {
+        match self.status {
+            200 | 204 => Ok(()),
+            _ => Err(self.into_error()),
+        }
+    }
+
+    /// レスポンスボディを文字列として扱う.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// 解釈できなかったレスポンスのエラー.
+    pub fn decode_error(&self, message: impl ToString) -> APIError {
+        APIError::Decode {
+            status: self.status,
+            body: self.text(),
+            message: message.to_string(),
+        }
+    }
+
+    /// 成功以外のレスポンスをエラーにする.
+    ///
+    /// `422`はパラメータの検証エラーとして解釈します.
+    pub fn into_error(self) -> APIError {
+        match self.status {
+            422 => match self.json::<HttpValidationError>() {
+                Ok(e) => APIError::Validation(e),
+                Err(e) => e,
+            },
+            status => APIError::Status {
+                status,
+                body: self.text(),
+            },
+        }
+    }
+}
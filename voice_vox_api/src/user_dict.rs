@@ -0,0 +1,371 @@
+//! ユーザー辞書のファイルの読み書き.
+//!
+//! JSONはエンジンの`UserDict`が返し`ImportUserDict`が受け取る,単語のUUIDをキーにした形式です.
+//! CSVは表層形,発音,アクセント型,単語の種類,優先度だけを持つ簡単な形式で,
+//! UUIDを持たないのでエンジンに関係なく同じ辞書を管理できます.
+//!
+//! `UserDictSync`はエンジンの辞書を用意した単語の一覧と同じ内容に揃えます.
+//!
+//! ```ignore
+//! let words = user_dict::from_csv(&std::fs::read_to_string("dict.csv")?)?;
+//! for change in UserDictSync::new(words).dry_run(true).run(&client).await? {
+//!     println!("{change:?}");
+//! }
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use crate::{
+    api,
+    api_schema::{full_width_surface, UnknownWordType, UserDictWord, WordType},
+    client::VoiceVoxClient,
+    error::APIError,
+    transport::Transport,
+};
+
+/// CSVの見出し行.
+pub const CSV_HEADER: [&str; 5] = [
+    "surface",
+    "pronunciation",
+    "accent_type",
+    "word_type",
+    "priority",
+];
+
+/// エンジンの辞書のJSONを読む.
+pub fn from_json(json: &str) -> Result<HashMap<String, UserDictWord>, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+/// エンジンの辞書のJSONを書く.
+///
+/// 差分を取りやすいように,UUIDの順に並べて整形します.
+pub fn to_json(dictionary: &HashMap<String, UserDictWord>) -> String {
+    let sorted: BTreeMap<_, _> = dictionary.iter().collect();
+    serde_json::to_string_pretty(&sorted).expect("UserDictWord is always serializable")
+}
+
+/// CSVを読む.
+///
+/// 見出し行と空行は読み飛ばします. 単語の種類と優先度は空欄にすると既定値になります.
+pub fn from_csv(csv: &str) -> Result<Vec<UserDictWord>, CsvError> {
+    let mut words = Vec::new();
+    for record in records(csv) {
+        let (line, fields) = record?;
+        if (fields.len() == 1 && fields[0].is_empty()) || fields == CSV_HEADER {
+            continue;
+        }
+        words.push(word(&fields).map_err(|kind| CsvError { line, kind })?);
+    }
+    Ok(words)
+}
+
+/// CSVを書く.
+///
+/// 差分を取りやすいように,表層形と発音の順に並べます.
+/// 品詞が`WordType`のどれにも当てはまらない単語は単語の種類が空欄になり,読み直すと固有名詞になります.
+pub fn to_csv<'a>(words: impl IntoIterator<Item = &'a UserDictWord>) -> String {
+    let mut words: Vec<_> = words.into_iter().collect();
+    words.sort_by(|a, b| {
+        (&a.surface, &a.pronunciation, a.accent_type).cmp(&(
+            &b.surface,
+            &b.pronunciation,
+            b.accent_type,
+        ))
+    });
+    let mut csv = CSV_HEADER.join(",");
+    csv.push('\n');
+    for word in words {
+        let fields = [
+            quote(&word.surface),
+            quote(&word.pronunciation),
+            word.accent_type.to_string(),
+            word.word_type()
+                .map(|word_type| word_type.as_str().to_owned())
+                .unwrap_or_default(),
+            word.priority.to_string(),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// 辞書を揃えるための変更.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserDictChange {
+    /// 単語を追加する. 適用するとエンジンが付けたUUIDが入る.
+    Add {
+        uuid: Option<String>,
+        word: UserDictWord,
+    },
+    /// アクセント型,単語の種類,優先度のいずれかが違うので書き換える.
+    Rewrite {
+        uuid: String,
+        from: Box<UserDictWord>,
+        to: UserDictWord,
+    },
+    /// 一覧にない単語か,同じ単語が重複しているので削除する.
+    Delete { uuid: String, word: UserDictWord },
+}
+
+/// エンジンの辞書`current`を`desired`と同じ内容にするための変更を求める.
+///
+/// 単語は表層形と発音が同じものを同じ単語とみなします. 表層形はエンジンと同じく全角にしてから比べます.
+/// `desired`に同じ単語が複数ある場合は後のものを使います.
+/// 変更は削除,書き換え,追加の順で,それぞれ表層形と発音の順に並べます.
+pub fn diff(
+    current: &HashMap<String, UserDictWord>,
+    desired: &[UserDictWord],
+) -> Vec<UserDictChange> {
+    let mut desired: BTreeMap<_, _> = desired.iter().map(|word| (key(word), word)).collect();
+    let mut current: Vec<_> = current.iter().collect();
+    current.sort_by(|(a_uuid, a), (b_uuid, b)| (key(a), a_uuid).cmp(&(key(b), b_uuid)));
+
+    let mut deletes = Vec::new();
+    let mut rewrites = Vec::new();
+    for (uuid, word) in current {
+        match desired.remove(&key(word)) {
+            Some(to) => {
+                let fields =
+                    |word: &UserDictWord| (word.accent_type, word.word_type(), word.priority);
+                if fields(word) != fields(to) {
+                    rewrites.push(UserDictChange::Rewrite {
+                        uuid: uuid.clone(),
+                        from: Box::new(word.clone()),
+                        to: to.clone(),
+                    });
+                }
+            }
+            // 一覧にないか,一覧の単語に既に対応付けた単語と重複している.
+            None => deletes.push(UserDictChange::Delete {
+                uuid: uuid.clone(),
+                word: word.clone(),
+            }),
+        }
+    }
+    let adds = desired.into_values().map(|word| UserDictChange::Add {
+        uuid: None,
+        word: word.clone(),
+    });
+    deletes.into_iter().chain(rewrites).chain(adds).collect()
+}
+
+/// 表層形と発音.
+fn key(word: &UserDictWord) -> (String, &str) {
+    (full_width_surface(&word.surface), &word.pronunciation)
+}
+
+/// エンジンの辞書を単語の一覧と同じ内容に揃えるリクエスト.
+///
+/// `UserDict`で取得した辞書と一覧の`diff`を,`UserDictWord`,`RewriteUserDictWord`,`DeleteUserDictWord`で適用します.
+/// 途中で失敗した場合,それまでの変更は適用されたままです. もう一度実行すると残りの変更を適用します.
+#[derive(Debug, Clone)]
+pub struct UserDictSync {
+    pub words: Vec<UserDictWord>,
+    /// 変更を求めるだけで適用しない.
+    pub dry_run: bool,
+}
+
+impl UserDictSync {
+    pub fn new(words: Vec<UserDictWord>) -> Self {
+        Self {
+            words,
+            dry_run: false,
+        }
+    }
+
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// 辞書を揃えて,適用した(`dry_run`の場合は適用する)変更を返す.
+    pub async fn run<T: Transport>(
+        self,
+        client: &VoiceVoxClient<T>,
+    ) -> Result<Vec<UserDictChange>, APIError> {
+        let mut changes = diff(&api::UserDict.call(client).await?, &self.words);
+        if self.dry_run {
+            return Ok(changes);
+        }
+        for change in &mut changes {
+            match change {
+                UserDictChange::Add { uuid, word } => {
+                    *uuid = Some(api::UserDictWord::from(&*word).call(client).await?);
+                }
+                UserDictChange::Rewrite { uuid, to, .. } => {
+                    api::UserDictWord::from(&*to)
+                        .rewrite(uuid.clone())
+                        .call(client)
+                        .await?;
+                }
+                UserDictChange::Delete { uuid, .. } => {
+                    api::DeleteUserDictWord { uuid: uuid.clone() }
+                        .call(client)
+                        .await?;
+                }
+            }
+        }
+        Ok(changes)
+    }
+
+    /// `run`と同じリクエストを同期的に送信する.
+    #[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+    pub fn run_blocking<T: crate::blocking::BlockingTransport>(
+        self,
+        client: &VoiceVoxClient<T>,
+    ) -> Result<Vec<UserDictChange>, APIError> {
+        let mut changes = diff(&api::UserDict.call_blocking(client)?, &self.words);
+        if self.dry_run {
+            return Ok(changes);
+        }
+        for change in &mut changes {
+            match change {
+                UserDictChange::Add { uuid, word } => {
+                    *uuid = Some(api::UserDictWord::from(&*word).call_blocking(client)?);
+                }
+                UserDictChange::Rewrite { uuid, to, .. } => {
+                    api::UserDictWord::from(&*to)
+                        .rewrite(uuid.clone())
+                        .call_blocking(client)?;
+                }
+                UserDictChange::Delete { uuid, .. } => {
+                    api::DeleteUserDictWord { uuid: uuid.clone() }.call_blocking(client)?;
+                }
+            }
+        }
+        Ok(changes)
+    }
+}
+
+/// CSVを読めなかった理由と行番号.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvError {
+    /// 1から始まる,その行が始まる行番号.
+    pub line: usize,
+    pub kind: CsvErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvErrorKind {
+    /// `"`が閉じられていない.
+    UnterminatedQuote,
+    /// 項目の数が3から5でない.
+    FieldCount(usize),
+    /// 表層形か発音が空.
+    EmptyField(&'static str),
+    InvalidAccentType(String),
+    InvalidWordType(UnknownWordType),
+    InvalidPriority(String),
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            CsvErrorKind::UnterminatedQuote => f.write_str("unterminated quote"),
+            CsvErrorKind::FieldCount(count) => {
+                write!(f, "expected 3 to 5 fields, found {count}")
+            }
+            CsvErrorKind::EmptyField(name) => write!(f, "{name} is empty"),
+            CsvErrorKind::InvalidAccentType(value) => write!(f, "invalid accent type `{value}`"),
+            CsvErrorKind::InvalidWordType(e) => e.fmt(f),
+            CsvErrorKind::InvalidPriority(value) => write!(f, "invalid priority `{value}`"),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+/// 1行分の項目から単語を作る.
+fn word(fields: &[String]) -> Result<UserDictWord, CsvErrorKind> {
+    if !(3..=5).contains(&fields.len()) {
+        return Err(CsvErrorKind::FieldCount(fields.len()));
+    }
+    let field = |index: usize| fields.get(index).map(String::as_str).unwrap_or_default();
+    for (index, name) in [(0, "surface"), (1, "pronunciation")] {
+        if field(index).is_empty() {
+            return Err(CsvErrorKind::EmptyField(name));
+        }
+    }
+    let accent_type = field(2)
+        .parse()
+        .map_err(|_| CsvErrorKind::InvalidAccentType(field(2).to_owned()))?;
+    let word_type = match field(3) {
+        "" => WordType::default(),
+        word_type => word_type.parse().map_err(CsvErrorKind::InvalidWordType)?,
+    };
+    let priority = match field(4) {
+        "" => UserDictWord::DEFAULT_PRIORITY,
+        priority => priority
+            .parse()
+            .map_err(|_| CsvErrorKind::InvalidPriority(priority.to_owned()))?,
+    };
+    Ok(UserDictWord::builder(field(0), field(1), accent_type)
+        .word_type(word_type)
+        .priority(priority)
+        .build())
+}
+
+/// 必要なら`"`で囲む.
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// CSVを行番号と項目の組に分ける.
+///
+/// `"`で囲んだ項目の中の改行は項目の一部として扱います.
+fn records(csv: &str) -> impl Iterator<Item = Result<(usize, Vec<String>), CsvError>> + '_ {
+    let mut chars = csv.chars().peekable();
+    let mut line = 1;
+    std::iter::from_fn(move || {
+        chars.peek()?;
+        let start = line;
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted => {
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                        field.push('"');
+                    } else {
+                        quoted = false;
+                    }
+                }
+                '"' if field.is_empty() => {
+                    quoted = true;
+                }
+                '\n' => {
+                    line += 1;
+                    if quoted {
+                        field.push(c);
+                    } else {
+                        break;
+                    }
+                }
+                '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+                ',' if !quoted => fields.push(std::mem::take(&mut field)),
+                _ => field.push(c),
+            }
+        }
+        if quoted {
+            // 残りを読み切ったので,次は`None`を返す.
+            return Some(Err(CsvError {
+                line: start,
+                kind: CsvErrorKind::UnterminatedQuote,
+            }));
+        }
+        fields.push(field);
+        Some(Ok((start, fields)))
+    })
+}
@@ -0,0 +1,69 @@
+//! エンジンのバージョン.
+//!
+//! 新しいエンジンで追加されたエンドポイントは`Endpoint::SINCE`に追加されたバージョンを持つので,
+//! 接続しているエンジンで使えるかを呼び出す前に確かめられます.
+//!
+//! ```ignore
+//! let version = Version::detect(&client).await?;
+//! if version.supports::<ValidateKana>() {
+//!     ValidateKana { text }.call(&client).await?;
+//! }
+//! ```
+
+use crate::endpoint::Endpoint;
+use std::fmt;
+
+/// `/version`が返すバージョン.
+///
+/// プレリリースなどの後ろに付く部分は無視します.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EngineVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl EngineVersion {
+    /// 開発版のエンジンが返す`latest`.全ての機能に対応しているとみなします.
+    pub const LATEST: Self = Self::new(u32::MAX, u32::MAX, u32::MAX);
+
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// `0.14.5`や`0.15.0-preview.1`のような文字列を読み取る.
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim();
+        if version == "latest" {
+            return Some(Self::LATEST);
+        }
+        let version = version.split(['-', '+']).next()?;
+        let mut parts = version.split('.').map(str::parse::<u32>);
+        let major = parts.next()?.ok()?;
+        let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+        let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+        match parts.next() {
+            Some(_) => None,
+            None => Some(Self::new(major, minor, patch)),
+        }
+    }
+
+    /// このバージョンのエンジンでエンドポイントを使えるか.
+    pub fn supports<E: Endpoint>(&self) -> bool {
+        E::SINCE.is_none_or(|since| *self >= since)
+    }
+}
+
+impl fmt::Display for EngineVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == Self::LATEST {
+            f.write_str("latest")
+        } else {
+            write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! wavの読み書き.
+
+use std::io::Write;
+use std::time::Duration;
+use voice_vox_api::audio::{
+    connect, connect_bytes, unpack_multi_synthesis, ArchiveEntryError, ConnectOptions, Wav,
+    WavError,
+};
+
+fn tone(bits_per_sample: u16) -> Wav {
+    let max = (1i64 << (bits_per_sample - 1)) - 1;
+    Wav {
+        sample_rate: 8000,
+        channels: 2,
+        bits_per_sample,
+        samples: vec![0, 1, -1, max as i32, -(max as i32) - 1, 0],
+    }
+}
+
+#[test]
+fn round_trip() {
+    for bits in [8, 16, 24, 32] {
+        let wav = tone(bits);
+        let bytes = wav.to_bytes().unwrap();
+        assert!(bytes.starts_with(b"RIFF"));
+        assert_eq!(bytes.len() % 2, 0);
+        assert_eq!(Wav::parse(&bytes), Ok(wav));
+    }
+}
+
+#[test]
+fn duration_and_peaks() {
+    let mut wav = Wav::silence(24000, 1, 16, Duration::from_millis(500));
+    assert_eq!(wav.frames(), 12000);
+    assert_eq!(wav.duration(), Duration::from_millis(500));
+    assert_eq!(wav.peak(), 0.0);
+
+    wav.samples[6000] = i16::MIN as i32;
+    assert_eq!(wav.peak(), 1.0);
+    assert_eq!(wav.peaks(4), [0.0, 0.0, 1.0, 0.0]);
+    assert_eq!(wav.peaks(0), Vec::<f32>::new());
+}
+
+#[test]
+fn skips_unknown_chunks() {
+    let wav = tone(16);
+    let bytes = wav.to_bytes().unwrap();
+    // fmtチャンクとdataチャンクの間にLISTチャンクを挟む.
+    let mut with_list = bytes[..36].to_vec();
+    with_list.extend_from_slice(b"LIST\x03\x00\x00\x00abc\x00");
+    with_list.extend_from_slice(&bytes[36..]);
+    assert_eq!(Wav::parse(&with_list), Ok(wav));
+}
+
+#[test]
+fn errors() {
+    assert_eq!(Wav::parse(b"not a wav"), Err(WavError::NotWave));
+    let bytes = tone(16).to_bytes().unwrap();
+    assert_eq!(Wav::parse(&bytes[..30]), Err(WavError::Truncated));
+    assert_eq!(Wav::parse(&bytes[..36]), Err(WavError::MissingData));
+
+    let mut float = bytes.clone();
+    float[20] = 3;
+    assert_eq!(Wav::parse(&float), Err(WavError::UnsupportedFormat(3)));
+    let mut bits = bytes.clone();
+    bits[34] = 12;
+    assert_eq!(Wav::parse(&bits), Err(WavError::UnsupportedBitDepth(12)));
+    // ブロックサイズがヘッダの16bitに収まらない.
+    let mut channels = bytes;
+    channels[22..24].copy_from_slice(&u16::MAX.to_le_bytes());
+    assert_eq!(Wav::parse(&channels), Err(WavError::TooLarge));
+
+    // バイトレートがヘッダの32bitに収まらないものは書き出さない.
+    let fast = Wav {
+        sample_rate: u32::MAX,
+        ..tone(16)
+    };
+    assert_eq!(fast.to_bytes(), Err(WavError::TooLarge));
+    let wide = Wav {
+        channels: u16::MAX,
+        ..tone(32)
+    };
+    assert_eq!(wide.to_bytes(), Err(WavError::TooLarge));
+    let empty = Wav {
+        channels: 0,
+        ..tone(16)
+    };
+    assert_eq!(empty.to_bytes(), Err(WavError::InvalidFormat));
+}
+
+#[test]
+fn resample_and_channels() {
+    let wav = Wav {
+        sample_rate: 4,
+        channels: 1,
+        bits_per_sample: 16,
+        samples: vec![0, 100, 200, 300],
+    };
+    let up = wav.resample(8);
+    assert_eq!(up.samples, [0, 50, 100, 150, 200, 250, 300, 300]);
+    assert_eq!(up.duration(), wav.duration());
+    let down = up.resample(4);
+    assert_eq!(down.samples, wav.samples);
+
+    let stereo = wav.to_channels(2).unwrap();
+    assert_eq!(stereo.samples, [0, 0, 100, 100, 200, 200, 300, 300]);
+    assert_eq!(stereo.to_channels(1).unwrap(), wav);
+    assert_eq!(
+        wav.to_channels(3),
+        Err(WavError::UnsupportedChannels { from: 1, to: 3 })
+    );
+}
+
+#[test]
+fn connect_with_silence() {
+    let mono = Wav {
+        sample_rate: 10,
+        channels: 1,
+        bits_per_sample: 16,
+        samples: vec![1, 2],
+    };
+    let stereo = Wav {
+        sample_rate: 20,
+        channels: 2,
+        bits_per_sample: 16,
+        samples: vec![3, 3, 3, 3, 3, 3, 3, 3],
+    };
+    let options = ConnectOptions {
+        silence: Duration::from_millis(100),
+        ..Default::default()
+    };
+    let connected = connect(&[mono.clone(), stereo.clone()], &options).unwrap();
+    assert_eq!((connected.sample_rate, connected.channels), (10, 2));
+    assert_eq!(connected.samples, [1, 1, 2, 2, 0, 0, 3, 3, 3, 3]);
+
+    let bytes = connect_bytes(
+        &[mono.to_bytes().unwrap(), stereo.to_bytes().unwrap()],
+        &options,
+    )
+    .unwrap();
+    assert_eq!(Wav::parse(&bytes), Ok(connected));
+
+    let options = ConnectOptions {
+        sample_rate: Some(20),
+        channels: Some(1),
+        ..Default::default()
+    };
+    let connected = connect(&[mono.clone(), stereo], &options).unwrap();
+    assert_eq!(connected.frames(), 8);
+    assert_eq!(connected.channels, 1);
+
+    let eight_bit = Wav {
+        bits_per_sample: 8,
+        ..mono.clone()
+    };
+    assert_eq!(
+        connect(&[mono, eight_bit], &ConnectOptions::default()),
+        Err(WavError::BitDepthMismatch {
+            index: 1,
+            expected: 16,
+            found: 8
+        })
+    );
+}
+
+#[test]
+fn unpack_archive() {
+    let mut archive = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::FileOptions::default();
+    archive.start_file("002.wav", options).unwrap();
+    archive.write_all(&tone(16).to_bytes().unwrap()).unwrap();
+    archive.start_file("001.wav", options).unwrap();
+    archive.write_all(&tone(8).to_bytes().unwrap()).unwrap();
+    archive.start_file("003.wav", options).unwrap();
+    archive.write_all(b"broken").unwrap();
+    let zip = archive.finish().unwrap().into_inner();
+
+    let wavs = unpack_multi_synthesis(&zip, 4).unwrap();
+    assert_eq!(wavs[0], Ok(tone(8)));
+    assert_eq!(wavs[1], Ok(tone(16)));
+    assert_eq!(
+        wavs[2],
+        Err(ArchiveEntryError::Wav {
+            name: "003.wav".to_owned(),
+            error: WavError::NotWave
+        })
+    );
+    assert_eq!(
+        wavs[3],
+        Err(ArchiveEntryError::Missing("004.wav".to_owned()))
+    );
+    assert!(unpack_multi_synthesis(b"not a zip", 1).is_err());
+}
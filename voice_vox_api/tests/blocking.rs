@@ -0,0 +1,214 @@
+//! `call_blocking`をモックのエンジンに対して呼び出す.
+//!
+//! 非同期ランタイムを使わずに呼び出せることを確かめます.
+
+use std::time::Duration;
+use voice_vox_api::{
+    api::{self, APIError, VoiceVoxClient},
+    api_schema::{self, PresetId, StyleId},
+    audio::Wav,
+    batch::{BatchEvent, BatchJob, BatchSynthesis},
+    blocking::BlockingClient,
+    mock::{MockEngine, ENGINE_UUID, ENGINE_VERSION},
+    registry::EngineRegistry,
+    retry::RetryPolicy,
+    user_dict::{UserDictChange, UserDictSync},
+};
+
+fn client(engine: &MockEngine) -> BlockingClient {
+    VoiceVoxClient::builder(&engine.base_url())
+        .build_blocking()
+        .unwrap()
+}
+
+#[test]
+fn version() {
+    let engine = MockEngine::start().unwrap();
+    let client = client(&engine);
+    let version = api::Version.call_blocking(&client).unwrap();
+    assert_eq!(version.as_deref(), Some(ENGINE_VERSION));
+
+    let client = client.negotiate_blocking().unwrap();
+    let capabilities = client.capabilities().unwrap();
+    assert_eq!(capabilities.version.to_string(), ENGINE_VERSION);
+    assert!(capabilities.features.synthesis_morphing);
+}
+
+#[test]
+fn registry() {
+    let engine = MockEngine::start().unwrap();
+    let other = MockEngine::start_as("00000000-0000-4000-8000-0000000000e2").unwrap();
+    let mut registry = EngineRegistry::new();
+    registry.add_blocking(client(&engine)).unwrap();
+    registry.add_blocking(client(&other)).unwrap();
+    assert_eq!(registry.engines()[0].id(), ENGINE_UUID);
+    assert_eq!(registry.speakers().len(), 6);
+    assert!(matches!(
+        registry.route(StyleId(0)).unwrap_err(),
+        APIError::AmbiguousStyle { .. }
+    ));
+}
+
+#[test]
+fn batch() {
+    let engine = MockEngine::start().unwrap();
+    let client = client(&engine);
+    let jobs = vec![
+        BatchJob::text("アイウエオ", StyleId(0)),
+        BatchJob::text("ア", StyleId(99)),
+        BatchJob::text("ア", StyleId(0)),
+    ];
+    let mut completed = Vec::new();
+    let results = BatchSynthesis::new(jobs)
+        .concurrency(2)
+        .run_blocking(&client, |event| {
+            if let BatchEvent::Finished { completed: n, .. } = event {
+                completed.push(n);
+            }
+        });
+    assert_eq!(completed, [1, 2, 3]);
+    assert!(matches!(results[1], Err(APIError::Validation(_))));
+    let first = Wav::parse(results[0].as_ref().unwrap()).unwrap();
+    let last = Wav::parse(results[2].as_ref().unwrap()).unwrap();
+    assert!(first.duration() > last.duration());
+}
+
+#[test]
+fn retry() {
+    let engine = MockEngine::start().unwrap();
+    engine.fail_next(2, 503);
+    let client = VoiceVoxClient::builder(&engine.base_url())
+        .retry(RetryPolicy::new(2).backoff(Duration::from_millis(10), Duration::from_millis(10)))
+        .build_blocking()
+        .unwrap();
+    let version = api::Version.call_blocking(&client).unwrap();
+    assert_eq!(version.as_deref(), Some(ENGINE_VERSION));
+
+    engine.fail_next(3, 503);
+    let version = client
+        .wait_until_ready_blocking(Duration::from_secs(10))
+        .unwrap();
+    assert_eq!(version.to_string(), ENGINE_VERSION);
+}
+
+#[test]
+fn speakers() {
+    let engine = MockEngine::start().unwrap();
+    let client = client(&engine);
+    let speakers = api::Speakers { core_version: None }
+        .call_blocking(&client)
+        .unwrap();
+    let info = api::SpeakerInfo {
+        speaker_uuid: speakers[0].speaker_uuid.clone(),
+        core_version: None,
+    }
+    .call_blocking(&client)
+    .unwrap();
+    assert_eq!(info.style_infos.len(), speakers[0].styles.len());
+}
+
+#[test]
+fn query_and_synthesis() {
+    let engine = MockEngine::start().unwrap();
+    let client = client(&engine);
+    let audio_query = api::AudioQuery {
+        text: "アイウ".to_owned(),
+        speaker: StyleId(0),
+        core_version: None,
+    }
+    .call_blocking(&client)
+    .unwrap();
+    let accent_phrases = api::MoraData {
+        speaker: StyleId(0),
+        core_version: None,
+        accent_phrases: audio_query.accent_phrases.clone(),
+    }
+    .call_blocking(&client)
+    .unwrap();
+    assert_eq!(accent_phrases, audio_query.accent_phrases);
+
+    let wav = api::Synthesis {
+        speaker: StyleId(0),
+        enable_interrogative_upspeak: None,
+        core_version: None,
+        audio_query,
+    }
+    .call_blocking(&client)
+    .unwrap();
+    assert!(wav.starts_with(b"RIFF"));
+
+    let e = api::AudioQuery {
+        text: "ア".to_owned(),
+        speaker: StyleId(99),
+        core_version: None,
+    }
+    .call_blocking(&client)
+    .unwrap_err();
+    assert!(matches!(e, APIError::Validation(_)));
+}
+
+#[test]
+fn presets() {
+    let engine = MockEngine::start().unwrap();
+    let client = client(&engine);
+    let preset = api_schema::Preset {
+        id: PresetId(1),
+        name: "preset".to_owned(),
+        speaker_uuid: "00000000-0000-4000-8000-000000000001".into(),
+        style_id: StyleId(0),
+        speedScale: 1.0,
+        pitchScale: 0.0,
+        intonationScale: 1.0,
+        volumeScale: 1.0,
+        prePhonemeLength: 0.1,
+        postPhonemeLength: 0.1,
+    };
+    let id = api::AddPreset { preset }.call_blocking(&client).unwrap();
+    assert_eq!(api::Presets.call_blocking(&client).unwrap().len(), 1);
+    api::DeletePreset { preset_id: id }
+        .call_blocking(&client)
+        .unwrap();
+    assert!(api::Presets.call_blocking(&client).unwrap().is_empty());
+}
+
+#[test]
+fn user_dict() {
+    let engine = MockEngine::start().unwrap();
+    let client = client(&engine);
+    let uuid = api::UserDictWord {
+        surface: "mock".to_owned(),
+        pronunciation: "モック".to_owned(),
+        accent_type: 1,
+        word_type: None,
+        priority: None,
+    }
+    .call_blocking(&client)
+    .unwrap();
+    let dict = api::UserDict.call_blocking(&client).unwrap();
+    assert!(dict.contains_key(&uuid));
+    api::DeleteUserDictWord { uuid }
+        .call_blocking(&client)
+        .unwrap();
+    assert!(api::UserDict.call_blocking(&client).unwrap().is_empty());
+
+    let words = vec![api_schema::UserDictWord::builder("mock", "モック", 1).build()];
+    let changes = UserDictSync::new(words.clone())
+        .run_blocking(&client)
+        .unwrap();
+    assert!(matches!(
+        changes[..],
+        [UserDictChange::Add { uuid: Some(_), .. }]
+    ));
+    let dict = api::UserDict.call_blocking(&client).unwrap();
+    assert_eq!(dict.into_values().collect::<Vec<_>>(), words);
+}
+
+#[test]
+fn connect_error() {
+    let engine = MockEngine::start().unwrap();
+    let base_url = engine.base_url();
+    drop(engine);
+    let client = VoiceVoxClient::builder(&base_url).build_blocking().unwrap();
+    let e = api::Version.call_blocking(&client).unwrap_err();
+    assert!(matches!(e, APIError::Connect(_)));
+}
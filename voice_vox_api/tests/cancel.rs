@@ -0,0 +1,37 @@
+use std::{
+    future::Future,
+    pin::pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+};
+use voice_vox_api::cancel::CancellationToken;
+
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+#[test]
+fn waiters_deregister() {
+    let token = CancellationToken::new();
+    let noop = Arc::new(NoopWaker);
+    let waker = Waker::from(noop.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    // 終わったリクエストの`Waker`をトークンが持ち続けない.
+    for _ in 0..100 {
+        let mut cancelled = pin!(token.cancelled());
+        assert_eq!(cancelled.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(cancelled.as_mut().poll(&mut cx), Poll::Pending);
+    }
+    assert_eq!(Arc::strong_count(&noop), 2);
+
+    let mut run = pin!(token.run(std::future::pending::<()>()));
+    assert_eq!(run.as_mut().poll(&mut cx), Poll::Pending);
+    assert_eq!(Arc::strong_count(&noop), 3);
+    token.cancel();
+    assert_eq!(Arc::strong_count(&noop), 2);
+    assert_eq!(run.as_mut().poll(&mut cx), Poll::Ready(None));
+    assert_eq!(pin!(token.cancelled()).poll(&mut cx), Poll::Ready(()));
+}
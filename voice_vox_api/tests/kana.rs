@@ -0,0 +1,108 @@
+use voice_vox_api::{
+    api_schema::{ErrorName, ParseKanaBadRequest, Phoneme},
+    kana::{self, KanaError},
+};
+
+#[test]
+fn parse() {
+    let accent_phrases = kana::parse("キャ'ンセル、_シ'ュ/ア'？").unwrap();
+    assert_eq!(accent_phrases.len(), 3);
+
+    let texts: Vec<&str> = accent_phrases[0]
+        .moras
+        .iter()
+        .map(|mora| mora.text.as_str())
+        .collect();
+    assert_eq!(texts, ["キャ", "ン", "セ", "ル"]);
+    assert_eq!(accent_phrases[0].accent, 1);
+    assert_eq!(accent_phrases[0].moras[0].consonant, Some(Phoneme::Ky));
+    assert_eq!(accent_phrases[0].moras[0].consonant_length, Some(0.0));
+    assert_eq!(accent_phrases[0].moras[1].vowel, Phoneme::MoraicN);
+    assert_eq!(accent_phrases[0].moras[1].consonant_length, None);
+    assert_eq!(
+        accent_phrases[0].pause_mora.as_ref().unwrap().vowel,
+        Phoneme::Pau
+    );
+    assert_eq!(accent_phrases[0].is_interrogative, Some(false));
+
+    // アクセント記号を跨いで"シュ"にはならない.
+    assert_eq!(accent_phrases[1].moras.len(), 2);
+    assert_eq!(accent_phrases[1].moras[0].vowel, Phoneme::DevoicedI);
+    assert!(accent_phrases[1].pause_mora.is_none());
+
+    assert_eq!(accent_phrases[2].is_interrogative, Some(true));
+}
+
+#[test]
+fn create_round_trip() {
+    for kana in [
+        "ア'",
+        "コンニチワ'、_シ'ャ/キョ'ウ",
+        "ア'イ/_カキ'？",
+        "ヴァ'イオリン、ファ'ン",
+    ] {
+        let accent_phrases = kana::parse(kana).unwrap();
+        assert_eq!(kana::create(&accent_phrases), kana);
+    }
+}
+
+#[test]
+fn errors() {
+    let error = |kana: &str| kana::parse(kana).unwrap_err();
+    assert_eq!(
+        error(""),
+        KanaError {
+            name: ErrorName::EmptyPhrase,
+            text: None,
+            position: Some(1),
+            range: Some(0..0),
+        }
+    );
+    assert_eq!(error("ア'//イ'").position, Some(2));
+    assert_eq!(error("'アイ").name, ErrorName::AccentTop);
+    assert_eq!(error("ア'イ'").name, ErrorName::AccentTwice);
+    assert_eq!(error("アイ/カ'").text.as_deref(), Some("アイ"));
+    assert_eq!(error("アイ/カ'").name, ErrorName::AccentNotFound);
+    assert_eq!(error("ア？イ'").name, ErrorName::InterrogationMarkNotAtEnd);
+
+    let unknown = error("ア'あい");
+    assert_eq!(unknown.name, ErrorName::UnknownText);
+    assert_eq!(unknown.text.as_deref(), Some("あい"));
+    assert_eq!(unknown.to_string(), "判別できない読み仮名があります: あい");
+    // 範囲は入力のバイト位置で,アクセントの誤りは記号を指す.
+    let input = "ア'イ/カ'キ'";
+    assert_eq!(&input[error(input).range.unwrap()], "'");
+    assert_eq!(error(input).range, Some(15..16));
+    assert_eq!(error("ア'/あ'").range, Some(5..8));
+    assert_eq!(error("ア'//イ'").range, Some(5..5));
+    // 撥音は無声化できない.
+    assert_eq!(error("ア'_ン").name, ErrorName::UnknownText);
+}
+
+#[test]
+fn error_name_serde() {
+    assert_eq!(
+        serde_json::to_string(&ErrorName::AccentNotFound).unwrap(),
+        "\"ACCENT_NOTFOUND\""
+    );
+    let name: ErrorName = serde_json::from_str("\"INTERROGATION_MARK_NOT_AT_END\"").unwrap();
+    assert_eq!(name, ErrorName::InterrogationMarkNotAtEnd);
+}
+
+#[test]
+fn from_bad_request() {
+    let bad_request: ParseKanaBadRequest = serde_json::from_str(
+        r#"{"text": "判別できない読み仮名があります: あ", "error_name": "UNKNOWN_TEXT", "error_args": {"text": "あ"}}"#,
+    )
+    .unwrap();
+    let e = KanaError::from_bad_request(&bad_request, "ア'/あ'").unwrap();
+    assert_eq!(e.name, ErrorName::UnknownText);
+    assert_eq!(e.text.as_deref(), Some("あ"));
+    assert_eq!(e.range, Some(5..8));
+    assert_eq!(e.to_string(), bad_request.text);
+
+    let bad_request: ParseKanaBadRequest =
+        serde_json::from_str(r#"{"text": "", "error_name": "SOMETHING_NEW", "error_args": {}}"#)
+            .unwrap();
+    assert!(KanaError::from_bad_request(&bad_request, "ア'").is_none());
+}
@@ -0,0 +1,1537 @@
+//! 全てのリクエストをモックのエンジンに対して呼び出す.
+//!
+//! 有効なバックエンドごとに同じテストを実行します.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use voice_vox_api::{
+    api::{self, APIError, AccentPhrasesErrors, VoiceVoxClient},
+    api_schema::{
+        self, CorsPolicyMode, Device, ErrorName, FrameAudioQuery, Note, Phoneme, PresetId, Score,
+        StyleId, StyleType, SupportedFeatures,
+    },
+    audio::Wav,
+    batch::{BatchEvent, BatchJob, BatchSynthesis},
+    cancel::CancellationToken,
+    capabilities::{EngineCapabilities, Feature},
+    mock::{MockEngine, ENGINE_UUID, ENGINE_VERSION, FRAME_DECODE_STYLE, SINGING_TEACHER_STYLE},
+    registry::{EngineRegistry, EngineStyleId},
+    retry::RetryPolicy,
+    speaker_cache::SpeakerCache,
+    synthesis_cache::{CacheKey, CacheableEndpoint, SynthesisCache},
+    transport::Transport,
+    user_dict::{self, UserDictChange, UserDictSync},
+    version::EngineVersion,
+};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+fn client<T: Transport>(engine: &MockEngine) -> VoiceVoxClient<T> {
+    VoiceVoxClient::builder(&engine.base_url())
+        .build_with()
+        .unwrap()
+}
+
+fn is_wav(bytes: &[u8]) -> bool {
+    Wav::parse(bytes).is_ok()
+}
+
+/// 検証エラーの場所.
+fn validation_loc(e: &APIError) -> Vec<String> {
+    match e {
+        APIError::Validation(e) => e.detail[0].loc.clone(),
+        e => panic!("expected validation error: {e}"),
+    }
+}
+
+async fn audio_query<T: Transport>(
+    client: &VoiceVoxClient<T>,
+    text: &str,
+) -> api_schema::AudioQuery {
+    api::AudioQuery {
+        text: text.to_owned(),
+        speaker: StyleId(0),
+        core_version: None,
+    }
+    .call(client)
+    .await
+    .unwrap()
+}
+
+async fn synthesis<T: Transport>(client: &VoiceVoxClient<T>, text: &str) -> Vec<u8> {
+    api::Synthesis {
+        speaker: StyleId(0),
+        enable_interrogative_upspeak: None,
+        core_version: None,
+        audio_query: audio_query(client, text).await,
+    }
+    .call(client)
+    .await
+    .unwrap()
+}
+
+fn preset(id: i32, name: &str) -> api_schema::Preset {
+    api_schema::Preset {
+        id: PresetId(id),
+        name: name.to_owned(),
+        speaker_uuid: "00000000-0000-4000-8000-000000000001".into(),
+        style_id: StyleId(1),
+        speedScale: 1.5,
+        pitchScale: 0.1,
+        intonationScale: 1.0,
+        volumeScale: 0.8,
+        prePhonemeLength: 0.2,
+        postPhonemeLength: 0.2,
+    }
+}
+
+fn word(surface: &str, pronunciation: &str) -> api::UserDictWord {
+    api::UserDictWord {
+        surface: surface.to_owned(),
+        pronunciation: pronunciation.to_owned(),
+        accent_type: 1,
+        word_type: Some(api_schema::WordType::ProperNoun),
+        priority: Some(5),
+    }
+}
+
+async fn version<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let version = api::Version.call(&client).await.unwrap();
+    assert_eq!(version.as_deref(), Some(ENGINE_VERSION));
+    let core_versions = api::CoreVersions.call(&client).await.unwrap();
+    assert_eq!(core_versions, vec![ENGINE_VERSION.to_owned()]);
+
+    let version = api::Version::detect(&client).await.unwrap();
+    assert_eq!(version.to_string(), ENGINE_VERSION);
+    assert!(version.supports::<api::InstalledLibraries>());
+    assert!(!EngineVersion::new(0, 13, 3).supports::<api::ValidateKana>());
+    assert!(EngineVersion::new(0, 13, 3).supports::<api::Synthesis>());
+    assert_eq!(
+        EngineVersion::parse("0.14.0-preview.2"),
+        Some(EngineVersion::new(0, 14, 0))
+    );
+    assert_eq!(EngineVersion::parse("latest"), Some(EngineVersion::LATEST));
+    assert_eq!(EngineVersion::parse("0.x"), None);
+}
+
+async fn speakers<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let speakers = api::Speakers { core_version: None }
+        .call(&client)
+        .await
+        .unwrap();
+    let style_ids: Vec<i32> = speakers
+        .iter()
+        .flat_map(|speaker| speaker.styles.iter().map(|style| style.id.0))
+        .collect();
+    assert_eq!(style_ids, vec![0, 1, 2, 3]);
+
+    let info = api::SpeakerInfo {
+        speaker_uuid: speakers[0].speaker_uuid.clone(),
+        core_version: None,
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert!(info.portrait.starts_with(PNG_SIGNATURE));
+    assert_eq!(info.style_infos.len(), 2);
+    for style_info in &info.style_infos {
+        assert!(style_info.icon.starts_with(PNG_SIGNATURE));
+        assert_eq!(style_info.voice_samples.len(), 3);
+        assert!(style_info.voice_samples.iter().all(|wav| is_wav(wav)));
+    }
+
+    let e = api::SpeakerInfo {
+        speaker_uuid: "unknown".into(),
+        core_version: None,
+    }
+    .call(&client)
+    .await
+    .unwrap_err();
+    assert_eq!(validation_loc(&e), ["query", "speaker_uuid"]);
+}
+
+async fn engine_info<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let devices = api::SupportedDevices { core_version: None }
+        .call(&client)
+        .await
+        .unwrap();
+    assert!(devices.cpu);
+    assert!(!devices.cuda);
+    assert_eq!(devices.devices(), [Device::Cpu]);
+    assert_eq!(Device::Cuda.engine_args(), ["--use_gpu"]);
+
+    let manifest = api::EngineManifest.call(&client).await.unwrap();
+    assert_eq!(manifest.uuid, ENGINE_UUID);
+    assert!(manifest.icon.starts_with(PNG_SIGNATURE));
+    assert!(manifest.supported_features.synthesis_morphing);
+    assert_eq!(manifest.supported_features.manage_library, Some(true));
+    assert_eq!(manifest.supported_features.sing, None);
+    assert_eq!(manifest.extra["brand_name"], "Mock");
+}
+
+async fn libraries<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    assert!(api::InstalledLibraries
+        .call(&client)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let downloadable = api::DownloadableLibraries.call(&client).await.unwrap();
+    assert_eq!(downloadable.len(), 1);
+    assert_eq!(downloadable[0].name, "モックライブラリ");
+    assert_eq!(downloadable[0].version, "0.0.1");
+    assert_eq!(downloadable[0].speakers[0].speaker.name, "モック話者C");
+    let library_uuid = downloadable[0].uuid.clone();
+    let e = api::InstallLibrary {
+        library_uuid: "00000000-0000-4000-8000-000000000000".to_owned(),
+        library: b"PK\x05\x06".to_vec(),
+    }
+    .call(&client)
+    .await
+    .unwrap_err();
+    assert_eq!(e.status(), Some(404));
+    let e = api::InstallLibrary {
+        library_uuid: library_uuid.clone(),
+        library: b"not a zip".to_vec(),
+    }
+    .call(&client)
+    .await
+    .unwrap_err();
+    assert_eq!(e.status(), Some(422));
+    api::InstallLibrary {
+        library_uuid: library_uuid.clone(),
+        library: b"PK\x05\x06".to_vec(),
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    let installed = api::InstalledLibraries.call(&client).await.unwrap();
+    let library = &installed[&library_uuid];
+    assert_eq!(library.version, downloadable[0].version);
+    assert!(library.uninstallable);
+    assert!(library.speakers[0]
+        .speaker_info
+        .portrait
+        .starts_with(PNG_SIGNATURE));
+
+    let uninstall = || api::UninstallLibrary {
+        library_uuid: library_uuid.clone(),
+    };
+    uninstall().call(&client).await.unwrap();
+    let e = uninstall().call(&client).await.unwrap_err();
+    assert_eq!(e.status(), Some(404));
+}
+
+async fn setting<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let page = api::Setting.call(&client).await.unwrap();
+    assert!(page.contains("localapps"));
+
+    api::UpdateSetting {
+        setting: api_schema::Setting {
+            cors_policy_mode: CorsPolicyMode::All,
+            allow_origin: Some("https://example.com http://localhost:8080".to_owned()),
+        },
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    let page = api::Setting.call(&client).await.unwrap();
+    assert!(page.contains("<option selected>all</option>"));
+    assert!(page.contains("https://example.com http://localhost:8080"));
+}
+
+async fn initialize_speaker<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let is_initialized = |speaker| api::IsInitializedSpeaker {
+        speaker,
+        core_version: None,
+    };
+    assert!(!is_initialized(StyleId(1)).call(&client).await.unwrap());
+    api::InitializeSpeaker {
+        speaker: StyleId(1),
+        core_version: None,
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert!(is_initialized(StyleId(1)).call(&client).await.unwrap());
+    assert!(!is_initialized(StyleId(0)).call(&client).await.unwrap());
+
+    let e = is_initialized(StyleId(99)).call(&client).await.unwrap_err();
+    assert_eq!(validation_loc(&e), ["query", "speaker"]);
+}
+
+async fn query<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let query = audio_query(&client, "アイウ、エオ？").await;
+    assert_eq!(query.accent_phrases.len(), 2);
+    assert_eq!(query.accent_phrases[0].moras.len(), 3);
+    assert!(query.accent_phrases[0].pause_mora.is_some());
+    assert_eq!(query.accent_phrases[1].is_interrogative, Some(true));
+    assert_eq!(query.kana.as_deref(), Some("ア'イウ、エ'オ？"));
+
+    let e = api::AudioQuery {
+        text: "ア".to_owned(),
+        speaker: StyleId(99),
+        core_version: None,
+    }
+    .call(&client)
+    .await
+    .unwrap_err();
+    assert_eq!(validation_loc(&e), ["query", "speaker"]);
+}
+
+async fn accent_phrases<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let accent_phrases = |text: &str, is_kana| api::AccentPhrases {
+        text: text.to_owned(),
+        speaker: StyleId(0),
+        is_kana,
+        core_version: None,
+    };
+    let response = accent_phrases("カキ", None).call(&client).await.unwrap();
+    assert_eq!(response.len(), 1);
+    let response = accent_phrases("ア'イ/_カキ'？", Some(true))
+        .call(&client)
+        .await
+        .unwrap();
+    assert_eq!(response.len(), 2);
+    assert_eq!(response[1].accent, 2);
+    assert_eq!(response[1].moras[0].vowel, Phoneme::DevoicedA);
+    assert_eq!(response[1].is_interrogative, Some(true));
+
+    // 400は`{"detail": ParseKanaBadRequest}`で返される.
+    let input = "ア'イ/アイ";
+    match accent_phrases(input, Some(true)).call(&client).await {
+        Err(AccentPhrasesErrors::KanaParseError(e)) => {
+            assert_eq!(e.name, ErrorName::AccentNotFound);
+            assert_eq!(e.text.as_deref(), Some("アイ"));
+            assert_eq!(&input[e.range.clone().unwrap()], "アイ");
+            assert_eq!(
+                e.to_string(),
+                "アクセントを指定していないアクセント句があります: アイ"
+            );
+        }
+        response => panic!("expected kana parse error: {response:?}"),
+    }
+    match accent_phrases("ア'//イ'", Some(true)).call(&client).await {
+        Err(AccentPhrasesErrors::KanaParseError(e)) => {
+            assert_eq!(e.name, ErrorName::EmptyPhrase);
+            assert_eq!(e.position, Some(2));
+        }
+        response => panic!("expected kana parse error: {response:?}"),
+    }
+
+    let validate_kana = |text: &str| api::ValidateKana {
+        text: text.to_owned(),
+    };
+    assert!(validate_kana("ア'イ/_カキ'？").call(&client).await.unwrap());
+    match validate_kana("'アイ").call(&client).await {
+        Err(AccentPhrasesErrors::KanaParseError(e)) => {
+            assert_eq!(e.name, ErrorName::AccentTop);
+            assert_eq!(e.range, Some(0..1));
+        }
+        response => panic!("expected kana parse error: {response:?}"),
+    }
+}
+
+async fn mora<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let mut accent_phrases = audio_query(&client, "カ_キ").await.accent_phrases;
+    for mora in &mut accent_phrases[0].moras {
+        mora.pitch = 0.0;
+        mora.vowel_length = 0.0;
+    }
+
+    let pitch = api::MoraPitch {
+        speaker: StyleId(2),
+        core_version: None,
+        accent_phrases: accent_phrases.clone(),
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert!(pitch[0].moras[0].pitch > 0.0);
+    assert_eq!(pitch[0].moras[0].vowel_length, 0.0);
+
+    let length = api::MoraLength {
+        speaker: StyleId(2),
+        core_version: None,
+        accent_phrases: accent_phrases.clone(),
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert_eq!(length[0].moras[0].pitch, 0.0);
+    assert!(length[0].moras[0].vowel_length > 0.0);
+
+    let data = api::MoraData {
+        speaker: StyleId(2),
+        core_version: None,
+        accent_phrases,
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert!(data[0].moras[0].pitch > 0.0);
+    assert!(data[0].moras[0].vowel_length > 0.0);
+}
+
+async fn synthesize<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let mut query = audio_query(&client, "アイウ").await;
+    query.outputSamplingRate = 48000;
+    query.outputStereo = true;
+
+    let wav = api::Synthesis {
+        speaker: StyleId(0),
+        enable_interrogative_upspeak: Some(true),
+        core_version: None,
+        audio_query: query.clone(),
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    let parsed = Wav::parse(&wav).unwrap();
+    assert_eq!((parsed.sample_rate, parsed.channels), (48000, 2));
+
+    let cancellable = api::CancellableSynthesis {
+        speaker: StyleId(0),
+        core_version: None,
+        audio_query: query.clone(),
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert_eq!(cancellable, wav);
+
+    let short = audio_query(&client, "ア").await;
+    let wavs = api::MultiSynthesis {
+        speaker: StyleId(0),
+        core_version: None,
+        audio_query: vec![query.clone(), short, query.clone()],
+    }
+    .call_wavs(&client)
+    .await
+    .unwrap();
+    let frames: Vec<usize> = wavs
+        .iter()
+        .map(|wav| wav.as_ref().unwrap().frames())
+        .collect();
+    assert_eq!(frames[0], Wav::parse(&wav).unwrap().frames());
+    assert!(frames[1] < frames[0]);
+    assert_eq!(frames[2], frames[0]);
+
+    query.outputSamplingRate = 0;
+    let e = api::Synthesis {
+        speaker: StyleId(0),
+        enable_interrogative_upspeak: None,
+        core_version: None,
+        audio_query: query,
+    }
+    .call(&client)
+    .await
+    .unwrap_err();
+    assert_eq!(validation_loc(&e), ["body"]);
+}
+
+async fn morphing<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let targets = api::MorpableTargets {
+        style_id: vec![StyleId(0), StyleId(2)],
+        core_version: None,
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert_eq!(targets.len(), 2);
+    assert!(targets[0][&StyleId(1)].is_morphable);
+    assert!(!targets[0][&StyleId(3)].is_morphable);
+    assert!(targets[1][&StyleId(2)].is_morphable);
+    assert!(!targets[1][&StyleId(0)].is_morphable);
+
+    let query = audio_query(&client, "アイ").await;
+    let morphing = |target_speaker| api::SynthesisMorphing {
+        base_speaker: StyleId(0),
+        target_speaker,
+        morph_rate: 0.5,
+        core_version: None,
+        audio_query: query.clone(),
+    };
+    let wav = morphing(StyleId(1)).call(&client).await.unwrap();
+    assert!(is_wav(&wav));
+    let e = morphing(StyleId(3)).call(&client).await.unwrap_err();
+    assert_eq!(e.status(), Some(400));
+}
+
+async fn capabilities<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine).negotiate().await.unwrap();
+    let capabilities = client.capabilities().unwrap().clone();
+    assert_eq!(capabilities.version.to_string(), ENGINE_VERSION);
+    assert!(capabilities.supports(Feature::InterrogativeUpspeak));
+
+    let mut query = audio_query(&client, "アイ？").await;
+    query.speedScale = 1.5;
+    let synthesis =
+        |audio_query: &api_schema::AudioQuery, enable_interrogative_upspeak| api::Synthesis {
+            speaker: StyleId(0),
+            enable_interrogative_upspeak,
+            core_version: None,
+            audio_query: audio_query.clone(),
+        };
+    assert!(is_wav(
+        &synthesis(&query, Some(true)).call(&client).await.unwrap()
+    ));
+
+    // 古いエンジンでは送信する前に失敗する.
+    let old = EngineCapabilities::new(
+        EngineVersion::new(0, 13, 3),
+        SupportedFeatures {
+            interrogative_upspeak: false,
+            synthesis_morphing: false,
+            adjust_speed_scale: false,
+            ..capabilities.features.clone()
+        },
+    );
+    let client = client.with_capabilities(old);
+    let unsupported = |e: APIError| match e {
+        APIError::Unsupported { feature, version } => {
+            assert_eq!(version, EngineVersion::new(0, 13, 3));
+            feature
+        }
+        e => panic!("expected unsupported: {e}"),
+    };
+    let e = synthesis(&query, Some(true))
+        .call(&client)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "engine 0.13.3 does not support adjusting speed scale"
+    );
+    query.speedScale = 1.0;
+    let e = synthesis(&query, Some(true))
+        .call(&client)
+        .await
+        .unwrap_err();
+    assert_eq!(unsupported(e), Feature::InterrogativeUpspeak);
+    let e = api::SynthesisMorphing {
+        base_speaker: StyleId(0),
+        target_speaker: StyleId(1),
+        morph_rate: 0.5,
+        core_version: None,
+        audio_query: query.clone(),
+    }
+    .call(&client)
+    .await
+    .unwrap_err();
+    assert_eq!(unsupported(e), Feature::SynthesisMorphing);
+    let e = api::InstalledLibraries.call(&client).await.unwrap_err();
+    assert_eq!(
+        unsupported(e),
+        Feature::Endpoint {
+            name: "InstalledLibraries",
+            since: EngineVersion::new(0, 15, 0),
+        }
+    );
+    // 対応している機能だけなら送信する.
+    let wav = synthesis(&query, Some(false)).call(&client).await.unwrap();
+    assert!(is_wav(&wav));
+
+    // 音高や音素長を調整できないエンジン.
+    let fixed = EngineCapabilities::new(
+        EngineVersion::new(0, 13, 3),
+        SupportedFeatures {
+            adjust_mora_pitch: false,
+            adjust_phoneme_length: false,
+            ..capabilities.features.clone()
+        },
+    );
+    let client = client.with_capabilities(fixed);
+    let e = api::MoraPitch {
+        speaker: StyleId(0),
+        core_version: None,
+        accent_phrases: query.accent_phrases.clone(),
+    }
+    .call(&client)
+    .await
+    .unwrap_err();
+    assert_eq!(unsupported(e), Feature::AdjustMoraPitch);
+    let e = api::MoraLength {
+        speaker: StyleId(0),
+        core_version: None,
+        accent_phrases: query.accent_phrases.clone(),
+    }
+    .call(&client)
+    .await
+    .unwrap_err();
+    assert_eq!(unsupported(e), Feature::AdjustPhonemeLength);
+    // `MoraData`はどちらの機能も使わない.
+    api::MoraData {
+        speaker: StyleId(0),
+        core_version: None,
+        accent_phrases: query.accent_phrases,
+    }
+    .call(&client)
+    .await
+    .unwrap();
+}
+
+async fn registry<T: Transport>() {
+    const OTHER_UUID: &str = "00000000-0000-4000-8000-0000000000e2";
+    let engine = MockEngine::start().unwrap();
+    let other = MockEngine::start_as(OTHER_UUID).unwrap();
+
+    let mut registry = EngineRegistry::<T>::new();
+    let registered = registry.add(client(&engine)).await.unwrap();
+    assert_eq!(registered.id(), ENGINE_UUID);
+    assert!(registered.client().capabilities().is_some());
+    // 同じエンジンを登録し直しても増えない.
+    registry.add(client(&engine)).await.unwrap();
+    assert_eq!(registry.engines().len(), 1);
+
+    // 2つ目のエンジンはモック話者Cだけを持っているとする.
+    let other_client = client::<T>(&other);
+    let manifest = api::EngineManifest.call(&other_client).await.unwrap();
+    let speakers = api::Speakers { core_version: None }
+        .call(&other_client)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|speaker| speaker.name == "モック話者C")
+        .collect();
+    registry.insert(other_client, manifest, speakers);
+
+    let speakers = registry.speakers();
+    assert_eq!(speakers.len(), 4);
+    let styles: Vec<EngineStyleId> = speakers
+        .iter()
+        .flat_map(|speaker| speaker.styles().map(|(id, _)| id))
+        .collect();
+    assert_eq!(styles.len(), 5);
+    assert_eq!(
+        styles.last(),
+        Some(&EngineStyleId {
+            engine_id: OTHER_UUID.to_owned(),
+            style_id: StyleId(3),
+        })
+    );
+
+    let style = registry.route(StyleId(1)).unwrap();
+    assert_eq!(style.engine_id, ENGINE_UUID);
+    match registry.route(StyleId(3)).unwrap_err() {
+        APIError::AmbiguousStyle { style_id, engines } => {
+            assert_eq!(style_id, StyleId(3));
+            assert_eq!(engines, [ENGINE_UUID, OTHER_UUID]);
+        }
+        e => panic!("expected ambiguous style: {e}"),
+    }
+    assert!(matches!(
+        registry.route(StyleId(99)).unwrap_err(),
+        APIError::UnknownStyle(StyleId(99))
+    ));
+
+    // エンジンを指定したスタイルはそのエンジンに送る.
+    let style = EngineStyleId {
+        engine_id: OTHER_UUID.to_owned(),
+        style_id: StyleId(3),
+    };
+    assert_eq!(
+        registry.client_for(&style).unwrap().base_url(),
+        other.base_url()
+    );
+    let query = registry.audio_query(&style, "アイウ").await.unwrap();
+    assert!(is_wav(&registry.synthesis(&style, query).await.unwrap()));
+    registry
+        .send(
+            OTHER_UUID,
+            api::InitializeSpeaker {
+                speaker: StyleId(3),
+                core_version: None,
+            },
+        )
+        .await
+        .unwrap();
+    let is_initialized = |engine_id: &'static str| {
+        registry.send(
+            engine_id,
+            api::IsInitializedSpeaker {
+                speaker: StyleId(3),
+                core_version: None,
+            },
+        )
+    };
+    assert!(is_initialized(OTHER_UUID).await.unwrap());
+    assert!(!is_initialized(ENGINE_UUID).await.unwrap());
+
+    let missing = EngineStyleId {
+        engine_id: OTHER_UUID.to_owned(),
+        style_id: StyleId(0),
+    };
+    assert!(matches!(
+        registry.client_for(&missing),
+        Err(APIError::UnknownStyle(StyleId(0)))
+    ));
+    assert!(registry.remove(OTHER_UUID).is_some());
+    let e = registry.audio_query(&style, "アイウ").await.unwrap_err();
+    assert_eq!(e.to_string(), format!("unknown engine {OTHER_UUID}"));
+}
+
+async fn speaker_cache<T: Transport>() {
+    let root = std::env::temp_dir().join(format!(
+        "voice_vox_api-speaker_cache-{}-{}",
+        std::process::id(),
+        std::any::type_name::<T>().rsplit("::").next().unwrap()
+    ));
+    let _ = std::fs::remove_dir_all(&root);
+    let engine = MockEngine::start().unwrap();
+    let online = client::<T>(&engine);
+    let cache = SpeakerCache::open(&root, &online).await.unwrap();
+    let dir = cache.dir().unwrap().to_owned();
+    assert_eq!(dir, root.join(ENGINE_UUID).join(ENGINE_VERSION));
+
+    let speakers = cache.speakers(&online).await.unwrap();
+    let uuid = &speakers[0].speaker_uuid;
+    let info = cache.speaker_info(&online, uuid).await.unwrap();
+    assert!(dir
+        .join(uuid.as_str())
+        .join("0")
+        .join("voice_sample_2.wav")
+        .exists());
+
+    // 同じバージョンのエンジンにはリクエストを送らない.
+    let base_url = engine.base_url();
+    drop(engine);
+    let offline = VoiceVoxClient::builder(&base_url)
+        .build_with::<T>()
+        .unwrap();
+    let version = EngineVersion::parse(ENGINE_VERSION).unwrap();
+    let cache = SpeakerCache::new(&root, ENGINE_UUID, version);
+    assert_eq!(
+        cache.speakers(&offline).await.unwrap().len(),
+        speakers.len()
+    );
+    let cached = cache.speaker_info(&offline, uuid).await.unwrap();
+    assert_eq!(cached.policy, info.policy);
+    assert_eq!(cached.portrait, info.portrait);
+    assert_eq!(cached.style_infos.len(), info.style_infos.len());
+    assert_eq!(
+        cached.style_infos[1].voice_samples,
+        info.style_infos[1].voice_samples
+    );
+    // 壊れたキャッシュは使わない.
+    std::fs::remove_file(dir.join(uuid.as_str()).join("portrait.png")).unwrap();
+    assert!(cache.load_speaker_info(uuid).is_none());
+    assert!(matches!(
+        cache.speaker_info(&offline, uuid).await,
+        Err(APIError::Connect(_))
+    ));
+
+    // バージョンが変わったら古いキャッシュを消して取得し直す.
+    let engine = MockEngine::start().unwrap();
+    let newer = SpeakerCache::new(&root, ENGINE_UUID, EngineVersion::new(0, 17, 0));
+    assert!(newer.load_speakers().is_none());
+    newer.speakers(&client::<T>(&engine)).await.unwrap();
+    newer.prune().unwrap();
+    assert!(!dir.exists());
+    assert!(newer.load_speakers().is_some());
+    // 開発版はキャッシュしない.
+    assert!(SpeakerCache::new(&root, ENGINE_UUID, EngineVersion::LATEST)
+        .dir()
+        .is_none());
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+async fn synthesis_cache<T: Transport>() {
+    let dir = std::env::temp_dir().join(format!(
+        "voice_vox_api-synthesis_cache-{}-{}",
+        std::process::id(),
+        std::any::type_name::<T>().rsplit("::").next().unwrap()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    let engine = MockEngine::start().unwrap();
+    let online = client::<T>(&engine).negotiate().await.unwrap();
+    let query = audio_query(&online, "アイウ").await;
+    let synthesis = |text: &str, enable_interrogative_upspeak| api::Synthesis {
+        speaker: StyleId(0),
+        enable_interrogative_upspeak,
+        core_version: None,
+        audio_query: api_schema::AudioQuery {
+            kana: Some(text.to_owned()),
+            ..query.clone()
+        },
+    };
+    let version = online.capabilities().unwrap().version;
+    // キーはリクエストの内容とエンジンのUUID,バージョンだけで決まる.
+    let key = synthesis("ア", None).cache_key(ENGINE_UUID, version);
+    assert_eq!(key, synthesis("ア", None).cache_key(ENGINE_UUID, version));
+    assert_eq!(key.to_string().len(), 64);
+    assert_ne!(
+        key,
+        synthesis("ア", Some(true)).cache_key(ENGINE_UUID, version)
+    );
+    assert_ne!(key, synthesis("イ", None).cache_key(ENGINE_UUID, version));
+    assert_ne!(
+        key,
+        synthesis("ア", None).cache_key(ENGINE_UUID, EngineVersion::new(0, 15, 0))
+    );
+    // スタイルIDはエンジンごとに振られるので,同じバージョンでも別のエンジンとは共有しない.
+    assert_ne!(
+        key,
+        synthesis("ア", None).cache_key("00000000-0000-4000-8000-0000000000e2", version)
+    );
+    assert_eq!(
+        SynthesisCache::key(&online, &synthesis("ア", None))
+            .await
+            .unwrap(),
+        Some(key)
+    );
+    // フィールドの順序はキーに影響しない.
+    let request = synthesis("ア", None).cache_request();
+    let mut fields: Vec<_> = request.as_object().unwrap().clone().into_iter().collect();
+    fields.reverse();
+    let reordered = serde_json::Value::Object(fields.into_iter().collect());
+    assert_eq!(CacheKey::new(&reordered, ENGINE_UUID, version), key);
+
+    let cache = Arc::new(
+        SynthesisCache::new(1 << 20)
+            .with_disk(&dir, 1 << 20)
+            .unwrap(),
+    );
+    let wav = cache.call(&online, synthesis("ア", None)).await.unwrap();
+    assert!(is_wav(&wav));
+    assert_eq!(cache.len(), 1);
+
+    // キャッシュにあるものはエンジンに送らない.
+    let base_url = engine.base_url();
+    drop(engine);
+    let offline = VoiceVoxClient::builder(&base_url)
+        .build_with::<T>()
+        .unwrap()
+        .with_capabilities(online.capabilities().unwrap().clone());
+    assert_eq!(
+        cache.call(&offline, synthesis("ア", None)).await.unwrap(),
+        wav
+    );
+    assert!(matches!(
+        cache.call(&offline, synthesis("イ", None)).await,
+        Err(APIError::Connect(_))
+    ));
+    // 開発版のエンジンはキャッシュを使わない.
+    let mut latest = online.capabilities().unwrap().clone();
+    latest.version = EngineVersion::LATEST;
+    let offline_latest = VoiceVoxClient::builder(&base_url)
+        .build_with::<T>()
+        .unwrap()
+        .with_capabilities(latest);
+    assert!(SynthesisCache::key(&offline_latest, &synthesis("ア", None))
+        .await
+        .unwrap()
+        .is_none());
+    assert!(matches!(
+        cache.call(&offline_latest, synthesis("ア", None)).await,
+        Err(APIError::Connect(_))
+    ));
+    // まとめて合成するときも同じキャッシュを使う.
+    let jobs = vec![
+        BatchJob::query(synthesis("ア", None).audio_query, StyleId(0)),
+        BatchJob::query(synthesis("イ", None).audio_query, StyleId(0)),
+    ];
+    let results = BatchSynthesis::new(jobs)
+        .cache(cache.clone())
+        .run(&offline, |_| {})
+        .await;
+    assert_eq!(results[0].as_ref().unwrap(), &wav);
+    assert!(matches!(results[1], Err(APIError::Connect(_))));
+    // ディスクのキャッシュは作り直しても残る.
+    let reopened = SynthesisCache::new(1 << 20)
+        .with_disk(&dir, 1 << 20)
+        .unwrap();
+    assert!(reopened.is_empty());
+    assert_eq!(reopened.get(&key), Some(wav.clone()));
+
+    // 上限を超えたら古いものから削除する.
+    let small = SynthesisCache::new(wav.len() * 2);
+    let keys: Vec<_> = ["ア", "イ", "ウ"]
+        .into_iter()
+        .map(|text| synthesis(text, None).cache_key(ENGINE_UUID, version))
+        .collect();
+    small.insert(keys[0], wav.clone()).unwrap();
+    small.insert(keys[1], wav.clone()).unwrap();
+    assert!(small.get(&keys[0]).is_some());
+    small.insert(keys[2], wav.clone()).unwrap();
+    assert_eq!(small.len(), 2);
+    assert_eq!(small.memory_bytes(), wav.len() * 2);
+    assert!(small.get(&keys[1]).is_none());
+    assert!(small.get(&keys[0]).is_some());
+
+    let disk_limited = SynthesisCache::new(0)
+        .with_disk(&dir, wav.len() as u64 * 2)
+        .unwrap();
+    for key in &keys {
+        disk_limited.insert(*key, wav.clone()).unwrap();
+    }
+    let stored = keys.iter().filter(|key| disk_limited.get(key).is_some());
+    assert_eq!(stored.count(), 2);
+    assert!(disk_limited.get(&keys[2]).is_some());
+    disk_limited.clear().unwrap();
+    assert!(disk_limited.get(&keys[2]).is_none());
+
+    // 同じディレクトリを使う他のインスタンスが書き込んだものも上限に数える.
+    let shared = SynthesisCache::new(0)
+        .with_disk(&dir, wav.len() as u64 * 2)
+        .unwrap();
+    disk_limited.insert(keys[0], wav.clone()).unwrap();
+    disk_limited.insert(keys[1], wav.clone()).unwrap();
+    shared.insert(keys[1], wav.clone()).unwrap();
+    shared.insert(keys[2], wav.clone()).unwrap();
+    let stored = keys.iter().filter(|key| shared.get(key).is_some());
+    assert_eq!(stored.count(), 2);
+    // 他のインスタンスが削除したものを置き換えても大きさを数え間違えない.
+    shared.clear().unwrap();
+    disk_limited.insert(keys[0], wav.clone()).unwrap();
+    disk_limited.insert(keys[1], wav.clone()).unwrap();
+    disk_limited.insert(keys[2], wav.clone()).unwrap();
+    let stored = keys.iter().filter(|key| disk_limited.get(key).is_some());
+    assert_eq!(stored.count(), 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+async fn cancel<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let synthesis = api::CancellableSynthesis {
+        speaker: StyleId(0),
+        core_version: None,
+        audio_query: audio_query(&client, "アイウ").await,
+    };
+
+    // 中断しなければ通常どおり返す.
+    let token = CancellationToken::new();
+    let wav = synthesis
+        .clone()
+        .call_cancellable(&client, &token)
+        .await
+        .unwrap();
+    assert!(is_wav(&wav));
+
+    // 送信中に中断するとレスポンスを待たずに返る.
+    engine.set_synthesis_delay(Duration::from_secs(5));
+    let stop = token.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(100));
+        stop.cancel();
+    });
+    let start = Instant::now();
+    let e = synthesis
+        .clone()
+        .call_cancellable(&client, &token)
+        .await
+        .unwrap_err();
+    assert!(matches!(e, APIError::Cancelled), "{e}");
+    assert!(start.elapsed() < Duration::from_secs(3));
+    assert!(token.is_cancelled());
+
+    // 中断済みのトークンでは送信しない.
+    engine.set_synthesis_delay(Duration::ZERO);
+    let e = api::Version
+        .call_cancellable(&client, &token)
+        .await
+        .unwrap_err();
+    assert_eq!(e.to_string(), "request was cancelled");
+    let version = api::Version
+        .call_cancellable(&client, &CancellationToken::new())
+        .await
+        .unwrap();
+    assert_eq!(version.as_deref(), Some(ENGINE_VERSION));
+}
+
+async fn batch<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let texts = ["ア", "アイウエオ", "アイ", "アイウ"];
+    let mut expected = Vec::new();
+    for text in texts {
+        expected.push(synthesis(&client, text).await);
+    }
+    let mut jobs: Vec<BatchJob> = texts
+        .iter()
+        .map(|text| BatchJob::text(*text, StyleId(0)))
+        .collect();
+    jobs[2] = BatchJob::query(audio_query(&client, "アイ").await, StyleId(0));
+    jobs.insert(1, BatchJob::text("アイ", StyleId(99)));
+
+    // 同時に送信する数を制限して並行に処理する.
+    engine.set_synthesis_delay(Duration::from_millis(400));
+    let mut events = Vec::new();
+    let start = Instant::now();
+    let results = BatchSynthesis::new(jobs)
+        .concurrency(5)
+        .run(&client, |event| events.push(event))
+        .await;
+    assert!(start.elapsed() < Duration::from_millis(1200));
+
+    // 失敗した行があっても他の行は入力の順番で返る.
+    assert_eq!(results.len(), 5);
+    assert_eq!(
+        validation_loc(results[1].as_ref().unwrap_err()),
+        ["query", "speaker"]
+    );
+    let wavs: Vec<&Vec<u8>> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+    assert_eq!(wavs, expected.iter().collect::<Vec<_>>());
+
+    let started = events
+        .iter()
+        .filter(|e| matches!(e, BatchEvent::Started { .. }))
+        .count();
+    assert_eq!(started, 5);
+    let finished: Vec<(usize, usize, bool)> = events
+        .iter()
+        .filter_map(|e| match e {
+            BatchEvent::Finished {
+                index,
+                completed,
+                total: 5,
+                error,
+            } => Some((*index, *completed, error.is_some())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        finished.iter().map(|f| f.1).collect::<Vec<_>>(),
+        [1, 2, 3, 4, 5]
+    );
+    assert!(finished.contains(&(1, 1, true)));
+
+    // 1行ずつ処理する場合は同時に1行しか送信しない.
+    engine.set_synthesis_delay(Duration::ZERO);
+    let mut running = 0;
+    let mut max_running = 0;
+    let jobs = texts
+        .iter()
+        .map(|text| BatchJob::text(*text, StyleId(0)))
+        .collect();
+    BatchSynthesis::new(jobs)
+        .run(&client, |event| {
+            match event {
+                BatchEvent::Started { .. } => running += 1,
+                BatchEvent::Finished { .. } => running -= 1,
+            }
+            max_running = max_running.max(running);
+        })
+        .await;
+    assert_eq!(max_running, 1);
+
+    // 中断すると残りの行は送信しない.
+    let token = CancellationToken::new();
+    token.cancel();
+    let jobs = texts
+        .iter()
+        .map(|text| BatchJob::text(*text, StyleId(0)))
+        .collect();
+    let results = BatchSynthesis::new(jobs)
+        .concurrency(2)
+        .cancel_with(token)
+        .run(&client, |_| {})
+        .await;
+    assert!(results
+        .iter()
+        .all(|r| matches!(r, Err(APIError::Cancelled))));
+}
+
+async fn retry<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let policy = RetryPolicy::new(3).backoff(Duration::from_millis(10), Duration::from_millis(50));
+    let retrying: VoiceVoxClient<T> = VoiceVoxClient::builder(&engine.base_url())
+        .retry(policy.clone())
+        .build_with()
+        .unwrap();
+
+    // 失敗が再試行の回数以内なら成功する.
+    engine.fail_next(2, 503);
+    let version = api::Version.call(&retrying).await.unwrap();
+    assert_eq!(version.as_deref(), Some(ENGINE_VERSION));
+
+    // 冪等でないリクエストは明示しない限りエラーのステータスでは再試行しない.
+    let add = || api::AddPreset {
+        preset: preset(1, "retry"),
+    };
+    engine.fail_next(1, 503);
+    let e = add().call(&retrying).await.unwrap_err();
+    assert_eq!(e.status(), Some(503));
+    assert!(api::Presets.call(&retrying).await.unwrap().is_empty());
+    let resending = VoiceVoxClient::builder(&engine.base_url())
+        .retry(policy.clone().retry_non_idempotent(true))
+        .build_with::<T>()
+        .unwrap();
+    engine.fail_next(1, 503);
+    assert_eq!(add().call(&resending).await.unwrap(), PresetId(1));
+
+    // 再試行しないステータスコードはそのまま返す.
+    engine.fail_next(1, 500);
+    let retrying = VoiceVoxClient::builder(&engine.base_url())
+        .retry(policy.retry_statuses([503]))
+        .build_with::<T>()
+        .unwrap();
+    let e = api::Version.call(&retrying).await.unwrap_err();
+    assert_eq!(e.status(), Some(500));
+
+    // 回数を超えた場合は最後のエラーを返す.1回の再試行で2回だけ送信する.
+    engine.fail_next(3, 503);
+    let retrying = VoiceVoxClient::builder(&engine.base_url())
+        .retry(RetryPolicy::new(1).backoff(Duration::ZERO, Duration::ZERO))
+        .build_with::<T>()
+        .unwrap();
+    let e = api::Version.call(&retrying).await.unwrap_err();
+    assert_eq!(e.status(), Some(503));
+    let e = client::<T>(&engine).send(api::Version).await.unwrap_err();
+    assert_eq!(e.status(), Some(503));
+
+    // 接続できない場合は間隔を空けて再試行する.
+    let offline = offline_url();
+    let client = VoiceVoxClient::builder(&offline)
+        .retry(RetryPolicy::new(2).backoff(Duration::from_millis(100), Duration::from_secs(1)))
+        .build_with::<T>()
+        .unwrap();
+    let start = Instant::now();
+    let e = api::Version.call(&client).await.unwrap_err();
+    assert!(matches!(e, APIError::Connect(_)), "{e}");
+    assert!(start.elapsed() >= Duration::from_millis(300));
+}
+
+async fn wait_until_ready<T: Transport>() {
+    // モデルを読み込んでいる間は503を返すエンジン.
+    let engine = MockEngine::start().unwrap();
+    engine.fail_next(3, 503);
+    let version = client::<T>(&engine)
+        .wait_until_ready(Duration::from_secs(10))
+        .await
+        .unwrap();
+    assert_eq!(version.to_string(), ENGINE_VERSION);
+
+    // 後から起動するエンジン.
+    let offline = offline_url();
+    let addr = offline.trim_start_matches("http://").to_owned();
+    let starting = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(300));
+        MockEngine::bind(addr).unwrap()
+    });
+    let client = VoiceVoxClient::builder(&offline).build_with::<T>().unwrap();
+    let version = client
+        .wait_until_ready(Duration::from_secs(10))
+        .await
+        .unwrap();
+    assert_eq!(version.to_string(), ENGINE_VERSION);
+    drop(starting.join().unwrap());
+
+    // 起動しなければ最後のエラーを返す.
+    let client = VoiceVoxClient::builder(&offline_url())
+        .build_with::<T>()
+        .unwrap();
+    let start = Instant::now();
+    let e = client
+        .wait_until_ready(Duration::from_millis(500))
+        .await
+        .unwrap_err();
+    assert!(matches!(e, APIError::Connect(_)), "{e}");
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+/// 何も待ち受けていないアドレス.
+fn offline_url() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+}
+
+async fn connect_waves<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let first = synthesis(&client, "ア").await;
+    let second = synthesis(&client, "アイウ").await;
+    let request = api::ConnectWaves {
+        waves: vec![first.clone(), second.clone()],
+    };
+    let offline = request.connect_offline(&Default::default()).unwrap();
+    let connected = request.call(&client).await.unwrap();
+    assert_eq!(connected, offline);
+    let connected = Wav::parse(&connected).unwrap();
+    let frames = Wav::parse(&first).unwrap().frames() + Wav::parse(&second).unwrap().frames();
+    assert_eq!(connected.frames(), frames);
+
+    let e = api::ConnectWaves {
+        waves: vec![b"not a wav".to_vec()],
+    }
+    .call(&client)
+    .await
+    .unwrap_err();
+    assert_eq!(validation_loc(&e), ["body"]);
+}
+
+async fn sing<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let singers = api::Singers { core_version: None }
+        .call(&client)
+        .await
+        .unwrap();
+    let types: Vec<&StyleType> = singers[0].styles.iter().map(|s| &s.style_type).collect();
+    assert_eq!(types, [&StyleType::SingingTeacher, &StyleType::FrameDecode]);
+    let info = api::SingerInfo {
+        speaker_uuid: singers[0].speaker_uuid.clone(),
+        core_version: None,
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert_eq!(info.style_infos.len(), singers[0].styles.len());
+
+    let note = |key, lyric: &str| Note {
+        id: Some(lyric.to_owned()),
+        key: Some(key),
+        frame_length: 30,
+        lyric: lyric.to_owned(),
+    };
+    let score = Score {
+        notes: vec![
+            Note::rest(15),
+            note(60, "ド"),
+            note(69, "ア"),
+            Note::rest(15),
+        ],
+    };
+    let query = api::SingFrameAudioQuery {
+        speaker: SINGING_TEACHER_STYLE,
+        core_version: None,
+        score: score.clone(),
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    let phonemes: Vec<&str> = query.phonemes.iter().map(|p| p.phoneme.as_str()).collect();
+    assert_eq!(phonemes, ["pau", "d", "o", "a", "pau"]);
+    assert_eq!(query.phonemes[1].note_id.as_deref(), Some("ド"));
+    assert_eq!(query.f0.len(), 90);
+
+    let moras = query.moras();
+    let texts: Vec<&str> = moras.iter().map(|m| m.text.as_str()).collect();
+    assert_eq!(texts, ["、", "ド", "ア", "、"]);
+    assert!((moras[2].pitch - 440f64.ln()).abs() < 1e-9);
+    assert_eq!(moras[0].pitch, 0.0);
+    assert!((moras[1].consonant_length.unwrap() - 2.0 / 93.75).abs() < 1e-9);
+    let rebuilt = FrameAudioQuery::from_moras(&moras, 0.3);
+    let frames: Vec<i32> = rebuilt.phonemes.iter().map(|p| p.frame_length).collect();
+    assert_eq!(frames, [15, 2, 28, 30, 15]);
+    assert_eq!(rebuilt.f0.len(), query.f0.len());
+
+    let wav = api::FrameSynthesis {
+        speaker: FRAME_DECODE_STYLE,
+        core_version: None,
+        frame_audio_query: query,
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert_eq!(Wav::parse(&wav).unwrap().frames(), 90 * 256);
+
+    // エンジンと同じく,ンは有声でッは無声.
+    let query = api::SingFrameAudioQuery {
+        speaker: SINGING_TEACHER_STYLE,
+        core_version: None,
+        score: Score {
+            notes: vec![note(60, "ン"), note(62, "ッ"), note(64, "ト")],
+        },
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    let moras = query.moras();
+    let vowels: Vec<Phoneme> = moras.iter().map(|m| m.vowel.clone()).collect();
+    assert_eq!(vowels, [Phoneme::MoraicN, Phoneme::Cl, Phoneme::O]);
+    let c4 = 440.0 * 2f64.powf(-9.0 / 12.0);
+    assert!((moras[0].pitch - f64::ln(c4)).abs() < 1e-9);
+    assert_eq!(moras[1].pitch, 0.0);
+    assert!(moras[2].pitch > 0.0);
+
+    let e = api::SingFrameAudioQuery {
+        speaker: SINGING_TEACHER_STYLE,
+        core_version: None,
+        score: Score {
+            notes: vec![note(60, "あ")],
+        },
+    }
+    .call(&client)
+    .await
+    .unwrap_err();
+    assert_eq!(e.status(), Some(400));
+    let e = api::SingFrameAudioQuery {
+        speaker: StyleId(0),
+        core_version: None,
+        score,
+    }
+    .call(&client)
+    .await
+    .unwrap_err();
+    assert_eq!(validation_loc(&e), ["query", "speaker"]);
+}
+
+async fn presets<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    assert!(api::Presets.call(&client).await.unwrap().is_empty());
+
+    let first = api::AddPreset {
+        preset: preset(1, "first"),
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    let second = api::AddPreset {
+        preset: preset(1, "second"),
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert_eq!((first, second), (PresetId(1), PresetId(2)));
+
+    let updated = api::UpdatePreset {
+        preset: preset(2, "updated"),
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert_eq!(updated, PresetId(2));
+    let presets = api::Presets.call(&client).await.unwrap();
+    let names: Vec<&str> = presets.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["first", "updated"]);
+
+    let query = api::AudioQueryFromPreset {
+        text: "アイ".to_owned(),
+        preset_id: PresetId(2),
+        core_version: None,
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert_eq!(query.speedScale, 1.5);
+
+    api::DeletePreset {
+        preset_id: PresetId(1),
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert_eq!(api::Presets.call(&client).await.unwrap().len(), 1);
+    let e = api::DeletePreset {
+        preset_id: PresetId(1),
+    }
+    .call(&client)
+    .await
+    .unwrap_err();
+    assert_eq!(validation_loc(&e), ["query", "id"]);
+    let e = api::UpdatePreset {
+        preset: preset(1, "deleted"),
+    }
+    .call(&client)
+    .await
+    .unwrap_err();
+    assert_eq!(validation_loc(&e), ["body"]);
+}
+
+async fn user_dict<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let uuid = word("mock", "モック").call(&client).await.unwrap();
+    let dict = api::UserDict.call(&client).await.unwrap();
+    assert_eq!(dict.keys().collect::<Vec<_>>(), [&uuid]);
+    // 手元で作った単語はエンジンが登録したものと同じになる.
+    assert_eq!(
+        dict[&uuid],
+        api_schema::UserDictWord::builder("mock", "モック", 1).build()
+    );
+    assert_eq!(api::UserDictWord::from(&dict[&uuid]).priority, Some(5));
+
+    let rewritten = api_schema::UserDictWord::builder("mock", "モックス", 2)
+        .word_type(api_schema::WordType::CommonNoun)
+        .build();
+    api::UserDictWord::from(&rewritten)
+        .rewrite(uuid.clone())
+        .call(&client)
+        .await
+        .unwrap();
+    let stored = &api::UserDict.call(&client).await.unwrap()[&uuid];
+    assert_eq!(stored, &rewritten);
+    assert_eq!(stored.word_type(), Some(api_schema::WordType::CommonNoun));
+    assert_eq!(
+        (stored.part_of_speech.as_str(), stored.mora_count),
+        ("名詞", Some(4))
+    );
+
+    let e = word("mock", "mock").call(&client).await.unwrap_err();
+    assert_eq!(validation_loc(&e), ["query", "pronunciation"]);
+
+    api::DeleteUserDictWord { uuid: uuid.clone() }
+        .call(&client)
+        .await
+        .unwrap();
+    assert!(api::UserDict.call(&client).await.unwrap().is_empty());
+    let e = api::DeleteUserDictWord { uuid }
+        .call(&client)
+        .await
+        .unwrap_err();
+    assert_eq!(validation_loc(&e), ["path", "word_uuid"]);
+
+    // `override`は必須のクエリで,同じUUIDの単語を上書きするかを決める.
+    let mut dictionary = dict.clone();
+    api::ImportUserDict {
+        over_ride: false,
+        dictionary: dictionary.clone(),
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert_eq!(api::UserDict.call(&client).await.unwrap(), dict);
+    dictionary.values_mut().for_each(|word| word.priority = 9);
+    api::ImportUserDict {
+        over_ride: false,
+        dictionary: dictionary.clone(),
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert_eq!(api::UserDict.call(&client).await.unwrap(), dict);
+    api::ImportUserDict {
+        over_ride: true,
+        dictionary: dictionary.clone(),
+    }
+    .call(&client)
+    .await
+    .unwrap();
+    assert_eq!(api::UserDict.call(&client).await.unwrap(), dictionary);
+}
+
+async fn user_dict_sync<T: Transport>() {
+    let engine = MockEngine::start().unwrap();
+    let client = client::<T>(&engine);
+    let mock = word("mock", "モック").call(&client).await.unwrap();
+    let duplicate = word("ｍｏｃｋ", "モック").call(&client).await.unwrap();
+    let old = word("old", "オールド").call(&client).await.unwrap();
+    let words = user_dict::from_csv(
+        "surface,pronunciation,accent_type,word_type,priority\n\
+         mock,モック,2,,\n\
+         new,ニュー,1,COMMON_NOUN,7\n",
+    )
+    .unwrap();
+
+    let planned = UserDictSync::new(words.clone())
+        .dry_run(true)
+        .run(&client)
+        .await
+        .unwrap();
+    let uuids: Vec<_> = planned
+        .iter()
+        .map(|change| match change {
+            UserDictChange::Delete { uuid, .. } => ("delete", Some(uuid)),
+            UserDictChange::Rewrite { uuid, .. } => ("rewrite", Some(uuid)),
+            UserDictChange::Add { uuid, .. } => ("add", uuid.as_ref()),
+        })
+        .collect();
+    // 先に登録した方を残し,重複した方を削除する.
+    assert_eq!(
+        uuids,
+        [
+            ("delete", Some(&duplicate)),
+            ("delete", Some(&old)),
+            ("rewrite", Some(&mock)),
+            ("add", None),
+        ]
+    );
+    assert_eq!(api::UserDict.call(&client).await.unwrap().len(), 3);
+
+    let applied = UserDictSync::new(words.clone()).run(&client).await.unwrap();
+    assert_eq!(applied[..3], planned[..3]);
+    let UserDictChange::Add {
+        uuid: Some(new), ..
+    } = &applied[3]
+    else {
+        panic!("{:?}", applied[3]);
+    };
+    let dict = api::UserDict.call(&client).await.unwrap();
+    assert_eq!(dict.len(), 2);
+    assert_eq!(dict[&mock], words[0]);
+    assert_eq!(dict[new], words[1]);
+    assert!(UserDictSync::new(words)
+        .run(&client)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+/// バックエンドごとに全てのテストを定義する.
+macro_rules! backend_tests {
+    ($transport:ty) => {
+        backend_tests!(
+            $transport;
+            version,
+            speakers,
+            engine_info,
+            libraries,
+            setting,
+            initialize_speaker,
+            query,
+            accent_phrases,
+            mora,
+            synthesize,
+            morphing,
+            capabilities,
+            registry,
+            speaker_cache,
+            synthesis_cache,
+            cancel,
+            batch,
+            retry,
+            wait_until_ready,
+            connect_waves,
+            sing,
+            presets,
+            user_dict,
+            user_dict_sync,
+        );
+    };
+    ($transport:ty; $($test:ident),* $(,)?) => {
+        $(
+            #[tokio::test]
+            async fn $test() {
+                super::$test::<$transport>().await;
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "backend_reqwest")]
+mod reqwest {
+    backend_tests!(voice_vox_api::reqwest_api::ReqwestTransport);
+}
+
+#[cfg(feature = "backend_surf")]
+mod surf {
+    backend_tests!(voice_vox_api::surf_api::SurfTransport);
+}
@@ -0,0 +1,230 @@
+//! スキーマの型がエンジンやエディタと同じ形式で読み書きできること.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use voice_vox_api::api_schema::{
+    AccentPhrase, AudioQuery, AudioQueryInProject, CorsPolicyMode, FramePhoneme, Mora,
+    MoraInProject, Phoneme, Preset, PresetId, Setting, Speaker,
+    SpeakerSupportPermittedSynthesisMorphing, SpeakerUuid, StyleId, StyleType, SupportedFeatures,
+    UserDictWord, WordType,
+};
+
+/// エディタのプロジェクトファイルのうち,このクレートの型を使う部分.
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize)]
+struct Project {
+    appVersion: String,
+    audioKeys: Vec<String>,
+    audioItems: HashMap<String, AudioItem>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize)]
+struct AudioItem {
+    text: String,
+    styleId: StyleId,
+    query: Option<AudioQueryInProject>,
+}
+
+fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &Value) -> Value {
+    let typed: T = serde_json::from_value(value.clone()).unwrap();
+    serde_json::to_value(typed).unwrap()
+}
+
+#[test]
+fn vvproj() {
+    let files = [
+        include_str!("../../sample.vvproj"),
+        include_str!("../../unnamed.vvproj"),
+    ];
+    for file in files {
+        let value: Value = serde_json::from_str(file).unwrap();
+        assert_eq!(round_trip::<Project>(&value), value);
+    }
+    let project: Project = serde_json::from_str(files[0]).unwrap();
+    let mut styles: Vec<StyleId> = project.audioItems.values().map(|ai| ai.styleId).collect();
+    styles.sort();
+    assert_eq!(styles, [StyleId(0), StyleId(2)]);
+}
+
+#[test]
+fn phonemes() {
+    assert_eq!(Phoneme::ALL.len(), 45);
+    for phoneme in Phoneme::ALL {
+        let value = serde_json::to_value(phoneme).unwrap();
+        assert_eq!(value, json!(phoneme.as_str()));
+        assert_eq!(&serde_json::from_value::<Phoneme>(value).unwrap(), phoneme);
+        assert_eq!(phoneme.as_str().parse::<Phoneme>().as_ref(), Ok(phoneme));
+    }
+    assert_eq!(Phoneme::MoraicN.as_str(), "N");
+    assert_eq!(Phoneme::N.as_str(), "n");
+    assert_eq!(Phoneme::Cl.as_str(), "cl");
+    assert_eq!(Phoneme::Pau.as_str(), "pau");
+    assert_eq!(Phoneme::U.devoiced(), Some(Phoneme::DevoicedU));
+    assert_eq!(Phoneme::DevoicedU.voiced(), Phoneme::U);
+    assert_eq!(Phoneme::MoraicN.devoiced(), None);
+    assert!(Phoneme::Cl.is_vowel() && !Phoneme::Ky.is_vowel());
+    assert_eq!(
+        "xa".parse::<Phoneme>().unwrap_err().to_string(),
+        "unknown phoneme `xa`"
+    );
+}
+
+#[test]
+fn moras() {
+    let engine = json!({
+        "text": "シュ",
+        "consonant": "sh",
+        "consonant_length": 0.05,
+        "vowel": "U",
+        "vowel_length": 0.1,
+        "pitch": 0.0,
+    });
+    let mora: Mora = serde_json::from_value(engine.clone()).unwrap();
+    assert_eq!(mora.consonant, Some(Phoneme::Sh));
+    assert_eq!(mora.vowel, Phoneme::DevoicedU);
+    assert_eq!(serde_json::to_value(&mora).unwrap(), engine);
+
+    let project = json!({
+        "text": "、",
+        "vowel": "pau",
+        "vowelLength": 0.3,
+        "pitch": 0.0,
+    });
+    assert_eq!(round_trip::<MoraInProject>(&project), project);
+
+    let phoneme = json!({ "phoneme": "cl", "frame_length": 4 });
+    assert_eq!(round_trip::<FramePhoneme>(&phoneme), phoneme);
+}
+
+#[test]
+fn ids() {
+    let preset = json!({
+        "id": 3,
+        "name": "preset",
+        "speaker_uuid": "00000000-0000-4000-8000-000000000001",
+        "style_id": 1,
+        "speedScale": 1.0,
+        "pitchScale": 0.0,
+        "intonationScale": 1.0,
+        "volumeScale": 1.0,
+        "prePhonemeLength": 0.1,
+        "postPhonemeLength": 0.1,
+    });
+    let typed: Preset = serde_json::from_value(preset.clone()).unwrap();
+    assert_eq!(typed.id, PresetId(3));
+    assert_eq!(typed.style_id, StyleId(1));
+    assert_eq!(
+        typed.speaker_uuid,
+        SpeakerUuid::from("00000000-0000-4000-8000-000000000001")
+    );
+    assert_eq!(serde_json::to_value(&typed).unwrap(), preset);
+
+    // `/morphable_targets`のようにスタイルIDをキーにしたオブジェクト.
+    let targets = json!({ "0": { "is_morphable": true } });
+    let typed: HashMap<StyleId, Value> = serde_json::from_value(targets.clone()).unwrap();
+    assert!(typed.contains_key(&StyleId(0)));
+    assert_eq!(serde_json::to_value(&typed).unwrap(), targets);
+    assert_eq!(StyleId(42).to_string(), "42");
+}
+
+#[test]
+fn newer_engine() {
+    let speakers = json!([
+        {
+            "name": "新しい話者",
+            "speaker_uuid": "00000000-0000-4000-8000-000000000001",
+            "styles": [
+                { "name": "ノーマル", "id": 0, "type": "talk" },
+                { "name": "ささやき", "id": 1, "type": "whisper", "order": 2 },
+            ],
+            "version": "1.0.0",
+            "supported_features": { "permitted_synthesis_morphing": "SAME_STYLE", "sing": true },
+            "order": 1,
+        },
+        {
+            "name": "古い話者",
+            "speaker_uuid": "00000000-0000-4000-8000-000000000002",
+            "styles": [{ "name": "ノーマル", "id": 2 }],
+        },
+    ]);
+    let typed: Vec<Speaker> = serde_json::from_value(speakers.clone()).unwrap();
+    assert_eq!(
+        typed[0].supported_features.permitted_synthesis_morphing,
+        SpeakerSupportPermittedSynthesisMorphing::Unknown("SAME_STYLE".to_owned())
+    );
+    assert_eq!(typed[0].supported_features.extra["sing"], true);
+    assert_eq!(
+        typed[0].styles[1].style_type,
+        StyleType::Unknown("whisper".to_owned())
+    );
+    assert_eq!(typed[0].styles[1].extra["order"], 2);
+    assert_eq!(typed[0].extra["order"], 1);
+    assert_eq!(serde_json::to_value(&typed[..1]).unwrap()[0], speakers[0]);
+    // 省略された項目は既定値になる.
+    assert_eq!(
+        typed[1].supported_features.permitted_synthesis_morphing,
+        SpeakerSupportPermittedSynthesisMorphing::ALL
+    );
+    assert_eq!(typed[1].styles[0].style_type, StyleType::Talk);
+    assert_eq!(typed[1].version, None);
+
+    let features: SupportedFeatures =
+        serde_json::from_value(json!({ "adjust_speed_scale": false, "sing": true, "foo": 1 }))
+            .unwrap();
+    assert!(!features.adjust_speed_scale);
+    assert!(features.adjust_pitch_scale && features.synthesis_morphing);
+    assert_eq!(features.sing, Some(true));
+    assert_eq!(features.manage_library, None);
+    assert_eq!(features.extra["foo"], 1);
+
+    // 知らないクエリの項目は合成するときにそのまま送り返す.
+    let mut query = serde_json::to_value(AudioQuery::default()).unwrap();
+    query["pauseLength"] = json!(0.5);
+    query["pauseLengthScale"] = json!(1.0);
+    let typed: AudioQuery = serde_json::from_value(query.clone()).unwrap();
+    assert_eq!(typed.extra["pauseLength"], 0.5);
+    assert_eq!(serde_json::to_value(&typed).unwrap(), query);
+
+    // 知らない音素,単語の種類,CORSの設定もそのまま読み書きする.
+    let phrase = json!({
+        "moras": [{
+            "text": "ヴォ",
+            "consonant": "xv",
+            "consonant_length": 0.1,
+            "vowel": "xo",
+            "vowel_length": 0.1,
+            "pitch": 5.0,
+        }],
+        "accent": 1,
+        "pause_mora": null,
+        "is_interrogative": false,
+    });
+    let typed: AccentPhrase = serde_json::from_value(phrase.clone()).unwrap();
+    assert_eq!(
+        typed.moras[0].consonant,
+        Some(Phoneme::Unknown("xv".to_owned()))
+    );
+    assert_eq!(typed.moras[0].vowel, Phoneme::Unknown("xo".to_owned()));
+    assert!(!typed.moras[0].vowel.is_vowel());
+    assert_eq!(serde_json::to_value(&typed).unwrap(), phrase);
+
+    let word_type: WordType = serde_json::from_value(json!("INTERJECTION")).unwrap();
+    assert_eq!(word_type, WordType::Unknown("INTERJECTION".to_owned()));
+    assert_eq!(serde_json::to_value(&word_type).unwrap(), "INTERJECTION");
+    assert_eq!(word_type.part_of_speech(), None);
+    let word = UserDictWord::builder("わあ", "ワア", 1)
+        .word_type(word_type)
+        .build();
+    assert_eq!(word.context_id, None);
+    assert_eq!(word.word_type(), None);
+
+    let setting = json!({ "cors_policy_mode": "strict", "allow_origin": null });
+    let typed: Setting = serde_json::from_value(setting.clone()).unwrap();
+    assert_eq!(
+        typed.cors_policy_mode,
+        CorsPolicyMode::Unknown("strict".to_owned())
+    );
+    assert_eq!(serde_json::to_value(&typed).unwrap(), setting);
+}
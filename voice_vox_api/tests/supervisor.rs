@@ -0,0 +1,142 @@
+//! `mock_engine`をエンジンの代わりに起動して監視する.
+
+use std::time::{Duration, Instant};
+use voice_vox_api::{
+    api::{self, APIError, VoiceVoxClient},
+    mock::ENGINE_VERSION,
+    supervisor::{EngineConfig, EngineStatus, EngineSupervisor},
+};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn config() -> EngineConfig {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    EngineConfig::new(env!("CARGO_BIN_EXE_mock_engine"))
+        .port(port)
+        .startup_timeout(TIMEOUT)
+        .health_interval(Duration::from_millis(100))
+        .shutdown_timeout(SHUTDOWN_TIMEOUT)
+}
+
+fn is_ready(status: &EngineStatus) -> bool {
+    matches!(status, EngineStatus::Ready(_))
+}
+
+#[test]
+fn start_restart_and_shutdown() {
+    let engine = EngineSupervisor::start(config()).unwrap();
+    let status = engine.wait_for(TIMEOUT, is_ready).unwrap();
+    assert_eq!(status.to_string(), format!("ready ({ENGINE_VERSION})"));
+    let client = VoiceVoxClient::new_blocking(&engine.base_url()).unwrap();
+    let version = api::Version.call_blocking(&client).unwrap();
+    assert_eq!(version.as_deref(), Some(ENGINE_VERSION));
+
+    // 要求すると別のプロセスで起動し直す.
+    let pid = engine.pid().unwrap();
+    engine.restart();
+    let deadline = Instant::now() + TIMEOUT;
+    while engine.pid().is_none_or(|new| new == pid) {
+        assert!(Instant::now() < deadline);
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    engine.wait_for(TIMEOUT, is_ready).unwrap();
+    assert!(api::Version.call_blocking(&client).is_ok());
+
+    // 終了を求めると強制終了するまで待たずに終了する.
+    let base_url = engine.base_url();
+    let start = Instant::now();
+    engine.shutdown();
+    assert!(start.elapsed() < SHUTDOWN_TIMEOUT);
+    let client = VoiceVoxClient::new_blocking(&base_url).unwrap();
+    let e = api::Version.call_blocking(&client).unwrap_err();
+    assert!(matches!(e, APIError::Connect(_)), "{e}");
+}
+
+#[cfg(unix)]
+#[test]
+fn restart_after_crash() {
+    let engine = EngineSupervisor::start(config()).unwrap();
+    engine.wait_for(TIMEOUT, is_ready).unwrap();
+    let pid = engine.pid().unwrap();
+    let killed = std::process::Command::new("kill")
+        .args(["-9", &pid.to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+    let status = engine
+        .wait_for(TIMEOUT, |status| {
+            matches!(status, EngineStatus::Crashed { .. })
+        })
+        .unwrap();
+    assert!(
+        matches!(
+            status,
+            EngineStatus::Crashed {
+                restarting: true,
+                ..
+            }
+        ),
+        "{status}"
+    );
+    engine.wait_for(TIMEOUT, is_ready).unwrap();
+    assert_ne!(engine.pid(), Some(pid));
+}
+
+#[test]
+fn give_up_after_max_restarts() {
+    // `mock_engine`は知らない引数を渡すとすぐに終了する.
+    let engine = EngineSupervisor::start(config().arg("--unknown").max_restarts(1)).unwrap();
+    let status = engine
+        .wait_for(TIMEOUT, |status| {
+            matches!(
+                status,
+                EngineStatus::Crashed {
+                    restarting: false,
+                    ..
+                }
+            )
+        })
+        .unwrap();
+    assert!(status.to_string().contains("exited"), "{status}");
+
+    let missing = EngineConfig::new("/nonexistent/voicevox_engine").max_restarts(0);
+    let engine = EngineSupervisor::start(missing).unwrap();
+    let status = engine
+        .wait_for(TIMEOUT, |status| {
+            matches!(
+                status,
+                EngineStatus::Crashed {
+                    restarting: false,
+                    ..
+                }
+            )
+        })
+        .unwrap();
+    assert!(status.to_string().contains("failed to launch"), "{status}");
+}
+
+#[tokio::test]
+async fn watch() {
+    let engine = EngineSupervisor::start(config()).unwrap();
+    let mut watcher = engine.watch();
+    let mut status = watcher.changed().await;
+    while !is_ready(&status) {
+        status = tokio::time::timeout(TIMEOUT, watcher.changed())
+            .await
+            .unwrap();
+    }
+    engine.restart();
+    let status = tokio::time::timeout(TIMEOUT, watcher.changed())
+        .await
+        .unwrap();
+    // 起動し直したエンジンの準備が済んでいる場合は最後の状態だけを受け取る.
+    assert!(
+        matches!(status, EngineStatus::Starting | EngineStatus::Ready(_)),
+        "{status}"
+    );
+}
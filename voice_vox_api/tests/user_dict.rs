@@ -0,0 +1,237 @@
+use serde_json::json;
+use std::collections::HashMap;
+use voice_vox_api::{
+    api,
+    api_schema::{UnknownWordType, UserDictWord, WordType},
+    user_dict::{self, CsvError, CsvErrorKind, UserDictChange},
+};
+
+#[test]
+fn builder() {
+    let word = UserDictWord::builder("ずんだもん", "ズンダモン", 1).build();
+    assert_eq!(
+        UserDictWord::builder("VOICEVOX 2", "ボイスボックス", 1)
+            .build()
+            .surface,
+        "ＶＯＩＣＥＶＯＸ ２"
+    );
+    assert_eq!(word.word_type(), Some(WordType::ProperNoun));
+    assert_eq!(word.priority, UserDictWord::DEFAULT_PRIORITY);
+    assert_eq!(word.mora_count, Some(5));
+    assert_eq!(word.yomi, "ズンダモン");
+
+    for word_type in WordType::ALL {
+        let word = UserDictWord::builder("食べる", "タベル", 2)
+            .word_type(word_type.clone())
+            .priority(10)
+            .build();
+        assert_eq!(word.word_type().as_ref(), Some(word_type));
+        assert_eq!(
+            word.context_id,
+            word_type.part_of_speech().map(|(id, _)| id)
+        );
+    }
+    let mut word = UserDictWord::builder("ぁ", "ァ", 0).build();
+    word.part_of_speech = "感動詞".to_owned();
+    assert_eq!(word.word_type(), None);
+    assert_eq!(word.mora_count, Some(0));
+}
+
+#[test]
+fn word_type() {
+    for word_type in WordType::ALL {
+        assert_eq!(
+            word_type.to_string().parse::<WordType>().as_ref(),
+            Ok(word_type)
+        );
+        let value = serde_json::to_value(word_type).unwrap();
+        assert_eq!(
+            &serde_json::from_value::<WordType>(value).unwrap(),
+            word_type
+        );
+    }
+    assert_eq!(
+        "NOUN".parse::<WordType>(),
+        Err(UnknownWordType("NOUN".to_owned()))
+    );
+}
+
+#[test]
+fn requests() {
+    let word = UserDictWord::builder("mock", "モック", 1)
+        .word_type(WordType::Verb)
+        .priority(3)
+        .build();
+    let request = api::UserDictWord::from(&word);
+    assert_eq!(request.word_type, Some(WordType::Verb));
+    assert_eq!(request.priority, Some(3));
+    assert_eq!(UserDictWord::from(request.clone()), word);
+
+    let rewrite = request.rewrite("uuid");
+    assert_eq!(rewrite.uuid, "uuid");
+    assert_eq!(UserDictWord::from(rewrite), word);
+
+    // 省略した項目はエンジンの既定値になる.
+    let request = api::UserDictWord {
+        surface: "mock".to_owned(),
+        pronunciation: "モック".to_owned(),
+        accent_type: 1,
+        word_type: None,
+        priority: None,
+    };
+    assert_eq!(
+        UserDictWord::from(request),
+        UserDictWord::builder("mock", "モック", 1).build()
+    );
+}
+
+#[test]
+fn json() {
+    // エンジンの`/user_dict`が返す形式.
+    let exported = json!({
+        "00000000-0000-4000-8000-000000000001": {
+            "surface": "ｍｏｃｋ",
+            "priority": 5,
+            "context_id": 1348,
+            "part_of_speech": "名詞",
+            "part_of_speech_detail_1": "固有名詞",
+            "part_of_speech_detail_2": "一般",
+            "part_of_speech_detail_3": "*",
+            "inflectional_type": "*",
+            "inflectional_form": "*",
+            "stem": "*",
+            "yomi": "モック",
+            "pronunciation": "モック",
+            "accent_type": 1,
+            "mora_count": 3,
+            "accent_associative_rule": "*",
+        }
+    });
+    let dictionary = user_dict::from_json(&exported.to_string()).unwrap();
+    let word = &dictionary["00000000-0000-4000-8000-000000000001"];
+    assert_eq!(
+        word,
+        &UserDictWord::builder("ｍｏｃｋ", "モック", 1).build()
+    );
+    let written = user_dict::to_json(&dictionary);
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&written).unwrap(),
+        exported
+    );
+    assert_eq!(user_dict::from_json(&written).unwrap(), dictionary);
+}
+
+#[test]
+fn csv() {
+    let mut quoted = UserDictWord::builder("ab", "エービー", 1)
+        .word_type(WordType::CommonNoun)
+        .priority(9)
+        .build();
+    quoted.surface = "a,\"b\"".to_owned();
+    let words = [
+        UserDictWord::builder("ずんだもん", "ズンダモン", 1).build(),
+        quoted,
+    ];
+    let csv = user_dict::to_csv(&words);
+    assert_eq!(
+        csv,
+        "surface,pronunciation,accent_type,word_type,priority\n\
+         \"a,\"\"b\"\"\",エービー,1,COMMON_NOUN,9\n\
+         ずんだもん,ズンダモン,1,PROPER_NOUN,5\n"
+    );
+    let read = user_dict::from_csv(&csv).unwrap();
+    assert_eq!(read[1], words[0]);
+    // 表層形はエンジンと同じく全角になる.
+    assert_eq!(read[0].surface, "ａ，＂ｂ＂");
+    assert_eq!(
+        (read[0].word_type(), read[0].priority),
+        (Some(WordType::CommonNoun), 9)
+    );
+
+    // 見出し行はなくてもよく,単語の種類と優先度は省略できる.
+    let read = user_dict::from_csv("\r\nもち,モチ,0\r\n\"改\n行\",カイギョウ,0,,\r\n").unwrap();
+    assert_eq!(
+        read,
+        [
+            UserDictWord::builder("もち", "モチ", 0).build(),
+            UserDictWord::builder("改\n行", "カイギョウ", 0).build(),
+        ]
+    );
+
+    let error = |csv: &str| user_dict::from_csv(csv).unwrap_err();
+    assert_eq!(
+        error("もち,モチ\n"),
+        CsvError {
+            line: 1,
+            kind: CsvErrorKind::FieldCount(2)
+        }
+    );
+    assert_eq!(
+        error("\"改\n行\",カイギョウ,0\n,モチ,0\n").kind,
+        CsvErrorKind::EmptyField("surface")
+    );
+    assert_eq!(error("\"改\n行\",カイギョウ,0\n,モチ,0\n").line, 3);
+    assert_eq!(
+        error("もち,モチ,x\n").kind,
+        CsvErrorKind::InvalidAccentType("x".to_owned())
+    );
+    assert_eq!(
+        error("もち,モチ,0,NOUN\n").kind,
+        CsvErrorKind::InvalidWordType(UnknownWordType("NOUN".to_owned()))
+    );
+    assert_eq!(
+        error("もち,モチ,0,,high\n").to_string(),
+        "line 1: invalid priority `high`"
+    );
+    assert_eq!(
+        error("もち,\"モチ,0\n").kind,
+        CsvErrorKind::UnterminatedQuote
+    );
+}
+
+#[test]
+fn diff() {
+    let word = |surface: &str, pronunciation: &str, accent_type| {
+        UserDictWord::builder(surface, pronunciation, accent_type).build()
+    };
+    let current = HashMap::from([
+        ("1".to_owned(), word("mock", "モック", 1)),
+        ("2".to_owned(), word("mock", "モック", 1)),
+        ("3".to_owned(), word("same", "セイム", 1)),
+        ("4".to_owned(), word("old", "オールド", 1)),
+    ]);
+    // 表層形は全角にしてから比べ,重複した単語は後のものを使う.
+    let mut rewritten = current["3"].clone();
+    rewritten.surface = "same".to_owned();
+    rewritten.priority = 8;
+    let desired = [
+        word("ｍｏｃｋ", "モック", 1),
+        word("same", "セイム", 0),
+        rewritten.clone(),
+        word("new", "ニュー", 1),
+    ];
+    assert_eq!(
+        user_dict::diff(&current, &desired),
+        [
+            UserDictChange::Delete {
+                uuid: "2".to_owned(),
+                word: current["2"].clone(),
+            },
+            UserDictChange::Delete {
+                uuid: "4".to_owned(),
+                word: current["4"].clone(),
+            },
+            UserDictChange::Rewrite {
+                uuid: "3".to_owned(),
+                from: Box::new(current["3"].clone()),
+                to: rewritten,
+            },
+            UserDictChange::Add {
+                uuid: None,
+                word: word("new", "ニュー", 1),
+            },
+        ]
+    );
+    assert_eq!(user_dict::diff(&current, &[]).len(), 4);
+    assert!(user_dict::diff(&HashMap::new(), &[]).is_empty());
+}
@@ -0,0 +1,52 @@
+//! 設定したエンジンの起動と監視.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use voice_vox_api::supervisor::{EngineConfig, EngineStatus, EngineSupervisor, StatusWatcher};
+
+use crate::Message;
+
+/// 起動するエンジン.
+///
+/// `executable`がなければエンジンを起動せずに`localhost:50021`の起動済みのエンジンに接続する.
+#[derive(Default, Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct EngineLaunch {
+    pub executable: Option<PathBuf>,
+    pub port: Option<u16>,
+    pub args: Vec<String>,
+}
+
+/// 設定されたエンジンを起動する.
+///
+/// 起動できなかった場合はタイトルに付ける理由を返す.
+pub(crate) fn launch(launch: &EngineLaunch) -> Result<Option<EngineSupervisor>, String> {
+    let Some(executable) = &launch.executable else {
+        return Ok(None);
+    };
+    let mut config = EngineConfig::new(executable).args(&launch.args);
+    if let Some(port) = launch.port {
+        config = config.port(port);
+    }
+    EngineSupervisor::start(config)
+        .map(Some)
+        .map_err(|e| format!("エンジンを起動できませんでした: {e}"))
+}
+
+/// エンジンの状態が変わるたびに`Message::EngineStatus`を送る.
+pub(crate) fn status_subscription(watcher: StatusWatcher) -> iced::Subscription<Message> {
+    iced::subscription::unfold("engine-status", watcher, |mut watcher| async move {
+        let status = watcher.changed().await;
+        (Some(Message::EngineStatus(status)), watcher)
+    })
+}
+
+/// タイトルに付ける状態.準備ができている場合は何も付けない.
+pub(crate) fn status_label(status: &EngineStatus) -> Option<String> {
+    match status {
+        EngineStatus::Ready(_) => None,
+        EngineStatus::Stopped => Some("エンジン停止".to_owned()),
+        EngineStatus::Starting => Some("エンジン起動中".to_owned()),
+        EngineStatus::Crashed { reason, .. } => Some(format!("エンジンエラー: {reason}")),
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::BTreeMap;
+use voice_vox_api::api_schema::{AudioQueryInProject, SpeakerUuid, StyleId};
 
 use iced::{
     widget::{Column, Text},
@@ -15,8 +16,8 @@ pub enum Diff {
     },
     CharacterChange {
         audio_item_key: String,
-        before: i32,
-        after: i32,
+        before: StyleId,
+        after: StyleId,
     },
     Pitch {
         audio_item_key: String,
@@ -48,6 +49,12 @@ pub enum Diff {
         before: f64,
         after: f64,
     },
+    /// テキストから作り直したクエリ.
+    Query {
+        audio_item_key: String,
+        before: Option<Box<AudioQueryInProject>>,
+        after: Option<Box<AudioQueryInProject>>,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -155,6 +162,15 @@ impl History {
                         ai.styleId = before;
                     }
                 }
+                Diff::Query {
+                    audio_item_key,
+                    before,
+                    after: _,
+                } => {
+                    if let Some(ai) = tab_context.project.audioItems.get_mut(&audio_item_key) {
+                        ai.query = before.map(|query| *query);
+                    }
+                }
             }
 
             self.depth += 1;
@@ -249,6 +265,15 @@ impl History {
                         ai.styleId = after;
                     }
                 }
+                Diff::Query {
+                    audio_item_key,
+                    before: _,
+                    after,
+                } => {
+                    if let Some(ai) = tab_context.project.audioItems.get_mut(&audio_item_key) {
+                        ai.query = after.map(|query| *query);
+                    }
+                }
             }
             self.depth -= 1;
         }
@@ -353,6 +378,19 @@ impl History {
                 self.undo_stack.push(diff);
                 return;
             }
+            Diff::Query {
+                audio_item_key,
+                before,
+                after,
+            } => {
+                if let Some(ai) = tab_context.project.audioItems.get_mut(audio_item_key) {
+                    *before = ai.query.take().map(Box::new);
+                    ai.query = after.as_deref().cloned();
+                }
+                self.depth = 0;
+                self.undo_stack.push(diff);
+                return;
+            }
         }
         self.depth = 0;
         self.unsquashed_buffer.push(diff);
@@ -525,8 +563,11 @@ impl History {
     }
     pub(crate) fn build_view(
         &self,
-        portrait_and_names: &BTreeMap<String, (iced::widget::image::Handle, String, Vec<i32>)>,
-        style_id_uuid_table: &BTreeMap<i32, (String, String, iced::widget::image::Handle)>,
+        portrait_and_names: &BTreeMap<
+            SpeakerUuid,
+            (iced::widget::image::Handle, String, Vec<StyleId>),
+        >,
+        style_id_uuid_table: &BTreeMap<StyleId, (SpeakerUuid, String, iced::widget::image::Handle)>,
     ) -> Column<crate::Message, Renderer> {
         let build_text = |diff: &Diff, depth: usize, id: usize| {
             if id == 0 {
@@ -625,6 +666,9 @@ impl History {
                             style_name_after
                         )
                     }
+                    Diff::Query { .. } => {
+                        format!("{} クエリ作成", if depth == id { "*" } else { "" })
+                    }
                 })
             }
         };
@@ -1,4 +1,6 @@
 mod character_change_button;
+#[cfg(not(target_arch = "wasm32"))]
+mod engine;
 mod history;
 mod main_page;
 mod project;
@@ -18,15 +20,115 @@ use project::VoiceVoxProject;
 use serde::{Deserialize, Serialize};
 
 use toolbar::{build_configure_ui, ConfigureMessage, ToolBarConfig, ToolBarKind};
-use voice_vox_api::api::{APIError, MorpableTargets, SpeakerInfo};
+use voice_vox_api::api::{APIError, AudioQuery, MorpableTargets, SpeakerInfo, VoiceVoxClient};
+use voice_vox_api::api_schema::{SpeakerUuid, StyleId};
+use voice_vox_api::batch::{BatchJob, BatchSynthesis};
+use voice_vox_api::cancel::CancellationToken;
+use voice_vox_api::client::DEFAULT_BASE_URL;
+use voice_vox_api::retry::RetryPolicy;
+#[cfg(not(target_arch = "wasm32"))]
+use voice_vox_api::speaker_cache::SpeakerCache;
+#[cfg(not(target_arch = "wasm32"))]
+use voice_vox_api::synthesis_cache::SynthesisCache;
 
 fn main() -> iced::Result {
     VoiceVox::run(Settings {
         default_font: Some(include_bytes!("../font/NotoSansCJKjp-Regular.otf")),
+        // 起動したエンジンを終了してから閉じる.
+        exit_on_close_request: false,
         ..Default::default()
     })
 }
-static SERVER: once_cell::sync::OnceCell<&str> = once_cell::sync::OnceCell::new();
+static CLIENT: once_cell::sync::OnceCell<VoiceVoxClient> = once_cell::sync::OnceCell::new();
+
+/// 書き出しで同時にエンジンに送る行の数.
+const EXPORT_CONCURRENCY: usize = 2;
+
+/// メモリに保存する合成した音声の上限.
+#[cfg(not(target_arch = "wasm32"))]
+const SYNTHESIS_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+/// ディスクに保存する合成した音声の上限.
+#[cfg(not(target_arch = "wasm32"))]
+const SYNTHESIS_DISK_LIMIT: u64 = 512 * 1024 * 1024;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn cache_dir(name: &str) -> std::path::PathBuf {
+    if let Some(project_dirs) = directories_next::ProjectDirs::from("rs", "Iced", "Voiced") {
+        project_dirs.cache_dir().join(name)
+    } else {
+        std::env::temp_dir().join(format!("voiced_{name}"))
+    }
+}
+
+/// 接続しているエンジンのバージョンの話者のキャッシュを開く.
+#[cfg(not(target_arch = "wasm32"))]
+async fn open_speaker_cache() -> Result<SpeakerCache, APIError> {
+    SpeakerCache::open(cache_dir("speakers"), CLIENT.get().unwrap()).await
+}
+
+/// 変わっていない行を合成し直さないためのキャッシュ.
+///
+/// キーにエンジンを含むので,エンジンを起動し直しても使える.
+#[cfg(not(target_arch = "wasm32"))]
+fn open_synthesis_cache() -> SynthesisCache {
+    SynthesisCache::new(SYNTHESIS_MEMORY_LIMIT)
+        .with_disk(cache_dir("synthesis"), SYNTHESIS_DISK_LIMIT)
+        .unwrap_or_else(|_| SynthesisCache::new(SYNTHESIS_MEMORY_LIMIT))
+}
+
+/// エンジンのバージョンが変わっていなければ話者をディスクのキャッシュから取得する.
+#[cfg(not(target_arch = "wasm32"))]
+async fn load_speakers(
+    cache: Option<SpeakerCache>,
+) -> Result<Vec<voice_vox_api::api_schema::Speaker>, APIError> {
+    let client = CLIENT.get().unwrap();
+    match cache {
+        Some(cache) => cache.speakers(client).await,
+        None => {
+            voice_vox_api::api::Speakers { core_version: None }
+                .call(client)
+                .await
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn load_speaker_info(
+    cache: Option<SpeakerCache>,
+    speaker_uuid: SpeakerUuid,
+) -> Result<voice_vox_api::api_schema::SpeakerInfo, APIError> {
+    let client = CLIENT.get().unwrap();
+    match cache {
+        Some(cache) => cache.speaker_info(client, &speaker_uuid).await,
+        None => {
+            SpeakerInfo {
+                speaker_uuid,
+                core_version: None,
+            }
+            .call(client)
+            .await
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn load_speakers() -> Result<Vec<voice_vox_api::api_schema::Speaker>, APIError> {
+    voice_vox_api::api::Speakers { core_version: None }
+        .call(CLIENT.get().unwrap())
+        .await
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn load_speaker_info(
+    speaker_uuid: SpeakerUuid,
+) -> Result<voice_vox_api::api_schema::SpeakerInfo, APIError> {
+    SpeakerInfo {
+        speaker_uuid,
+        core_version: None,
+    }
+    .call(CLIENT.get().unwrap())
+    .await
+}
 
 #[derive(Debug, Clone)]
 pub(crate) enum Message {
@@ -51,10 +153,21 @@ pub(crate) enum Message {
     QueryParameterCommit,
     APICall(APICall),
     APIResult(APIResult),
-    CharacterChange(String, i32),
+    CharacterChange(String, StyleId),
     FileLoadError,
     NewTab(TabContext),
     NewAudioCell,
+    #[cfg(not(target_arch = "wasm32"))]
+    EngineStatus(voice_vox_api::supervisor::EngineStatus),
+    CloseRequested,
+    /// 書き出した音声を保存した.
+    Exported(Result<(), SaveError>),
+    /// 接続しているエンジンの話者のキャッシュを開いた.
+    #[cfg(not(target_arch = "wasm32"))]
+    SpeakerCacheOpened(Result<SpeakerCache, APIError>),
+    /// エンジンが終了したので閉じる.
+    #[cfg(not(target_arch = "wasm32"))]
+    EngineStopped,
 }
 #[derive(Debug, Clone)]
 pub(crate) enum APIResult {
@@ -64,9 +177,15 @@ pub(crate) enum APIResult {
         Result<voice_vox_api::api_schema::SpeakerInfo, APIError>,
     ),
     MorpableTargets(
-        i32,
-        Result<Vec<HashMap<i32, voice_vox_api::api_schema::MorphableTargetInfo>>, APIError>,
+        StyleId,
+        Result<Vec<HashMap<StyleId, voice_vox_api::api_schema::MorphableTargetInfo>>, APIError>,
     ),
+    AudioQuery(
+        String,
+        Result<voice_vox_api::api_schema::AudioQuery, APIError>,
+    ),
+    /// 書き出し先のディレクトリと,行の順番に並べた合成の結果.
+    ExportAll(std::path::PathBuf, Vec<Result<Vec<u8>, APIError>>),
 }
 
 #[derive(Debug, Clone)]
@@ -76,12 +195,19 @@ pub(crate) enum APICall {
         voice_vox_api::api_schema::Speaker,
         voice_vox_api::api::SpeakerInfo,
     ),
+    /// テキストから行のクエリを作る.
+    AudioQuery(String),
+    /// 表示しているタブの全ての行を合成してディレクトリに書き出す.
+    ExportAll(Option<std::path::PathBuf>),
 }
 #[derive(Default, Serialize, Deserialize, Debug, Clone)]
 struct VoiceVoxState {
     tool_bar_config: ToolBarConfig,
     viewing_tab: Option<usize>,
     tabs: Vec<TabContext>,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(default)]
+    engine: engine::EngineLaunch,
 }
 
 enum VoiceVox {
@@ -104,10 +230,23 @@ impl Application for VoiceVox {
         match self {
             VoiceVox::Loading => "Loading".to_owned(),
             VoiceVox::Loaded(state) => {
-                format!(
+                let mut title = format!(
                     "Voiced - {}",
                     state.persistence.get_tab_file_name().unwrap_or_default()
-                )
+                );
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(label) = state
+                    .engine
+                    .as_ref()
+                    .and_then(|engine| engine::status_label(&engine.status()))
+                    .or_else(|| state.engine_error.clone())
+                {
+                    title = format!("{title} [{label}]");
+                }
+                if let Some(status) = &state.synthesis_status {
+                    title = format!("{title} ({status})");
+                }
+                title
             }
         }
     }
@@ -115,7 +254,30 @@ impl Application for VoiceVox {
     fn update(&mut self, message: Self::Message) -> Command<Message> {
         match self {
             Self::Loading => {
-                SERVER.set("localhost:50021").unwrap();
+                let persistence = match message {
+                    Message::Loaded(Ok(state)) => state,
+                    Message::Loaded(Err(_)) => VoiceVoxState::default(),
+                    Message::CloseRequested => return iced::window::close(),
+                    _ => return Command::none(),
+                };
+                #[cfg(not(target_arch = "wasm32"))]
+                let (engine, engine_error) = match engine::launch(&persistence.engine) {
+                    Ok(engine) => (engine, None),
+                    Err(label) => (None, Some(label)),
+                };
+                #[cfg(not(target_arch = "wasm32"))]
+                let base_url = engine.as_ref().map_or_else(
+                    || DEFAULT_BASE_URL.to_owned(),
+                    voice_vox_api::supervisor::EngineSupervisor::base_url,
+                );
+                #[cfg(target_arch = "wasm32")]
+                let base_url = DEFAULT_BASE_URL.to_owned();
+                // 起動直後のエンジンはモデルの読み込み中に失敗することがあるので再試行する.
+                let client = VoiceVoxClient::builder(&base_url)
+                    .retry(RetryPolicy::new(3))
+                    .build()
+                    .unwrap();
+                CLIENT.set(client).unwrap();
                 // initialize pane
                 // character and other split.
                 let configure = pane_grid::Configuration::Split {
@@ -139,51 +301,48 @@ impl Application for VoiceVox {
                         b: Box::new(pane_grid::Configuration::Pane(InTabPane::History)),
                     }),
                 };
-                match message {
-                    Message::Loaded(Ok(state)) => {
-                        let buffer_count = state.tabs.len();
-                        *self = Self::Loaded(State {
-                            dirty: false,
-                            saving: false,
-                            persistence: state,
-                            opening_page: Page::Main,
-                            configure_ui_selected_tool: ToolBarKind::default(),
-                            toolbar_ui_temp_config: ToolBarConfig::default(),
-
-                            tab_state: PaneGridState::with_configuration(configure),
-                            portrait_and_names: BTreeMap::new(),
-                            style_id_uuid_table: BTreeMap::new(),
-                            tracking_buffer: vec![History::new(); buffer_count],
-                            character_change_menu: vec![],
-                            prev_style_id_table_len: 0,
-                            morphable_targets: BTreeMap::new(),
-                        });
-                    }
-                    Message::Loaded(Err(_)) => {
-                        *self = Self::Loaded(State {
-                            dirty: false,
-                            saving: false,
-                            persistence: VoiceVoxState::default(),
-                            opening_page: Page::Main,
-                            configure_ui_selected_tool: ToolBarKind::default(),
-                            toolbar_ui_temp_config: ToolBarConfig::default(),
-
-                            tab_state: PaneGridState::with_configuration(configure),
-                            portrait_and_names: BTreeMap::new(),
-                            style_id_uuid_table: BTreeMap::new(),
-                            tracking_buffer: Vec::new(),
-                            character_change_menu: vec![],
-                            prev_style_id_table_len: 0,
-                            morphable_targets: BTreeMap::new(),
-                        });
-                    }
-                    _ => {}
+                // 起動したエンジンからは準備ができてから話者を取得する.
+                #[cfg(not(target_arch = "wasm32"))]
+                let supervised = engine.is_some();
+                #[cfg(target_arch = "wasm32")]
+                let supervised = false;
+                let buffer_count = persistence.tabs.len();
+                *self = Self::Loaded(State {
+                    dirty: false,
+                    saving: false,
+                    persistence,
+                    opening_page: Page::Main,
+                    configure_ui_selected_tool: ToolBarKind::default(),
+                    toolbar_ui_temp_config: ToolBarConfig::default(),
+
+                    tab_state: PaneGridState::with_configuration(configure),
+                    portrait_and_names: BTreeMap::new(),
+                    style_id_uuid_table: BTreeMap::new(),
+                    tracking_buffer: vec![History::new(); buffer_count],
+                    character_change_menu: vec![],
+                    prev_style_id_table_len: 0,
+                    morphable_targets: BTreeMap::new(),
+                    synthesis_token: CancellationToken::new(),
+                    synthesis_status: None,
+                    #[cfg(not(target_arch = "wasm32"))]
+                    synthesis_cache: std::sync::Arc::new(open_synthesis_cache()),
+                    #[cfg(not(target_arch = "wasm32"))]
+                    engine,
+                    #[cfg(not(target_arch = "wasm32"))]
+                    engine_error,
+                    #[cfg(not(target_arch = "wasm32"))]
+                    speaker_cache: None,
+                });
+                if supervised {
+                    return Command::none();
                 }
                 // collect informations from engine.s
-                Command::perform(
-                    voice_vox_api::api::Speakers { core_version: None }.call("localhost:50021"),
-                    |res| Message::APIResult(APIResult::Speakers(res)),
-                )
+                #[cfg(not(target_arch = "wasm32"))]
+                return Command::perform(open_speaker_cache(), Message::SpeakerCacheOpened);
+                #[cfg(target_arch = "wasm32")]
+                Command::perform(load_speakers(), |res| {
+                    Message::APIResult(APIResult::Speakers(res))
+                })
             }
             Self::Loaded(state) => {
                 let mut saved = false;
@@ -243,7 +402,45 @@ impl Application for VoiceVox {
                             },
                         )),
                     },
-                    Message::EngineMenuOpen(_) => {}
+                    Message::EngineMenuOpen(EngineMenu::Reboot) => {
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if let Some(engine) = &state.engine {
+                            engine.restart();
+                        }
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    Message::EngineStatus(status) => {
+                        // 起動し直したエンジンの話者を,そのバージョンのキャッシュから取得し直す.
+                        if let voice_vox_api::supervisor::EngineStatus::Ready(_) = status {
+                            state.speaker_cache = None;
+                            cmd_buff.push(Command::perform(
+                                open_speaker_cache(),
+                                Message::SpeakerCacheOpened,
+                            ));
+                        }
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    Message::SpeakerCacheOpened(cache) => {
+                        // 開けない場合はキャッシュを使わずに取得する.
+                        state.speaker_cache = cache.ok();
+                        cmd_buff.push(Command::perform(
+                            load_speakers(state.speaker_cache.clone()),
+                            |res| Message::APIResult(APIResult::Speakers(res)),
+                        ));
+                    }
+                    Message::CloseRequested => {
+                        // エンジンを終了してから閉じる.終了はUIのスレッドで待たない.
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if let Some(engine) = state.engine.take() {
+                            return Command::perform(
+                                async_std::task::spawn_blocking(move || engine.shutdown()),
+                                |()| Message::EngineStopped,
+                            );
+                        }
+                        return iced::window::close();
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    Message::EngineStopped => return iced::window::close(),
                     Message::SettingsMenuOpen(settings_menu) => match settings_menu {
                         SettingsMenu::KeyConfig => todo!(),
                         SettingsMenu::ToolbarConfig => {
@@ -259,9 +456,20 @@ impl Application for VoiceVox {
                     Message::HelpMenuOpen => {}
                     Message::ToolBar(tbk) => match tbk {
                         ToolBarKind::ContinuosPlay => todo!(),
-                        ToolBarKind::Stop => todo!(),
+                        ToolBarKind::Stop => {
+                            state.synthesis_token.cancel();
+                            state.synthesis_token = CancellationToken::new();
+                        }
                         ToolBarKind::ExportSelected => todo!(),
-                        ToolBarKind::ExportAll => todo!(),
+                        ToolBarKind::ExportAll => cmd_buff.push(Command::perform(
+                            async {
+                                rfd::AsyncFileDialog::new()
+                                    .pick_folder()
+                                    .await
+                                    .map(|dir| dir.path().to_owned())
+                            },
+                            |dir| Message::APICall(APICall::ExportAll(dir)),
+                        )),
                         ToolBarKind::ConnectExport => todo!(),
                         ToolBarKind::SaveProject => todo!(),
                         ToolBarKind::Undo => {
@@ -369,25 +577,85 @@ impl Application for VoiceVox {
                             }
                         }
                     }
-                    Message::APICall(_request) => {}
+                    Message::APICall(request) => match request {
+                        APICall::Speakers(_) | APICall::SpeakerInfo(..) => {}
+                        APICall::AudioQuery(key) => {
+                            if let Some(vt) = state.persistence.viewing_tab {
+                                state.tracking_buffer[vt].commit();
+                            }
+                            let request = state.audio_item(&key).map(|item| AudioQuery {
+                                text: item.text.clone(),
+                                speaker: item.styleId,
+                                core_version: None,
+                            });
+                            if let Some(request) = request {
+                                let token = state.synthesis_token.clone();
+                                state.synthesis_status = None;
+                                cmd_buff.push(Command::perform(
+                                    async move {
+                                        CLIENT
+                                            .get()
+                                            .unwrap()
+                                            .send_cancellable(request, &token)
+                                            .await
+                                    },
+                                    move |query| {
+                                        Message::APIResult(APIResult::AudioQuery(key, query))
+                                    },
+                                ));
+                            }
+                        }
+                        APICall::ExportAll(None) => {}
+                        APICall::ExportAll(Some(dir)) => {
+                            if let Some(tab_ctx) = state
+                                .persistence
+                                .viewing_tab
+                                .and_then(|tab_number| state.persistence.tabs.get(tab_number))
+                            {
+                                state.synthesis_status = None;
+                                let project = &tab_ctx.project;
+                                // クエリを作っていない行は書き出すときに作る.
+                                let jobs = project
+                                    .audioKeys
+                                    .iter()
+                                    .filter_map(|key| project.audioItems.get(key))
+                                    .map(|item| match &item.query {
+                                        Some(query) => {
+                                            BatchJob::query(query.clone().into(), item.styleId)
+                                        }
+                                        None => BatchJob::text(item.text.clone(), item.styleId),
+                                    })
+                                    .collect();
+                                let batch = BatchSynthesis::new(jobs)
+                                    .concurrency(EXPORT_CONCURRENCY)
+                                    .cancel_with(state.synthesis_token.clone());
+                                #[cfg(not(target_arch = "wasm32"))]
+                                let batch = batch.cache(state.synthesis_cache.clone());
+                                cmd_buff.push(Command::perform(
+                                    batch.run(CLIENT.get().unwrap(), |_| {}),
+                                    move |wavs| Message::APIResult(APIResult::ExportAll(dir, wavs)),
+                                ));
+                            }
+                        }
+                    },
                     Message::APIResult(result) => match result {
                         APIResult::Speakers(speakers) => {
                             println!("{speakers:?}");
 
                             if let Ok(speakers) = speakers {
                                 for speaker in speakers {
-                                    cmd_buff.push(Command::perform(
-                                        SpeakerInfo {
-                                            speaker_uuid: speaker.speaker_uuid.clone(),
-                                            core_version: None,
-                                        }
-                                        .call(SERVER.get().unwrap()),
-                                        |response| {
-                                            Message::APIResult(APIResult::SpeakerInfo(
-                                                speaker, response,
-                                            ))
-                                        },
-                                    ));
+                                    #[cfg(not(target_arch = "wasm32"))]
+                                    let info = load_speaker_info(
+                                        state.speaker_cache.clone(),
+                                        speaker.speaker_uuid.clone(),
+                                    );
+                                    #[cfg(target_arch = "wasm32")]
+                                    let info = load_speaker_info(speaker.speaker_uuid.clone());
+                                    cmd_buff.push(Command::perform(info, |response| {
+                                        Message::APIResult(APIResult::SpeakerInfo(
+                                            speaker, response,
+                                        ))
+                                    }));
                                 }
                             }
                         }
@@ -409,7 +677,7 @@ impl Application for VoiceVox {
                                                 style_id: vec![style_id],
                                                 core_version: None,
                                             }
-                                            .call(SERVER.get().unwrap()),
+                                            .call(CLIENT.get().unwrap()),
                                             move |response| {
                                                 Message::APIResult(APIResult::MorpableTargets(
                                                     style_id, response,
@@ -446,6 +714,45 @@ impl Application for VoiceVox {
                                     ));
                             }
                         }
+                        APIResult::AudioQuery(key, query) => match query {
+                            // 元に戻したときにテキストとクエリが食い違わないように履歴に残す.
+                            Ok(query) => {
+                                if let Some(tab_number) =
+                                    state.persistence.tabs.iter().position(|tab_ctx| {
+                                        tab_ctx.project.audioItems.contains_key(&key)
+                                    })
+                                {
+                                    state.tracking_buffer[tab_number].apply(
+                                        Diff::Query {
+                                            audio_item_key: key,
+                                            before: None,
+                                            after: Some(Box::new(query.into())),
+                                        },
+                                        &mut state.persistence.tabs[tab_number],
+                                    );
+                                }
+                            }
+                            Err(e) => state.synthesis_status = Some(failure_label(&e)),
+                        },
+                        APIResult::ExportAll(dir, wavs) => {
+                            for (index, wav) in wavs.into_iter().enumerate() {
+                                match wav {
+                                    Ok(wav) => {
+                                        let path = dir.join(format!("{:03}.wav", index + 1));
+                                        cmd_buff.push(Command::perform(
+                                            async_std::fs::write(path, wav),
+                                            |x| Message::Exported(x.map_err(|_| SaveError::Write)),
+                                        ))
+                                    }
+                                    // 失敗した行は番号を空けたままにして,最初の原因を表示する.
+                                    Err(e) => {
+                                        if state.synthesis_status.is_none() {
+                                            state.synthesis_status = Some(failure_label(&e));
+                                        }
+                                    }
+                                }
+                            }
+                        }
                         APIResult::MorpableTargets(style_id, morphable_targets) => {
                             if let Ok(mut morphable_targets) = morphable_targets {
                                 state.morphable_targets.insert(
@@ -554,7 +861,7 @@ impl Application for VoiceVox {
                                 state.tracking_buffer[vt].apply(
                                     Diff::CharacterChange {
                                         audio_item_key,
-                                        before: 0,
+                                        before: StyleId(0),
                                         after,
                                     },
                                     tab_ctx,
@@ -563,6 +870,11 @@ impl Application for VoiceVox {
                         }
                     }
                     Message::FileLoadError => {}
+                    Message::Exported(result) => {
+                        if result.is_err() {
+                            state.synthesis_status = Some("書き出しに失敗しました".to_owned());
+                        }
+                    }
                     Message::NewTab(tab_ctx) => {
                         state.persistence.tabs.push(tab_ctx);
                         state.tracking_buffer.push(History::new());
@@ -596,6 +908,24 @@ impl Application for VoiceVox {
         }
     }
 
+    fn subscription(&self) -> iced::Subscription<Message> {
+        let close = iced::subscription::events_with(|event, _| match event {
+            iced::Event::Window(iced::window::Event::CloseRequested) => {
+                Some(Message::CloseRequested)
+            }
+            _ => None,
+        });
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Self::Loaded(State {
+            engine: Some(engine),
+            ..
+        }) = self
+        {
+            return iced::Subscription::batch([close, engine::status_subscription(engine.watch())]);
+        }
+        close
+    }
+
     fn view(&self) -> Element<'_, Self::Message> {
         if let Self::Loaded(state) = self {
             let vbox = Column::new();
@@ -693,19 +1023,54 @@ struct State {
     tracking_buffer: Vec<History>,
     tab_state: PaneGridState<InTabPane>,
     /// UUID -> (Portrait,Name,StyleIDs)
-    portrait_and_names: BTreeMap<String, (iced::widget::image::Handle, String, Vec<i32>)>,
+    portrait_and_names: BTreeMap<SpeakerUuid, (iced::widget::image::Handle, String, Vec<StyleId>)>,
     /// StyleID -> (UUID,StyleName,Icon)
-    style_id_uuid_table: BTreeMap<i32, (String, String, iced::widget::image::Handle)>,
+    style_id_uuid_table: BTreeMap<StyleId, (SpeakerUuid, String, iced::widget::image::Handle)>,
     /// (Name,stylemenu (icon ,stylename,styleId))
     character_change_menu: OptionsOwned,
     prev_style_id_table_len: usize,
-    morphable_targets: BTreeMap<i32, BTreeSet<i32>>,
+    morphable_targets: BTreeMap<StyleId, BTreeSet<StyleId>>,
+    /// 停止ボタンで実行中の合成を中断する.
+    synthesis_token: CancellationToken,
+    /// 最後のクエリの作成や合成が終わらなかった理由. タイトルに表示する.
+    synthesis_status: Option<String>,
+    /// 書き出した行の合成した音声.
+    #[cfg(not(target_arch = "wasm32"))]
+    synthesis_cache: std::sync::Arc<SynthesisCache>,
+    /// 接続しているエンジンの話者のキャッシュ. エンジンの準備ができるたびに開き直す.
+    #[cfg(not(target_arch = "wasm32"))]
+    speaker_cache: Option<SpeakerCache>,
+    /// このアプリが起動して監視しているエンジン.
+    #[cfg(not(target_arch = "wasm32"))]
+    engine: Option<voice_vox_api::supervisor::EngineSupervisor>,
+    /// 設定したエンジンを起動できなかった理由. タイトルに表示する.
+    #[cfg(not(target_arch = "wasm32"))]
+    engine_error: Option<String>,
 }
-pub(crate) type OptionsOwned = Vec<(String, Vec<(iced::widget::image::Handle, String, i32)>)>;
-pub(crate) type OptionsRef<'a> = &'a [(String, Vec<(iced::widget::image::Handle, String, i32)>)];
+impl State {
+    /// 開いているいずれかのタブの行. キーはUUIDなのでタブの間で重ならない.
+    fn audio_item(&self, key: &str) -> Option<&project::AudioItem> {
+        self.persistence
+            .tabs
+            .iter()
+            .find_map(|tab_ctx| tab_ctx.project.audioItems.get(key))
+    }
+}
+
+/// 合成が終わらなかった理由. 停止ボタンで中断した場合はエラーとして扱わない.
+fn failure_label(error: &APIError) -> String {
+    match error {
+        APIError::Cancelled => "停止しました".to_owned(),
+        e => format!("合成に失敗しました: {e}"),
+    }
+}
+
+pub(crate) type OptionsOwned = Vec<(String, Vec<(iced::widget::image::Handle, String, StyleId)>)>;
+pub(crate) type OptionsRef<'a> =
+    &'a [(String, Vec<(iced::widget::image::Handle, String, StyleId)>)];
 fn build_character_change_menu(
-    portrait_and_names: &BTreeMap<String, (iced::widget::image::Handle, String, Vec<i32>)>,
-    style_id_uuid_table: &BTreeMap<i32, (String, String, iced::widget::image::Handle)>,
+    portrait_and_names: &BTreeMap<SpeakerUuid, (iced::widget::image::Handle, String, Vec<StyleId>)>,
+    style_id_uuid_table: &BTreeMap<StyleId, (SpeakerUuid, String, iced::widget::image::Handle)>,
 ) -> OptionsOwned {
     let mut menu = vec![];
     for (_, (_, name, style_ids)) in portrait_and_names.iter() {
@@ -8,16 +8,17 @@ use iced::{
 
 use iced_native::widget::pane_grid;
 use std::collections::BTreeMap;
+use voice_vox_api::api_schema::{SpeakerUuid, StyleId};
 
-use crate::{history::History, toolbar::ToolBarConfig, Message, TabContext};
+use crate::{history::History, toolbar::ToolBarConfig, APICall, Message, TabContext};
 pub(crate) fn build_ui<'a>(
     tool_bar: &'a ToolBarConfig,
 
     in_tab_state: &'a State<InTabPane>,
     active_tab: usize,
     tab_contexts: &'a [TabContext],
-    portraits: &BTreeMap<String, (iced::widget::image::Handle, String, Vec<i32>)>,
-    style_id_uuid_table: &BTreeMap<i32, (String, String, iced::widget::image::Handle)>,
+    portraits: &BTreeMap<SpeakerUuid, (iced::widget::image::Handle, String, Vec<StyleId>)>,
+    style_id_uuid_table: &BTreeMap<StyleId, (SpeakerUuid, String, iced::widget::image::Handle)>,
     histories: &'a [History],
     menu: crate::OptionsRef<'a>,
 ) -> Column<'a, Message, Renderer> {
@@ -57,7 +58,7 @@ pub(crate) fn build_ui<'a>(
                                 &tab_ctx.project.audioItems.get(key).unwrap().text,
                                 |txt| Message::EditText(key.clone(), txt),
                             )
-                            .on_submit(Message::QueryParameterCommit),
+                            .on_submit(Message::APICall(APICall::AudioQuery(key.clone()))),
                         );
                         column = column.push(line);
                     }
@@ -1,13 +1,13 @@
 use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
-use voice_vox_api::api_schema;
+use voice_vox_api::api_schema::{self, StyleId};
 
 #[allow(non_snake_case)]
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct AudioItem {
     pub text: String,
-    pub styleId: i32,
+    pub styleId: StyleId,
     pub query: Option<api_schema::AudioQueryInProject>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub presetKey: Option<String>,
@@ -24,7 +24,7 @@ impl VoiceVoxProject {
     pub fn add_audio_cell(&mut self) {
         let audio_item = AudioItem {
             text: String::new(),
-            styleId: 0,
+            styleId: StyleId(0),
             query: Some(voice_vox_api::api_schema::AudioQuery::default().into()),
             presetKey: None,
         };
@@ -37,7 +37,7 @@ impl Default for VoiceVoxProject {
     fn default() -> Self {
         let audio_item = AudioItem {
             text: String::new(),
-            styleId: 0,
+            styleId: StyleId(0),
             query: Some(voice_vox_api::api_schema::AudioQuery::default().into()),
             presetKey: None,
         };